@@ -4,6 +4,8 @@ import { useEffect, useCallback } from 'react';
 import { useParams } from 'next/navigation';
 import { useWasm, useStageManager } from '@/hooks';
 import { PacketCanvas } from '@/components/PacketCanvas';
+import { formatSimEvents } from '@/lib/simEvents';
+import type { SimEvent } from '@/types/stage';
 
 // =============================================================================
 // Job Detail Page - /jobs/[id]
@@ -15,6 +17,18 @@ export default function JobDetailPage() {
   const jobId = params.id as string;
 
   const { isLoaded, isGpuReady, error: wasmError, wasm, initGpu } = useWasm();
+
+  // ログ追加用（PacketCanvas・シミュレーションイベントで使用）
+  const addLog = useCallback((source: string, message: string) => {
+    console.log(`[${source}] ${message}`);
+  }, []);
+
+  const handleEvents = useCallback((events: SimEvent[]) => {
+    for (const message of formatSimEvents(events)) {
+      addLog('Rust', message);
+    }
+  }, [addLog]);
+
   const {
     phase,
     stageConfig,
@@ -26,7 +40,7 @@ export default function JobDetailPage() {
     resumeSimulation,
     resetStage,
     showResult,
-  } = useStageManager(wasm);
+  } = useStageManager(wasm, handleEvents);
 
   // GPU初期化後にステージをロード
   useEffect(() => {
@@ -35,11 +49,6 @@ export default function JobDetailPage() {
     }
   }, [isGpuReady, jobId, phase, loadStage]);
 
-  // ログ追加用（PacketCanvasで使用）
-  const addLog = useCallback((source: string, message: string) => {
-    console.log(`[${source}] ${message}`);
-  }, []);
-
   const error = wasmError || stageError;
 
   return (
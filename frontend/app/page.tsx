@@ -133,8 +133,8 @@ export default function Home() {
         0,           // target_node_idx (Gateway)
         50,          // count
         1000,        // duration_ms
-        6.0,         // base_speed (increased for larger canvas)
-        1.5,         // speed_variance
+        0.36,        // base_speed (px/ms)
+        0.09,        // speed_variance (px/ms)
         0,           // packet_type (Normal)
         10           // complexity
       );
@@ -153,8 +153,8 @@ export default function Home() {
         1,           // target_node_idx (LB)
         100,         // count
         1000,        // duration_ms
-        6.0,         // base_speed (increased for larger canvas)
-        1.5,         // speed_variance
+        0.36,        // base_speed (px/ms)
+        0.09,        // speed_variance (px/ms)
         0,           // packet_type (Normal)
         10           // complexity
       );
@@ -168,11 +168,11 @@ export default function Home() {
     if (wasm && isGpuReady) {
       // LB位置から各サーバーにパケットを分散
       // Server 1 (上)
-      wasm.simulation_spawn_wave_to_node(550, 540, 2, 50, 500, 7.0, 2.0, 0, 10);
+      wasm.simulation_spawn_wave_to_node(550, 540, 2, 50, 500, 0.42, 0.12, 0, 10);
       // Server 2 (中央)
-      wasm.simulation_spawn_wave_to_node(550, 540, 3, 50, 500, 7.0, 2.0, 0, 10);
+      wasm.simulation_spawn_wave_to_node(550, 540, 3, 50, 500, 0.42, 0.12, 0, 10);
       // Server 3 (下)
-      wasm.simulation_spawn_wave_to_node(550, 540, 4, 50, 500, 7.0, 2.0, 0, 10);
+      wasm.simulation_spawn_wave_to_node(550, 540, 4, 50, 500, 0.42, 0.12, 0, 10);
       
       setIsSimulationRunning(true);
       addLog('JS', 'spawn_wave: 150 packets → Servers');
@@ -183,9 +183,9 @@ export default function Home() {
   const handleSpawnToDB = useCallback(() => {
     if (wasm && isGpuReady) {
       // 各サーバーからDBにパケットを送信
-      wasm.simulation_spawn_wave_to_node(1050, 270, 5, 30, 300, 6.0, 1.5, 0, 10);
-      wasm.simulation_spawn_wave_to_node(1050, 540, 5, 30, 300, 6.0, 1.5, 0, 10);
-      wasm.simulation_spawn_wave_to_node(1050, 810, 5, 30, 300, 6.0, 1.5, 0, 10);
+      wasm.simulation_spawn_wave_to_node(1050, 270, 5, 30, 300, 0.36, 0.09, 0, 10);
+      wasm.simulation_spawn_wave_to_node(1050, 540, 5, 30, 300, 0.36, 0.09, 0, 10);
+      wasm.simulation_spawn_wave_to_node(1050, 810, 5, 30, 300, 0.36, 0.09, 0, 10);
       
       setIsSimulationRunning(true);
       addLog('JS', 'spawn_wave: 90 packets → DB');
@@ -196,23 +196,23 @@ export default function Home() {
   const handleFullFlow = useCallback(() => {
     if (wasm && isGpuReady) {
       // Step 1: → LB
-      wasm.simulation_spawn_wave_to_node(-20, 540, 1, 200, 2000, 6.0, 1.5, 0, 10);
+      wasm.simulation_spawn_wave_to_node(-20, 540, 1, 200, 2000, 0.36, 0.09, 0, 10);
       
       // Step 2: LB → Servers（少し遅延を持たせて）
       setTimeout(() => {
         if (wasm) {
-          wasm.simulation_spawn_wave_to_node(550, 540, 2, 70, 1500, 7.0, 2.0, 0, 10);
-          wasm.simulation_spawn_wave_to_node(550, 540, 3, 70, 1500, 7.0, 2.0, 0, 10);
-          wasm.simulation_spawn_wave_to_node(550, 540, 4, 60, 1500, 7.0, 2.0, 0, 10);
+          wasm.simulation_spawn_wave_to_node(550, 540, 2, 70, 1500, 0.42, 0.12, 0, 10);
+          wasm.simulation_spawn_wave_to_node(550, 540, 3, 70, 1500, 0.42, 0.12, 0, 10);
+          wasm.simulation_spawn_wave_to_node(550, 540, 4, 60, 1500, 0.42, 0.12, 0, 10);
         }
       }, 1000);
       
       // Step 3: Servers → DB
       setTimeout(() => {
         if (wasm) {
-          wasm.simulation_spawn_wave_to_node(1050, 270, 5, 50, 1000, 6.0, 1.5, 0, 10);
-          wasm.simulation_spawn_wave_to_node(1050, 540, 5, 50, 1000, 6.0, 1.5, 0, 10);
-          wasm.simulation_spawn_wave_to_node(1050, 810, 5, 50, 1000, 6.0, 1.5, 0, 10);
+          wasm.simulation_spawn_wave_to_node(1050, 270, 5, 50, 1000, 0.36, 0.09, 0, 10);
+          wasm.simulation_spawn_wave_to_node(1050, 540, 5, 50, 1000, 0.36, 0.09, 0, 10);
+          wasm.simulation_spawn_wave_to_node(1050, 810, 5, 50, 1000, 0.36, 0.09, 0, 10);
         }
       }, 2200);
       
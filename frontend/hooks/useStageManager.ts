@@ -2,7 +2,8 @@
 
 import { useState, useCallback, useRef, useEffect } from 'react';
 import type { WasmModule } from './useWasm';
-import type { StageConfig } from '@/types/stage';
+import type { SimEvent, StageConfig, StageResult } from '@/types/stage';
+import { drainSimEvents } from '@/lib/simEvents';
 
 // =============================================================================
 // Stage Manager Hook - ステージライフサイクル管理
@@ -36,6 +37,8 @@ export interface UseStageManagerReturn {
   stageConfig: StageConfig | null;
   /** 現在の統計 */
   stats: SimulationStats;
+  /** ステージ結果（COMPLETED以降、Rust側で判定） */
+  result: StageResult | null;
   /** エラーメッセージ */
   error: string | null;
   
@@ -64,10 +67,17 @@ const initialStats: SimulationStats = {
   slaRate: 0,
 };
 
-export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn {
+/**
+ * @param onEvents 毎フレーム取り出したシミュレーションイベント（ログ表示用、省略時は捨てる）
+ */
+export function useStageManager(
+  wasm: WasmModule | null,
+  onEvents?: (events: SimEvent[]) => void,
+): UseStageManagerReturn {
   const [phase, setPhase] = useState<StagePhase>('IDLE');
   const [stageConfig, setStageConfig] = useState<StageConfig | null>(null);
   const [stats, setStats] = useState<SimulationStats>(initialStats);
+  const [result, setResult] = useState<StageResult | null>(null);
   const [error, setError] = useState<string | null>(null);
 
   const lastTimeRef = useRef<number>(0);
@@ -105,13 +115,15 @@ export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn
     const deltaMs = lastTimeRef.current ? currentTime - lastTimeRef.current : 16.67;
     lastTimeRef.current = currentTime;
 
-    // Rustのcurrent_timeを取得してWaveを発火
-    const simTime = wasm.simulation_get_current_time();
-    wasm.trigger_waves_until(Math.floor(simTime));
-
-    // シミュレーションを1フレーム進める
+    // シミュレーションを1フレーム進める（Waveは固定ステップごとにRust側で発火）
     wasm.simulation_tick(deltaMs);
 
+    // イベントを取り出す（溜めたままにしない）
+    const events = drainSimEvents(wasm);
+    if (events.length > 0) {
+      onEvents?.(events);
+    }
+
     // 描画
     wasm.render_simulation_frame();
 
@@ -122,13 +134,15 @@ export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn
     const pendingWaves = wasm.get_pending_wave_count();
     const activeCount = wasm.simulation_get_active_count();
     if (pendingWaves === 0 && activeCount === 0 && stats.spawned > 0) {
+      const resultJson = wasm.get_stage_result();
+      setResult(resultJson ? (JSON.parse(resultJson) as StageResult) : null);
       setPhase('COMPLETED');
       console.log('[useStageManager] Simulation completed');
       return; // ループ終了
     }
 
     animationFrameRef.current = requestAnimationFrame(simulationLoop);
-  }, [wasm, updateStats, stats.spawned]);
+  }, [wasm, updateStats, stats.spawned, onEvents]);
 
   /**
    * ステージをロード
@@ -163,6 +177,7 @@ export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn
       // シミュレーションをリセット
       wasm.simulation_reset();
       setStats(initialStats);
+      setResult(null);
 
       setPhase('BUILD');
       console.log('[useStageManager] Stage loaded:', config.meta.title);
@@ -188,6 +203,7 @@ export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn
     // 統計をリセット
     wasm.simulation_reset();
     setStats(initialStats);
+    setResult(null);
 
     setPhase('RUNNING');
     lastTimeRef.current = 0;
@@ -242,6 +258,7 @@ export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn
     
     // 統計をリセット
     setStats(initialStats);
+    setResult(null);
 
     setPhase('BUILD');
     console.log('[useStageManager] Stage reset');
@@ -269,6 +286,7 @@ export function useStageManager(wasm: WasmModule | null): UseStageManagerReturn
     phase,
     stageConfig,
     stats,
+    result,
     error,
     loadStage,
     startSimulation,
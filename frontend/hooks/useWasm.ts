@@ -44,11 +44,15 @@ export interface WasmModule {
   simulation_debug_spawn: (x: number, y: number, count: number) => void;
   simulation_tick: (deltaMs: number) => void;
   simulation_get_active_count: () => number;
+  simulation_get_packet_slot_count: () => number;
+  simulation_get_packet_positions_ptr: () => number;
+  simulation_get_packet_appearance_ptr: () => number;
   simulation_add_node: (id: number, x: number, y: number, nodeType: number) => void;
   simulation_clear_nodes: () => void;
   simulation_get_node_count: () => number;
   simulation_update_node_position: (id: number, x: number, y: number) => void;
   render_simulation_frame: () => void;
+  simulation_set_gpu_motion: (enabled: boolean) => boolean;
   // Stage Config API
   load_stage_config: (jsonStr: string) => boolean;
   get_stage_meta: () => string | undefined;
@@ -57,13 +61,45 @@ export interface WasmModule {
   trigger_waves_until: (currentTimeMs: number) => void;
   get_pending_wave_count: () => number;
   reset_stage_waves: () => void;
+  get_stage_result: () => string | undefined;
+  // Placement API（結果はJSON文字列: PlacementResponse）
+  place_node: (nodeType: string, grade: string, x: number, y: number) => string;
+  remove_node: (id: number) => string;
+  upgrade_node: (id: number, grade: string) => string;
+  get_stage_remaining_budget: () => number;
+  get_placed_nodes: () => string | undefined;
+  // Failure API（障害訓練）
+  simulation_crash_node: (id: number) => boolean;
+  simulation_degrade_node: (id: number) => boolean;
+  simulation_get_node_health_states: () => Uint32Array;
+  simulation_flush_cache: (id: number) => boolean;
   // Stats API
   simulation_get_stats_spawned: () => number;
   simulation_get_stats_processed: () => number;
   simulation_get_stats_dropped: () => number;
+  simulation_get_stats_filtered: () => number;
+  simulation_get_stats_rate_limited: () => number;
+  simulation_get_stats_timed_out: () => number;
+  simulation_get_stats_retried: () => number;
   simulation_get_current_time: () => number;
   simulation_reset: () => void;
   simulation_get_node_position: (index: number) => Float32Array;
+  // Node Metrics API（ノードごとの時系列メトリクス、リングバッファをゼロコピーで参照）
+  simulation_set_metrics_config: (intervalMs: number, capacity: number) => void;
+  simulation_get_metrics_interval: () => number;
+  simulation_get_metrics_capacity: () => number;
+  simulation_get_node_metrics_ptr: (index: number) => number;
+  simulation_get_node_metrics_head: (index: number) => number;
+  simulation_get_node_metrics_count: (index: number) => number;
+  // Event API（ドロップ原因・飽和・Wave・ステージ終了のイベント、JSON配列で取り出す）
+  simulation_drain_events: () => string | undefined;
+  // Snapshot API（シミュレーションとステージの保存・復元）
+  simulation_export_snapshot: () => Uint8Array | undefined;
+  simulation_export_snapshot_json: () => string | undefined;
+  simulation_import_snapshot: (data: Uint8Array) => boolean;
+  simulation_import_snapshot_json: (json: string) => boolean;
+  simulation_export_replay: () => string | undefined;
+  simulation_load_replay: (json: string) => boolean;
 }
 
 export interface UseWasmReturn {
@@ -113,11 +149,15 @@ export function useWasm(): UseWasmReturn {
           simulation_debug_spawn: wasmModule.simulation_debug_spawn,
           simulation_tick: wasmModule.simulation_tick,
           simulation_get_active_count: wasmModule.simulation_get_active_count,
+          simulation_get_packet_slot_count: wasmModule.simulation_get_packet_slot_count,
+          simulation_get_packet_positions_ptr: wasmModule.simulation_get_packet_positions_ptr,
+          simulation_get_packet_appearance_ptr: wasmModule.simulation_get_packet_appearance_ptr,
           simulation_add_node: wasmModule.simulation_add_node,
           simulation_clear_nodes: wasmModule.simulation_clear_nodes,
           simulation_get_node_count: wasmModule.simulation_get_node_count,
           simulation_update_node_position: wasmModule.simulation_update_node_position,
           render_simulation_frame: wasmModule.render_simulation_frame,
+          simulation_set_gpu_motion: wasmModule.simulation_set_gpu_motion,
           // Stage Config API
           load_stage_config: wasmModule.load_stage_config,
           get_stage_meta: wasmModule.get_stage_meta,
@@ -126,13 +166,44 @@ export function useWasm(): UseWasmReturn {
           trigger_waves_until: wasmModule.trigger_waves_until,
           get_pending_wave_count: wasmModule.get_pending_wave_count,
           reset_stage_waves: wasmModule.reset_stage_waves,
+          get_stage_result: wasmModule.get_stage_result,
+          // Placement API
+          place_node: wasmModule.place_node,
+          remove_node: wasmModule.remove_node,
+          upgrade_node: wasmModule.upgrade_node,
+          get_stage_remaining_budget: wasmModule.get_stage_remaining_budget,
+          get_placed_nodes: wasmModule.get_placed_nodes,
+          // Failure API
+          simulation_crash_node: wasmModule.simulation_crash_node,
+          simulation_degrade_node: wasmModule.simulation_degrade_node,
+          simulation_get_node_health_states: wasmModule.simulation_get_node_health_states,
+          simulation_flush_cache: wasmModule.simulation_flush_cache,
           // Stats API
           simulation_get_stats_spawned: wasmModule.simulation_get_stats_spawned,
           simulation_get_stats_processed: wasmModule.simulation_get_stats_processed,
           simulation_get_stats_dropped: wasmModule.simulation_get_stats_dropped,
+          simulation_get_stats_filtered: wasmModule.simulation_get_stats_filtered,
+          simulation_get_stats_rate_limited: wasmModule.simulation_get_stats_rate_limited,
+          simulation_get_stats_timed_out: wasmModule.simulation_get_stats_timed_out,
+          simulation_get_stats_retried: wasmModule.simulation_get_stats_retried,
           simulation_get_current_time: wasmModule.simulation_get_current_time,
           simulation_reset: wasmModule.simulation_reset,
           simulation_get_node_position: wasmModule.simulation_get_node_position,
+          simulation_set_metrics_config: wasmModule.simulation_set_metrics_config,
+          simulation_get_metrics_interval: wasmModule.simulation_get_metrics_interval,
+          simulation_get_metrics_capacity: wasmModule.simulation_get_metrics_capacity,
+          simulation_get_node_metrics_ptr: wasmModule.simulation_get_node_metrics_ptr,
+          simulation_get_node_metrics_head: wasmModule.simulation_get_node_metrics_head,
+          simulation_get_node_metrics_count: wasmModule.simulation_get_node_metrics_count,
+          // Event API
+          simulation_drain_events: wasmModule.simulation_drain_events,
+          // Snapshot API
+          simulation_export_snapshot: wasmModule.simulation_export_snapshot,
+          simulation_export_snapshot_json: wasmModule.simulation_export_snapshot_json,
+          simulation_import_snapshot: wasmModule.simulation_import_snapshot,
+          simulation_import_snapshot_json: wasmModule.simulation_import_snapshot_json,
+          simulation_export_replay: wasmModule.simulation_export_replay,
+          simulation_load_replay: wasmModule.simulation_load_replay,
         };
 
         // Pre-allocate packet buffer
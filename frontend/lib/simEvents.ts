@@ -0,0 +1,81 @@
+import type { WasmModule } from '@/hooks/useWasm';
+import type { DropReason, SimEvent } from '@/types/stage';
+
+// =============================================================================
+// Simulation Events - Rust側イベントの取り出しとログ用の整形
+// 毎フレーム取り出し、同じフレーム内のドロップは原因・ノードごとにまとめて1行にする
+// =============================================================================
+
+const dropReasonLabels: Record<DropReason, string> = {
+  queue_full: 'queue full',
+  queue_evicted: 'evicted by higher priority',
+  red_early_drop: 'RED early drop',
+  codel_drop: 'CoDel drop',
+  no_route: 'no route',
+  invalid_target: 'invalid target',
+  route_too_long: 'route too long',
+  firewall_overload: 'firewall overload',
+  node_down: 'node down',
+  node_removed: 'node removed',
+  link_loss: 'link loss',
+  link_removed: 'link removed',
+  no_packet_slot: 'no packet slot',
+  rate_limited: 'rate limited',
+  filtered: 'firewall',
+};
+
+/** 溜まっているイベントをすべて取り出す（Wasm未ロード・空なら空配列） */
+export function drainSimEvents(wasm: WasmModule | null): SimEvent[] {
+  const json = wasm?.simulation_drain_events();
+  return json ? (JSON.parse(json) as SimEvent[]) : [];
+}
+
+/** イベントをログ用の文字列にする（ドロップは原因・ノードごとに件数をまとめる） */
+export function formatSimEvents(events: SimEvent[]): string[] {
+  const messages: string[] = [];
+  const drops = new Map<string, { time_ms: number; node_id: number | null; reason: DropReason; count: number }>();
+
+  for (const event of events) {
+    const time = `${(event.time_ms / 1000).toFixed(1)}s`;
+    switch (event.kind) {
+      case 'packet_dropped': {
+        const key = `${event.reason}:${event.node_id ?? '-'}`;
+        const drop = drops.get(key);
+        if (drop) {
+          drop.count++;
+        } else {
+          drops.set(key, { time_ms: event.time_ms, node_id: event.node_id, reason: event.reason, count: 1 });
+        }
+        break;
+      }
+      case 'node_saturated':
+        messages.push(`[${time}] Node ${event.node_id} saturated`);
+        break;
+      case 'node_recovered':
+        messages.push(`[${time}] Node ${event.node_id} recovered`);
+        break;
+      case 'wave_started':
+        messages.push(`[${time}] Wave ${event.wave_id} started (${event.count} requests)`);
+        break;
+      case 'wave_finished':
+        messages.push(`[${time}] Wave ${event.wave_id} finished`);
+        break;
+      case 'stage_finished':
+        messages.push(`[${time}] Stage finished: ${event.passed ? 'PASSED' : 'FAILED'}`);
+        break;
+      case 'events_discarded':
+        messages.push(`[${time}] ${event.count} events discarded (not drained in time)`);
+        break;
+    }
+  }
+
+  for (const drop of drops.values()) {
+    const time = `${(drop.time_ms / 1000).toFixed(1)}s`;
+    const where = drop.node_id === null ? '' : ` at node ${drop.node_id}`;
+    const count = drop.count > 1 ? ` x${drop.count}` : '';
+    const verb = drop.reason === 'filtered' ? 'Blocked' : 'Dropped'; // Firewallの遮断は守れた側として表示
+    messages.push(`[${time}] ${verb}${count}${where}: ${dropReasonLabels[drop.reason]}`);
+  }
+
+  return messages;
+}
@@ -6,21 +6,58 @@
 export interface StageMeta {
   title: string;
   description: string;
-  budget: number;
+  budget: number; // プレイヤーが買うノードとオートスケーリングの課金に使える額（固定ノードは無料）
   sla_target: number;
 }
 
 /** 固定配置されるノード（Gateway等） */
 export interface FixedNode {
   id: string;
-  type: 'gateway' | 'lb' | 'server' | 'db';
+  type: 'gateway' | 'lb' | 'server' | 'db' | 'firewall' | 'cache' | 'rate_limiter';
+  grade?: string;
   x: number;
   y: number;
+  lb_strategy?:
+    | 'least_load'
+    | 'round_robin'
+    | 'weighted_round_robin'
+    | 'random'
+    | 'power_of_two_choices'
+    | 'least_connections'
+    | 'consistent_hash';
+}
+
+/** ノード間リンク（from -> to の向きにリクエストが流れる） */
+export interface LinkConfig {
+  from: string;
+  to: string;
+  latency_ms?: number; // 伝搬遅延（省略時は距離と移動速度から決まる）
+  bandwidth?: number; // 帯域（パケットサイズ/秒、省略時は無制限）
+  loss_rate?: number; // 損失率（0.0 - 1.0）
+}
+
+/** オートスケーリンググループ（リンクの from / to にidを書ける） */
+export interface AutoscalingGroupConfig {
+  id: string;
+  type?: 'server' | 'db' | 'lb' | 'firewall' | 'cache' | 'rate_limiter'; // 省略時は server
+  grade: string;
+  x: number;
+  y: number;
+  min_instances?: number;
+  max_instances?: number;
+  scale_out_load?: number;
+  scale_in_load?: number;
+  window_ms?: number;
+  cooldown_ms?: number;
+  boot_delay_ms?: number;
+  cost_per_sec?: number;
 }
 
 /** マップ設定 */
 export interface MapConfig {
   fixed_nodes: FixedNode[];
+  links?: LinkConfig[];
+  autoscaling_groups?: AutoscalingGroupConfig[];
 }
 
 /** パケット出現パターン（Wave） */
@@ -30,7 +67,19 @@ export interface WaveConfig {
   count: number;
   duration_ms: number;
   packet_type: 'NORMAL' | 'SYN_FLOOD' | 'HEAVY_TASK' | 'KILLER';
-  speed: number;
+  speed: number; // 移動速度（ピクセル/ミリ秒）
+  complexity?: number; // 処理の重さ（10 = 通常、省略時はHEAVY_TASKのみ50）
+  client_id?: number; // すべて同じクライアントから送る（省略時はクライアントを順番に割り当て）
+}
+
+/** クライアントのタイムアウト・リトライ（省略時はタイムアウトなし） */
+export interface ClientConfig {
+  timeout_ms?: number;
+  max_retries?: number;
+  backoff_base_ms?: number;
+  backoff_multiplier?: number;
+  backoff_max_ms?: number;
+  jitter?: number;
 }
 
 /** ステージ全体の設定 */
@@ -38,6 +87,91 @@ export interface StageConfig {
   meta: StageMeta;
   map: MapConfig;
   waves: WaveConfig[];
+  client?: ClientConfig;
+}
+
+/** 不合格の理由（Rust側 FailReason と一致） */
+export type FailReason =
+  | { kind: 'not_finished' }
+  | { kind: 'sla_missed'; success_ratio: number; sla_target: number }
+  | { kind: 'over_budget'; total_cost: number; budget: number };
+
+/** ステージ結果（Rust側 get_stage_result() のJSON） */
+export interface StageResult {
+  finished: boolean;
+  spawned: number;
+  attacks_spawned: number;
+  processed: number;
+  dropped: number;
+  filtered: number; // Firewallで遮断した数（誤検知を含む）
+  rate_limited: number; // RateLimiterで拒否した数（429応答を含む）
+  timed_out: number; // クライアントがタイムアウトした回数
+  retried: number; // クライアントが送り直した数
+  success_ratio: number;
+  sla_target: number;
+  autoscaling_cost: number; // オートスケーリングの課金
+  total_cost: number; // プレイヤー配置ノード + オートスケーリングの課金（予算と比べる額）
+  fixed_cost: number; // 固定ノードのグレードの合計コスト（予算には含めない）
+  budget: number;
+  passed: boolean;
+  reasons: FailReason[];
+}
+
+/** パケットをドロップした原因（Rust側 DropReason と一致） */
+export type DropReason =
+  | 'queue_full'        // 待機キューが満杯
+  | 'queue_evicted'     // 優先度の高いパケットに押し出された
+  | 'red_early_drop'    // REDの早期廃棄
+  | 'codel_drop'        // CoDelの滞在時間超過
+  | 'no_route'          // 次に送れるノードがない
+  | 'invalid_target'    // 向かっていたノードが存在しない
+  | 'route_too_long'    // 通過履歴が上限を超えた
+  | 'firewall_overload' // Firewallの検査スループット超過
+  | 'node_down'         // いたノードが停止した
+  | 'node_removed'      // ノードが削除された
+  | 'link_loss'         // リンクでの損失
+  | 'link_removed'      // 移動中のリンクが削除された
+  | 'no_packet_slot'    // 送り直す空きスロットがない
+  | 'rate_limited'      // RateLimiterで拒否して捨てた（droppedではなくrate_limitedに数える）
+  | 'filtered';         // Firewallが遮断した（誤検知を含む。droppedではなくfilteredに数える）
+
+/** シミュレーションイベント（Rust側 simulation_drain_events() のJSON配列の要素） */
+export type SimEvent = { time_ms: number } & (
+  | { kind: 'packet_dropped'; node_id: number | null; reason: DropReason; packet_type: number }
+  | { kind: 'node_saturated'; node_id: number }
+  | { kind: 'node_recovered'; node_id: number }
+  | { kind: 'wave_started'; wave_id: number; count: number }
+  | { kind: 'wave_finished'; wave_id: number }
+  | { kind: 'stage_finished'; passed: boolean }
+  | { kind: 'events_discarded'; count: number }
+);
+
+/** 配置系APIのエラー（Rust側 PlacementError と一致） */
+export type PlacementError =
+  | { kind: 'stage_not_loaded' }
+  | { kind: 'unknown_node_type'; node_type: string }
+  | { kind: 'unknown_grade'; node_type: string; grade: string }
+  | { kind: 'over_budget'; cost: number; remaining: number }
+  | { kind: 'node_not_found'; id: number }
+  | { kind: 'fixed_node'; id: number };
+
+/** place_node / remove_node / upgrade_node の戻り値（JSON） */
+export type PlacementResponse =
+  | {
+      ok: true;
+      remaining_budget: number;
+      node_id?: number; // place_node
+      cost?: number;    // place_node / upgrade_node
+      refund?: number;  // remove_node
+    }
+  | { ok: false; error: PlacementError };
+
+/** プレイヤーが配置したノード */
+export interface PlacedNode {
+  id: number;
+  node_type: 'lb' | 'server' | 'db' | 'firewall' | 'cache' | 'rate_limiter';
+  grade: string | null;
+  cost: number;
 }
 
 /** ステージ一覧用の簡易情報 */
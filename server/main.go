@@ -48,7 +48,7 @@ type Wave struct {
 	Count       int     `json:"count"`
 	DurationMs  int     `json:"duration_ms"`
 	PacketType  string  `json:"packet_type"`
-	Speed       float64 `json:"speed"`
+	Speed       float64 `json:"speed"` // 移動速度（ピクセル/ミリ秒）
 }
 
 // StageListItem はステージ一覧用の簡易情報（manifest.jsonから読み込む）
@@ -0,0 +1,285 @@
+// =============================================================================
+// AUTOSCALE - オートスケーリンググループ担当
+// 同じグレードのノード（インスタンス）を束ね、平均負荷に応じて台数を増減する
+// ノードの追加・削除そのものはSimulationStateが行い、ここでは判断と課金を管理する
+// =============================================================================
+
+use crate::link::LinkProfile;
+use crate::queue::QueuePolicy;
+use crate::simulation::{HealthConfig, NodeSpec};
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// スケーリングの方針（ステージJSONではグループ定義に直接書く）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScalingPolicy {
+    pub min_instances: u32,  // 最小台数（開始時はこの台数で稼働）
+    pub max_instances: u32,  // 最大台数
+    pub scale_out_load: f32, // ウィンドウ平均の負荷率がこれを超えたら1台追加
+    pub scale_in_load: f32,  // ウィンドウ平均の負荷率がこれを下回ったら1台削除
+    pub window_ms: f64,      // 負荷率を平均する期間
+    pub cooldown_ms: f64,    // 増減してから次に増減できるまでの時間
+    pub boot_delay_ms: f64,  // 追加したインスタンスが振り分け対象になるまでの時間
+    pub cost_per_sec: f64,   // 1インスタンス・1秒あたりのコスト
+}
+
+impl Default for ScalingPolicy {
+    fn default() -> Self {
+        ScalingPolicy {
+            min_instances: 1,
+            max_instances: 4,
+            scale_out_load: 0.8,
+            scale_in_load: 0.3,
+            window_ms: 3000.0,
+            cooldown_ms: 5000.0,
+            boot_delay_ms: 2000.0,
+            cost_per_sec: 1.0,
+        }
+    }
+}
+
+/// スケーリングの判断結果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScaleAction {
+    None,
+    Out, // 1台追加
+    In,  // 1台削除（ドレインしてから）
+}
+
+/// オートスケーリンググループ
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoscalingGroup {
+    pub id: u32,
+    pub node_type: u32,              // インスタンスのNodeType as u32
+    pub spec: NodeSpec,              // インスタンスのスペック
+    pub health_config: HealthConfig, // インスタンスの障害・復旧設定
+    pub queue_policy: QueuePolicy,   // インスタンスのキュー規律
+    pub policy: ScalingPolicy,
+    pub x: f32, // インスタンスを並べる基準位置
+    pub y: f32,
+    pub instances: Vec<u32>, // 稼働中・起動中のインスタンスのノードID
+    pub draining: Vec<u32>,  // 削除待ち（新しいリクエストを受けず、処理が終わったら消える）
+    pub upstream: Vec<(u32, LinkProfile)>, // グループへのリンク元ノードIDと伝送特性（インスタンス追加時にリンクを張る）
+    pub downstream: Vec<(u32, LinkProfile)>, // グループからのリンク先ノードIDと伝送特性
+    samples: VecDeque<(f64, f32)>, // (時刻, 平均負荷率)
+    window_start_ms: f64,          // 現在のウィンドウの計測開始時刻
+    #[serde(with = "crate::snapshot::non_finite")]
+    last_scale_ms: f64,            // 最後に増減した時刻
+    instance_ms: f64,              // 累計のインスタンス稼働時間（課金用）
+}
+
+/// グループの状態（JS・ヘッドレス実行向け）
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AutoscalingStatus {
+    pub id: u32,
+    pub instances: u32,
+    pub draining: u32,
+    pub window_load: Option<f32>,
+    pub cost: f64,
+}
+
+impl AutoscalingGroup {
+    pub fn new(
+        id: u32,
+        node_type: u32,
+        spec: NodeSpec,
+        health_config: HealthConfig,
+        policy: ScalingPolicy,
+        x: f32,
+        y: f32,
+    ) -> Self {
+        AutoscalingGroup {
+            id,
+            node_type,
+            spec,
+            health_config,
+            queue_policy: QueuePolicy::default(),
+            policy,
+            x,
+            y,
+            instances: Vec::new(),
+            draining: Vec::new(),
+            upstream: Vec::new(),
+            downstream: Vec::new(),
+            samples: VecDeque::new(),
+            window_start_ms: 0.0,
+            last_scale_ms: f64::NEG_INFINITY,
+            instance_ms: 0.0,
+        }
+    }
+
+    /// インスタンスを置く位置（基準位置から上下に交互に並べる）
+    pub fn slot_position(&self, slot: usize) -> (f32, f32) {
+        const SPACING: f32 = 70.0;
+        let step = slot.div_ceil(2) as f32 * SPACING;
+        let dy = if slot % 2 == 1 { step } else { -step };
+        (self.x, self.y + dy)
+    }
+
+    /// 稼働時間を課金に加算（起動中・ドレイン中も課金する）
+    pub fn accrue(&mut self, delta_ms: f64) {
+        self.instance_ms += delta_ms * (self.instances.len() + self.draining.len()) as f64;
+    }
+
+    /// 累計コスト
+    pub fn cost(&self) -> f64 {
+        self.instance_ms / 1000.0 * self.policy.cost_per_sec
+    }
+
+    /// 平均負荷率を記録し、ウィンドウより古いものを捨てる
+    pub fn record_load(&mut self, now_ms: f64, load_rate: f32) {
+        self.samples.push_back((now_ms, load_rate));
+        while let Some(&(t, _)) = self.samples.front() {
+            if t < now_ms - self.policy.window_ms {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// ウィンドウ内の平均負荷率
+    pub fn window_load(&self) -> Option<f32> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let sum: f32 = self.samples.iter().map(|&(_, load)| load).sum();
+        Some(sum / self.samples.len() as f32)
+    }
+
+    /// ウィンドウ分の計測が溜まっていて、クールダウンが明けていれば増減を判断
+    pub fn decide(&self, now_ms: f64) -> ScaleAction {
+        if now_ms - self.last_scale_ms < self.policy.cooldown_ms
+            || now_ms - self.window_start_ms < self.policy.window_ms
+        {
+            return ScaleAction::None;
+        }
+        let Some(load) = self.window_load() else {
+            return ScaleAction::None;
+        };
+        let count = self.instances.len() as u32;
+        if load > self.policy.scale_out_load && count < self.policy.max_instances {
+            ScaleAction::Out
+        } else if load < self.policy.scale_in_load && count > self.policy.min_instances {
+            ScaleAction::In
+        } else {
+            ScaleAction::None
+        }
+    }
+
+    /// 増減した（または初期化した）ので、計測をやり直す
+    pub fn mark_scaled(&mut self, now_ms: f64) {
+        self.last_scale_ms = now_ms;
+        self.window_start_ms = now_ms;
+        self.samples.clear();
+    }
+
+    /// 課金と計測をリセット（シミュレーションのリセット用）
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.window_start_ms = 0.0;
+        self.last_scale_ms = f64::NEG_INFINITY;
+        self.instance_ms = 0.0;
+    }
+
+    pub fn status(&self) -> AutoscalingStatus {
+        AutoscalingStatus {
+            id: self.id,
+            instances: self.instances.len() as u32,
+            draining: self.draining.len() as u32,
+            window_load: self.window_load(),
+            cost: self.cost(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_group(policy: ScalingPolicy) -> AutoscalingGroup {
+        let mut group = AutoscalingGroup::new(
+            0,
+            2,
+            NodeSpec::default_for(2),
+            HealthConfig::default(),
+            policy,
+            0.0,
+            0.0,
+        );
+        group.instances = vec![10, 11];
+        group
+    }
+
+    fn policy() -> ScalingPolicy {
+        ScalingPolicy {
+            min_instances: 1,
+            max_instances: 3,
+            window_ms: 1000.0,
+            cooldown_ms: 5000.0,
+            ..ScalingPolicy::default()
+        }
+    }
+
+    #[test]
+    fn waits_for_a_full_window_and_the_cooldown() {
+        let mut group = new_group(policy());
+        group.mark_scaled(0.0);
+        group.record_load(500.0, 0.95);
+        // ウィンドウ分の計測が溜まっていない
+        assert_eq!(group.decide(500.0), ScaleAction::None);
+
+        // ウィンドウは溜まったが、前回の増減からクールダウンが明けていない
+        group.record_load(1000.0, 0.95);
+        assert_eq!(group.decide(1000.0), ScaleAction::None);
+        group.record_load(4999.0, 0.95);
+        assert_eq!(group.decide(4999.0), ScaleAction::None);
+
+        group.record_load(5000.0, 0.95);
+        assert_eq!(group.decide(5000.0), ScaleAction::Out);
+
+        // 増減すると計測もやり直しになる
+        group.mark_scaled(5000.0);
+        assert_eq!(group.window_load(), None);
+        assert_eq!(group.decide(10_000.0), ScaleAction::None);
+    }
+
+    #[test]
+    fn scales_within_min_and_max_instances() {
+        let mut group = new_group(policy());
+        group.record_load(1000.0, 0.9);
+        assert_eq!(group.decide(1000.0), ScaleAction::Out);
+        group.instances.push(12);
+        assert_eq!(group.decide(1000.0), ScaleAction::None); // 最大台数
+
+        let mut group = new_group(policy());
+        group.record_load(1000.0, 0.1);
+        assert_eq!(group.decide(1000.0), ScaleAction::In);
+        group.instances.pop();
+        assert_eq!(group.decide(1000.0), ScaleAction::None); // 最小台数
+
+        // 古い計測はウィンドウから外れる
+        let mut group = new_group(policy());
+        group.record_load(0.0, 0.1);
+        group.record_load(2000.0, 0.5);
+        assert_eq!(group.window_load(), Some(0.5));
+        assert_eq!(group.decide(2000.0), ScaleAction::None);
+    }
+
+    #[test]
+    fn charges_running_booting_and_draining_instances() {
+        let mut group = new_group(ScalingPolicy {
+            cost_per_sec: 2.0,
+            ..policy()
+        });
+        group.draining = vec![12];
+        group.accrue(1500.0);
+        assert_eq!(group.cost(), 9.0); // 3台 × 1.5秒 × 2
+        assert_eq!(group.status().cost, 9.0);
+
+        group.reset();
+        assert_eq!(group.cost(), 0.0);
+    }
+}
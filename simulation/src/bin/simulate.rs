@@ -0,0 +1,375 @@
+// =============================================================================
+// SIMULATE - ステージJSONをヘッドレスで実行するネイティブランナー
+// 使い方: cargo run --bin simulate -- stages/BlueBull1.json --layout layout.json --seed 1
+// 途中保存: --snapshot-at 30000 --snapshot snap.bin（.json ならJSON形式）、再開: --resume snap.bin
+// 操作の記録: --record replay.json、再生: --replay replay.json（記録した実行の結果を出力）
+// イベントの書き出し: --events events.jsonl（ドロップの原因・ノードの飽和・Wave・ステージ終了を1行1イベントで）
+// =============================================================================
+
+use serde::Serialize;
+use simulation::metrics::LatencySummary;
+use simulation::{
+    Command, LayoutConfig, LinkStatus, LoadedStage, QueueDrops, Replay, Session, SimulationState,
+    Snapshot, StageConfig, StageResult,
+};
+use std::io::{BufWriter, Write};
+use std::process::exit;
+
+// デフォルトの固定ステップ（60fps相当）
+const DEFAULT_DT_MS: f64 = 1000.0 / 60.0;
+
+// Waveが終わらない場合の打ち切り時間
+const DEFAULT_MAX_TIME_MS: f64 = 600_000.0;
+
+// ブラウザ版と同じパケットスロット数
+const DEFAULT_MAX_PACKETS: usize = 100_000;
+
+const USAGE: &str = "usage: simulate <stage.json> [--layout <layout.json>] [--seed <n>] [--dt <ms>] [--max-time <ms>] [--max-packets <n>] [--snapshot-at <ms> --snapshot <file>] [--record <replay.json>] [--events <events.jsonl>]\n       simulate --resume <snapshot> [--dt <ms>] [--max-time <ms>]\n       simulate --replay <replay.json>";
+
+/// コマンドライン引数
+struct Args {
+    stage_path: Option<String>,
+    resume_path: Option<String>, // 保存した状態から再開する（ステージ・レイアウト・シードは保存時のもの）
+    snapshot_path: Option<String>, // snapshot_at_ms の時点の状態を書き出す先
+    record_path: Option<String>, // 実行中の操作の記録を書き出す先
+    replay_path: Option<String>, // 記録を再生し、その結果を出力する（ほかの実行オプションは使わない）
+    events_path: Option<String>, // 実行中のイベントを書き出す先（JSON Lines）
+    layout_path: Option<String>,
+    seed: u64,
+    dt_ms: f64,
+    max_time_ms: f64,
+    max_packets: usize,
+    snapshot_at_ms: f64,
+}
+
+/// 実行結果（標準出力にJSONで出力）
+#[derive(Serialize)]
+struct RunReport {
+    stage: String,
+    seed: u64,
+    elapsed_ms: f64,
+    #[serde(flatten)]
+    result: StageResult, // 打ち切り時点の結果（SLA・予算・合否）
+    latency: LatencySummary, // レスポンスがGatewayに戻るまでの時間（全体・Waveごと）
+    queue_drops: QueueDrops, // キューでのドロップ数（原因ごと）
+    links: Vec<LinkStatus>,  // リンクごとの送信数・損失・利用率
+}
+
+fn main() {
+    let args = parse_args(std::env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("error: {}", msg);
+        eprintln!("{}", USAGE);
+        exit(2);
+    });
+
+    if let Some(path) = &args.replay_path {
+        let (sim, stage) = read_replay(path);
+        print_report(&sim, &stage);
+        return;
+    }
+
+    // ブラウザと同じ操作として記録する（時刻は操作を受けた時点のシミュレーション時刻）
+    let mut recorder = args.record_path.as_ref().map(|_| Replay::default());
+    let (mut sim, mut stage) = match (&args.resume_path, &args.stage_path) {
+        (Some(path), _) => read_snapshot(path),
+        (None, Some(path)) => {
+            let config: StageConfig = read_json(path);
+            let layout: Option<LayoutConfig> = args.layout_path.as_deref().map(read_json);
+            record(&mut recorder, 0.0, || Command::CreateSimulation {
+                max_packets: args.max_packets,
+                seed: args.seed,
+            });
+            record(&mut recorder, 0.0, || Command::LoadStage {
+                config: Box::new(config.clone()),
+            });
+            let mut sim = SimulationState::with_seed(args.max_packets, args.seed);
+            let mut stage = LoadedStage::new(config);
+            stage.place_fixed_nodes(&mut sim);
+            if let Some(layout) = layout {
+                stage.apply_layout(&mut sim, &layout);
+                record(&mut recorder, 0.0, || Command::ApplyLayout { layout });
+            }
+            (sim, stage)
+        }
+        (None, None) => unreachable!("parse_args requires a stage or a snapshot"),
+    };
+    let mut snapshot_path = args.snapshot_path.clone();
+    let mut events_out = args.events_path.as_deref().map(|path| {
+        let file = std::fs::File::create(path).unwrap_or_else(|e| {
+            eprintln!("error: failed to create {}: {}", path, e);
+            exit(1);
+        });
+        BufWriter::new(file)
+    });
+
+    // ブラウザのゲームループと同じく、tickの中で固定ステップごとにWaveを発火させる
+    while sim.get_current_time() < args.max_time_ms {
+        if sim.get_current_time() >= args.snapshot_at_ms {
+            if let Some(path) = snapshot_path.take() {
+                write_snapshot(&path, &sim, &stage);
+            }
+        }
+        record(&mut recorder, sim.get_current_time(), || Command::Tick {
+            delta_ms: args.dt_ms,
+        });
+        let finished = stage.tick(&mut sim, args.dt_ms);
+        if let Some(out) = events_out.as_mut() {
+            write_events(out, &mut sim);
+        }
+        if finished {
+            break;
+        }
+    }
+    if let Some(mut out) = events_out {
+        if let Err(e) = out.flush() {
+            eprintln!("error: failed to write events: {}", e);
+            exit(1);
+        }
+    }
+
+    if let (Some(path), Some(replay)) = (&args.record_path, &recorder) {
+        if let Err(e) = std::fs::write(path, replay.to_json()) {
+            eprintln!("error: failed to write {}: {}", path, e);
+            exit(1);
+        }
+        eprintln!(
+            "replay written to {} ({} commands)",
+            path,
+            replay.commands.len()
+        );
+    }
+
+    print_report(&sim, &stage);
+}
+
+/// 実行結果をJSONで標準出力に書く
+fn print_report(sim: &SimulationState, stage: &LoadedStage) {
+    let report = RunReport {
+        stage: stage.config.meta.title.clone(),
+        seed: sim.seed(),
+        elapsed_ms: sim.get_current_time(),
+        result: stage.evaluate(sim),
+        latency: sim.latency_summary(),
+        queue_drops: sim.queue_drops(),
+        links: sim.link_status(),
+    };
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("error: failed to serialize report: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// 引数を解析
+fn parse_args(mut iter: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut stage_path = None;
+    let mut resume_path = None;
+    let mut snapshot_path = None;
+    let mut snapshot_at_ms: f64 = 0.0;
+    let mut record_path = None;
+    let mut replay_path = None;
+    let mut events_path = None;
+    let mut layout_path = None;
+    let mut seed = 1;
+    let mut dt_ms = DEFAULT_DT_MS;
+    let mut max_time_ms = DEFAULT_MAX_TIME_MS;
+    let mut max_packets = DEFAULT_MAX_PACKETS;
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--layout" => layout_path = Some(value(&mut iter, &arg)?),
+            "--seed" => seed = parse_value(&mut iter, &arg)?,
+            "--dt" => dt_ms = parse_value(&mut iter, &arg)?,
+            "--max-time" => max_time_ms = parse_value(&mut iter, &arg)?,
+            "--max-packets" => max_packets = parse_value(&mut iter, &arg)?,
+            "--resume" => resume_path = Some(value(&mut iter, &arg)?),
+            "--snapshot" => snapshot_path = Some(value(&mut iter, &arg)?),
+            "--snapshot-at" => snapshot_at_ms = parse_value(&mut iter, &arg)?,
+            "--record" => record_path = Some(value(&mut iter, &arg)?),
+            "--replay" => replay_path = Some(value(&mut iter, &arg)?),
+            "--events" => events_path = Some(value(&mut iter, &arg)?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+            _ if stage_path.is_none() => stage_path = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    // NaN・無限大を通すと時間が進まず終わらなくなる
+    if !dt_ms.is_finite() || dt_ms <= 0.0 {
+        return Err("--dt must be a positive finite number".to_string());
+    }
+    if !max_time_ms.is_finite() || max_time_ms <= 0.0 {
+        return Err("--max-time must be a positive finite number".to_string());
+    }
+    if !snapshot_at_ms.is_finite() || snapshot_at_ms < 0.0 {
+        return Err("--snapshot-at must be a non-negative finite number".to_string());
+    }
+
+    if stage_path.is_none() && resume_path.is_none() && replay_path.is_none() {
+        return Err("missing stage file".to_string());
+    }
+    if record_path.is_some() && resume_path.is_some() {
+        return Err("--record cannot be combined with --resume".to_string());
+    }
+    if events_path.is_some() && replay_path.is_some() {
+        return Err("--events cannot be combined with --replay".to_string());
+    }
+
+    Ok(Args {
+        stage_path,
+        resume_path,
+        snapshot_path,
+        record_path,
+        replay_path,
+        events_path,
+        layout_path,
+        seed,
+        dt_ms,
+        max_time_ms,
+        max_packets,
+        snapshot_at_ms,
+    })
+}
+
+/// オプションの値を取得
+fn value(iter: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    iter.next()
+        .ok_or_else(|| format!("missing value for {}", name))
+}
+
+/// オプションの値を取得して数値に変換
+fn parse_value<T: std::str::FromStr>(
+    iter: &mut impl Iterator<Item = String>,
+    name: &str,
+) -> Result<T, String> {
+    let raw = value(iter, name)?;
+    raw.parse()
+        .map_err(|_| format!("invalid value '{}' for {}", raw, name))
+}
+
+/// JSONファイルを読み込んでデシリアライズ（失敗したら終了）
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> T {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("error: failed to read {}: {}", path, e);
+        exit(1);
+    });
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("error: failed to parse {}: {}", path, e);
+        exit(1);
+    })
+}
+
+/// 拡張子が .json ならJSON形式のスナップショットとして扱う
+fn is_json_path(path: &str) -> bool {
+    path.ends_with(".json")
+}
+
+/// 状態をスナップショットとして書き出す（失敗したら終了）
+fn write_snapshot(path: &str, sim: &SimulationState, stage: &LoadedStage) {
+    let encoded = if is_json_path(path) {
+        Snapshot::to_json(sim, Some(stage)).map(String::into_bytes)
+    } else {
+        Snapshot::encode(sim, Some(stage))
+    };
+    let written = encoded
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| std::fs::write(path, bytes).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("error: failed to write {}: {}", path, e);
+        exit(1);
+    }
+    eprintln!(
+        "snapshot written to {} at {}ms",
+        path,
+        sim.get_current_time()
+    );
+}
+
+/// スナップショットから状態を復元（失敗したら終了）
+fn read_snapshot(path: &str) -> (SimulationState, LoadedStage) {
+    let restored = if is_json_path(path) {
+        std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| Snapshot::from_json(&json).map_err(|e| e.to_string()))
+    } else {
+        std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| Snapshot::decode(&bytes).map_err(|e| e.to_string()))
+    };
+    match restored {
+        Ok(Snapshot {
+            state,
+            stage: Some(stage),
+        }) => (state, stage),
+        Ok(_) => {
+            eprintln!("error: {} has no loaded stage", path);
+            exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: failed to read {}: {}", path, e);
+            exit(1);
+        }
+    }
+}
+
+/// 溜まったイベントを1行1イベントのJSONで書き出す（失敗したら終了）
+fn write_events(out: &mut impl Write, sim: &mut SimulationState) {
+    for event in sim.drain_events() {
+        let written = serde_json::to_writer(&mut *out, &event)
+            .map_err(|e| e.to_string())
+            .and_then(|()| writeln!(out).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!("error: failed to write events: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// 記録中なら操作を記録する（記録しないときは操作を組み立てない）
+fn record(recorder: &mut Option<Replay>, time_ms: f64, command: impl FnOnce() -> Command) {
+    if let Some(replay) = recorder {
+        replay.record(time_ms, command());
+    }
+}
+
+/// 記録を再生して最後の状態を返す（失敗したら終了）
+fn read_replay(path: &str) -> (SimulationState, LoadedStage) {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("error: failed to read {}: {}", path, e);
+        exit(1);
+    });
+    let replay = Replay::from_json(&text).unwrap_or_else(|e| {
+        eprintln!("error: {}: {}", path, e);
+        exit(1);
+    });
+    if replay.gpu_motion {
+        eprintln!(
+            "warning: {} was recorded with GPU motion; the CPU replay may not match",
+            path
+        );
+    }
+    match replay.run() {
+        Ok(Session {
+            sim: Some(sim),
+            stage: Some(stage),
+        }) => (sim, stage),
+        Ok(_) => {
+            eprintln!(
+                "error: {} does not create a simulation and load a stage",
+                path
+            );
+            exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: {}: {}", path, e);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+// =============================================================================
+// CLIENT - クライアント側のタイムアウト・リトライ担当
+// 送信したリクエストの期限を管理し、期限切れ・429応答のものをバックオフ付きで送り直す
+// パケットの生成・破棄そのものはSimulationStateが行い、ここでは記録と判断を管理する
+// =============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// クライアントのタイムアウト・リトライ方針（ステージJSONでは "client" に書く）
+/// timeout_ms が 0 のときは期限なし（失敗を検知できないのでリトライもしない）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub timeout_ms: f64,         // 送信から応答までの期限（0 = 期限なし）
+    pub max_retries: u32,        // 1つのリクエストを送り直す最大回数
+    pub backoff_base_ms: f64,    // 1回目の送り直しまでの待ち時間
+    pub backoff_multiplier: f64, // 送り直すたびに待ち時間にかける倍率
+    pub backoff_max_ms: f64,     // 待ち時間の上限
+    pub jitter: f64,             // 待ち時間をランダムに縮める割合（0.0 - 1.0、1.0 = フルジッター）
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            timeout_ms: 0.0,
+            max_retries: 0,
+            backoff_base_ms: 200.0,
+            backoff_multiplier: 2.0,
+            backoff_max_ms: 5000.0,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 期限を管理するか
+    pub fn is_enabled(&self) -> bool {
+        self.timeout_ms > 0.0
+    }
+
+    /// retry回目（1始まり）の送り直しまでの待ち時間（random: 0.0 - 1.0）
+    pub fn backoff_ms(&self, retry: u32, random: f64) -> f64 {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.backoff_base_ms * self.backoff_multiplier.powi(exponent))
+            .min(self.backoff_max_ms)
+            .max(0.0);
+        delay * (1.0 - self.jitter.clamp(0.0, 1.0) * random)
+    }
+}
+
+/// リクエストを送り直すのに必要な情報（最初の送信時のもの）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequestOrigin {
+    pub x: f32, // 送信元の位置
+    pub y: f32,
+    pub target_node_idx: i32, // 入口ノードのインデックス
+    pub speed: f32,
+    pub packet_type: u32,
+    pub complexity: u8,
+    pub wave_id: u32,
+    pub client_id: u32,
+    pub spawn_time_ms: f64, // 最初に送信した時刻（レイテンシは送り直しを含めて計る）
+}
+
+/// 応答待ちのリクエスト
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct OutstandingRequest {
+    pub packet_idx: usize, // 送信したパケットのインデックス
+    pub deadline_ms: f64,  // この時刻までに応答がなければタイムアウト
+    pub retry: u32,        // 何回目の送り直しか（0 = 最初の送信）
+    pub origin: RequestOrigin,
+}
+
+/// 送り直しの予約
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PendingRetry {
+    pub fire_ms: f64, // 送り直す時刻
+    pub retry: u32,   // 何回目の送り直しか（1始まり）
+    pub origin: RequestOrigin,
+}
+
+/// 応答待ちの期限（期限の早い順、同じならリクエストIDの小さい順に取り出す）
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct Deadline {
+    deadline_ms: f64,
+    request_id: u32,
+}
+
+impl Ord for Deadline {
+    // BinaryHeapは最大のものから取り出すので、早いほど大きいとみなす
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .deadline_ms
+            .total_cmp(&self.deadline_ms)
+            .then_with(|| other.request_id.cmp(&self.request_id))
+    }
+}
+
+impl PartialOrd for Deadline {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Deadline {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Deadline {}
+
+/// クライアント全体の状態
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "SavedClientState")]
+pub struct ClientState {
+    pub policy: RetryPolicy,
+    outstanding: HashMap<u32, OutstandingRequest>, // リクエストID -> 応答待ち
+    deadlines: BinaryHeap<Deadline>, // 応答待ちの期限（応答済みのものは期限まで残り、取り出すときに捨てる）
+    retries: Vec<PendingRetry>,      // 送り直し予約
+    next_request_id: u32,            // 最後に割り当てたリクエストID（0 = 未追跡）
+    #[serde(skip)]
+    wave_requests: HashMap<u32, u32>, // Wave ID -> 応答待ち・送り直し予約の数（0になったら消す）
+}
+
+/// スナップショット上のクライアント状態（Waveごとの数は読み込んだ後で数え直す）
+#[derive(Deserialize)]
+struct SavedClientState {
+    policy: RetryPolicy,
+    outstanding: HashMap<u32, OutstandingRequest>,
+    deadlines: BinaryHeap<Deadline>,
+    retries: Vec<PendingRetry>,
+    next_request_id: u32,
+}
+
+impl From<SavedClientState> for ClientState {
+    fn from(saved: SavedClientState) -> Self {
+        let mut state = ClientState {
+            policy: saved.policy,
+            outstanding: saved.outstanding,
+            deadlines: saved.deadlines,
+            retries: saved.retries,
+            next_request_id: saved.next_request_id,
+            wave_requests: HashMap::new(),
+        };
+        let waves: Vec<u32> = state
+            .outstanding
+            .values()
+            .map(|req| req.origin)
+            .chain(state.retries.iter().map(|retry| retry.origin))
+            .map(|origin| origin.wave_id)
+            .collect();
+        for wave_id in waves {
+            state.count_wave(wave_id, true);
+        }
+        state
+    }
+}
+
+impl ClientState {
+    /// 応答待ちも送り直し予約も残っていないか
+    pub fn is_idle(&self) -> bool {
+        self.outstanding.is_empty() && self.retries.is_empty()
+    }
+
+    /// Waveに属する応答待ち・送り直し予約のリクエストの数
+    pub fn wave_requests(&self, wave_id: u32) -> u32 {
+        self.wave_requests.get(&wave_id).copied().unwrap_or(0)
+    }
+
+    /// Waveごとの数を1つ足す・引く
+    fn count_wave(&mut self, wave_id: u32, add: bool) {
+        if add {
+            *self.wave_requests.entry(wave_id).or_insert(0) += 1;
+        } else if let Some(count) = self.wave_requests.get_mut(&wave_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.wave_requests.remove(&wave_id);
+            }
+        }
+    }
+
+    /// 送信したリクエストを記録し、(リクエストID, 期限) を返す
+    /// 期限を管理しない場合は (0, f64::INFINITY)
+    pub fn register(
+        &mut self,
+        packet_idx: usize,
+        now_ms: f64,
+        retry: u32,
+        origin: RequestOrigin,
+    ) -> (u32, f64) {
+        if !self.policy.is_enabled() {
+            return (0, f64::INFINITY);
+        }
+        self.next_request_id = self.next_request_id.wrapping_add(1).max(1);
+        let deadline_ms = now_ms + self.policy.timeout_ms;
+        self.outstanding.insert(
+            self.next_request_id,
+            OutstandingRequest {
+                packet_idx,
+                deadline_ms,
+                retry,
+                origin,
+            },
+        );
+        self.deadlines.push(Deadline {
+            deadline_ms,
+            request_id: self.next_request_id,
+        });
+        self.count_wave(origin.wave_id, true);
+        (self.next_request_id, deadline_ms)
+    }
+
+    /// 応答が届いたリクエストを記録から外す（期限切れ後・未追跡ならNone）
+    pub fn complete(&mut self, request_id: u32) -> Option<OutstandingRequest> {
+        if request_id == 0 {
+            return None;
+        }
+        let req = self.outstanding.remove(&request_id)?;
+        self.count_wave(req.origin.wave_id, false);
+        Some(req)
+    }
+
+    /// 期限が切れたリクエストを記録から外して返す（リクエストID順）
+    /// 期限の早い順に並べてあるので、切れていないものは見ない
+    pub fn take_expired(&mut self, now_ms: f64) -> Vec<(u32, OutstandingRequest)> {
+        let mut expired: Vec<(u32, OutstandingRequest)> = Vec::new();
+        while let Some(&next) = self.deadlines.peek() {
+            if now_ms < next.deadline_ms {
+                break;
+            }
+            self.deadlines.pop();
+            // 応答済み（IDが一巡して別のリクエストに使われたものを含む）は捨てる
+            if let Some(&req) = self.outstanding.get(&next.request_id) {
+                if req.deadline_ms == next.deadline_ms {
+                    expired.push((next.request_id, req));
+                    self.outstanding.remove(&next.request_id);
+                    self.count_wave(req.origin.wave_id, false);
+                }
+            }
+        }
+        // 同じ時刻に切れたものは送り直しの順序が結果に響くので、これまでどおりID順にする
+        expired.sort_unstable_by_key(|&(id, _)| id);
+        expired
+    }
+
+    /// 送り直しを予約（最大回数を超える場合はfalse、random: 0.0 - 1.0）
+    pub fn schedule_retry(
+        &mut self,
+        now_ms: f64,
+        retry: u32,
+        origin: RequestOrigin,
+        random: f64,
+    ) -> bool {
+        if retry > self.policy.max_retries {
+            return false;
+        }
+        self.retries.push(PendingRetry {
+            fire_ms: now_ms + self.policy.backoff_ms(retry, random),
+            retry,
+            origin,
+        });
+        self.count_wave(origin.wave_id, true);
+        true
+    }
+
+    /// 送り直す時刻になった予約を取り出す（予約順）
+    pub fn take_due_retries(&mut self, now_ms: f64) -> Vec<PendingRetry> {
+        let (due, waiting): (Vec<PendingRetry>, Vec<PendingRetry>) = self
+            .retries
+            .iter()
+            .partition(|retry| now_ms >= retry.fire_ms);
+        self.retries = waiting;
+        for retry in &due {
+            self.count_wave(retry.origin.wave_id, false);
+        }
+        due
+    }
+
+    /// 応答待ちと送り直し予約をすべて捨てる（方針はそのまま）
+    pub fn reset(&mut self) {
+        self.outstanding.clear();
+        self.deadlines.clear();
+        self.retries.clear();
+        self.wave_requests.clear();
+        self.next_request_id = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin() -> RequestOrigin {
+        RequestOrigin {
+            x: 0.0,
+            y: 0.0,
+            target_node_idx: 0,
+            speed: 0.3,
+            packet_type: 0,
+            complexity: 1,
+            wave_id: 0,
+            client_id: 1,
+            spawn_time_ms: 0.0,
+        }
+    }
+
+    fn client(timeout_ms: f64) -> ClientState {
+        ClientState {
+            policy: RetryPolicy {
+                timeout_ms,
+                ..RetryPolicy::default()
+            },
+            ..ClientState::default()
+        }
+    }
+
+    fn ids(expired: &[(u32, OutstandingRequest)]) -> Vec<u32> {
+        expired.iter().map(|&(id, _)| id).collect()
+    }
+
+    #[test]
+    fn expired_requests_come_out_by_deadline_then_id() {
+        let mut client = client(1000.0);
+        let (a, _) = client.register(10, 0.0, 0, origin());
+        let (b, _) = client.register(11, 0.0, 0, origin());
+        let (c, _) = client.register(12, 500.0, 0, origin());
+
+        assert!(client.take_expired(999.0).is_empty());
+        let expired = client.take_expired(1000.0);
+        assert_eq!(ids(&expired), vec![a, b]);
+        assert_eq!(expired[1].1.packet_idx, 11);
+        assert!(client.take_expired(1000.0).is_empty());
+
+        assert_eq!(ids(&client.take_expired(2000.0)), vec![c]);
+        assert!(client.is_idle());
+    }
+
+    #[test]
+    fn completed_requests_do_not_expire() {
+        let mut client = client(1000.0);
+        let (a, _) = client.register(10, 0.0, 0, origin());
+        let (b, _) = client.register(11, 0.0, 0, origin());
+        assert!(client.complete(a).is_some());
+
+        // 応答済みの期限は取り出すときに捨てる
+        assert_eq!(ids(&client.take_expired(1000.0)), vec![b]);
+        assert!(client.deadlines.is_empty());
+        assert!(client.complete(b).is_none());
+    }
+
+    #[test]
+    fn disabled_policy_tracks_nothing() {
+        let mut client = client(0.0);
+        assert_eq!(client.register(10, 0.0, 0, origin()), (0, f64::INFINITY));
+        assert!(client.take_expired(f64::MAX).is_empty());
+        assert!(client.is_idle());
+    }
+
+    #[test]
+    fn counts_requests_per_wave_until_answered_or_retried() {
+        let mut client = client(1000.0);
+        client.policy.max_retries = 1;
+        let other = RequestOrigin {
+            wave_id: 1,
+            ..origin()
+        };
+        let (a, _) = client.register(10, 0.0, 0, origin());
+        client.register(11, 0.0, 0, other);
+        assert_eq!(client.wave_requests(0), 1);
+        assert_eq!(client.wave_requests(1), 1);
+
+        client.complete(a);
+        assert_eq!(client.wave_requests(0), 0);
+
+        // 期限切れで送り直し予約に移っても、送り直すまではWaveに残る
+        assert_eq!(client.take_expired(1000.0).len(), 1);
+        assert!(client.schedule_retry(1000.0, 1, other, 0.0));
+        let restored: ClientState =
+            serde_json::from_str(&serde_json::to_string(&client).unwrap()).unwrap();
+        assert_eq!(client.wave_requests(1), 1);
+        assert_eq!(restored.wave_requests(1), 1);
+
+        assert_eq!(client.take_due_retries(f64::MAX).len(), 1);
+        assert_eq!(client.wave_requests(1), 0);
+    }
+}
@@ -0,0 +1,19 @@
+// =============================================================================
+// CONSOLE - ログ出力担当
+// ブラウザ（wasm）ではconsole.logへ、ネイティブ実行時は何も出力しない
+// =============================================================================
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+use wasm_bindgen::prelude::*;
+
+// JS側の関数（console.log）をRustで使うための宣言
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    pub fn log(s: &str);
+}
+
+// ネイティブ実行時（ヘッドレスランナー等）はログを出さない
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn log(_s: &str) {}
@@ -0,0 +1,52 @@
+// =============================================================================
+// DECODE - 受信データ（バイナリ / JSON）のデコード担当
+// WebSocket等で受け取ったパケット座標を描画用の [x, y, ...] 配列に変換する
+// =============================================================================
+
+use crate::simulation::{HEIGHT, WIDTH};
+use serde::{Deserialize, Serialize};
+
+// バイナリ形式の1パケットのバイト数（[id: u32][x: u16][y: u16]、リトルエンディアン）
+pub const BINARY_PACKET_SIZE: usize = 8;
+
+// パケットのデータを表す構造体。JSONのシリアライズ/デシリアライズに対応（旧API用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonPacket {
+    pub id: u32,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// バイナリ形式のパケット列を座標に変換して out に追加し、パケット数を返す
+/// x, y は 0..65535 に正規化されているのでキャンバスサイズに戻す
+pub fn decode_binary_coords(data: &[u8], out: &mut Vec<f32>) -> usize {
+    let packet_count = data.len() / BINARY_PACKET_SIZE;
+    out.reserve(packet_count * 2);
+
+    for chunk in data.chunks_exact(BINARY_PACKET_SIZE) {
+        let x16 = u16::from_le_bytes([chunk[4], chunk[5]]);
+        let x = (x16 as f32) * WIDTH / 65535.0;
+
+        let y16 = u16::from_le_bytes([chunk[6], chunk[7]]);
+        let y = (y16 as f32) * HEIGHT / 65535.0;
+
+        out.push(x);
+        out.push(y);
+    }
+
+    packet_count
+}
+
+/// JSON配列形式のパケット列をパース
+pub fn decode_json_packets(json_data: &str) -> Result<Vec<JsonPacket>, serde_json::Error> {
+    serde_json::from_str(json_data)
+}
+
+/// JSONパケット列の座標を out に追加
+pub fn json_packets_to_coords(packets: &[JsonPacket], out: &mut Vec<f32>) {
+    out.reserve(packets.len() * 2);
+    for packet in packets {
+        out.push(packet.x as f32);
+        out.push(packet.y as f32);
+    }
+}
@@ -0,0 +1,126 @@
+// =============================================================================
+// EVENTS - シミュレーションイベントの記録担当
+// パケットのドロップ（原因・ノード付き）、ノードの飽和・回復、Waveの開始・終了、ステージ終了を時刻付きで記録する
+// JSは毎フレーム取り出してログに表示する（取り出されないまま溜まった分は古いものから捨てる）
+// =============================================================================
+
+use crate::queue::QueueDrop;
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// 取り出されるまで保持するイベント数の上限（超えたら古いものから捨てる）
+pub const MAX_PENDING_EVENTS: usize = 4096;
+
+/// パケットをドロップした原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    QueueFull,        // 待機キューが満杯（SYN Floodの占有を含む）
+    QueueEvicted,     // 優先度の高いパケットにキューから押し出された
+    RedEarlyDrop,     // REDの早期廃棄
+    CodelDrop,        // CoDelの滞在時間超過
+    NoRoute,          // 次に送れるノードがない
+    InvalidTarget,    // 向かっていたノードが存在しない
+    RouteTooLong,     // 通過履歴が上限を超えた（ループ等）
+    FirewallOverload, // Firewallの検査スループット超過
+    NodeDown,         // いたノードが停止した
+    NodeRemoved,      // いた・向かっていたノードが削除された
+    LinkLoss,         // リンクでの損失
+    LinkRemoved,      // 移動中のリンクが削除された
+    NoPacketSlot,     // 送り直す空きスロットがない
+    RateLimited,      // RateLimiterで拒否して捨てた（droppedではなくrate_limitedに数える）
+    Filtered,         // Firewallが遮断した（誤検知を含む。droppedではなくfilteredに数える）
+}
+
+/// イベントの種類と内容
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimEventKind {
+    /// パケットをドロップした（ノードに関係しない原因ならnode_idはnull）
+    /// RateLimited・Filteredは統計ではdroppedに含めない
+    PacketDropped {
+        node_id: Option<u32>,
+        reason: DropReason,
+        packet_type: u32,
+    },
+    /// ノードが飽和した（処理枠もキューも埋まった）
+    NodeSaturated { node_id: u32 },
+    /// 飽和していたノードが回復した（処理枠が空いたか、キューが半分まで空いた）
+    NodeRecovered { node_id: u32 },
+    /// Waveの生成を始めた
+    WaveStarted { wave_id: u32, count: u32 },
+    /// Waveのリクエストがすべて終わった（生成し終わり、応答待ち・送り直し予約も残っていない）
+    WaveFinished { wave_id: u32 },
+    /// ステージが終わった（すべてのWaveが発火し、処理中のものがなくなった）
+    StageFinished { passed: bool },
+    /// 取り出されないまま上限を超えて捨てたイベント数（取り出したときに先頭に付く）
+    EventsDiscarded { count: u32 },
+}
+
+/// 時刻付きのイベント
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimEvent {
+    pub time_ms: f64, // 起きた時点のシミュレーション時刻
+    #[serde(flatten)]
+    pub kind: SimEventKind,
+}
+
+/// 取り出されるまでのイベント
+#[derive(Clone, Debug, Default)]
+pub struct EventLog {
+    pending: VecDeque<SimEvent>,
+    discarded: u32, // 前回取り出してから捨てた数
+}
+
+impl EventLog {
+    /// イベントを記録（上限を超えたら最も古いものを捨てる）
+    pub fn push(&mut self, time_ms: f64, kind: SimEventKind) {
+        if self.pending.len() >= MAX_PENDING_EVENTS {
+            self.pending.pop_front();
+            self.discarded = self.discarded.saturating_add(1);
+        }
+        self.pending.push_back(SimEvent { time_ms, kind });
+    }
+
+    /// 記録したイベントを古い順にすべて取り出す
+    pub fn drain(&mut self) -> Vec<SimEvent> {
+        let mut events = Vec::with_capacity(self.pending.len() + 1);
+        if self.discarded > 0 {
+            let time_ms = self.pending.front().map_or(0.0, |e| e.time_ms);
+            events.push(SimEvent {
+                time_ms,
+                kind: SimEventKind::EventsDiscarded {
+                    count: self.discarded,
+                },
+            });
+            self.discarded = 0;
+        }
+        events.extend(self.pending.drain(..));
+        events
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.discarded = 0;
+    }
+}
+
+impl From<QueueDrop> for DropReason {
+    fn from(cause: QueueDrop) -> Self {
+        match cause {
+            QueueDrop::Tail => DropReason::QueueFull,
+            QueueDrop::Priority => DropReason::QueueEvicted,
+            QueueDrop::Red => DropReason::RedEarlyDrop,
+            QueueDrop::Codel => DropReason::CodelDrop,
+        }
+    }
+}
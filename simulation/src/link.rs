@@ -0,0 +1,317 @@
+// =============================================================================
+// LINK - ノード間リンク担当
+// リンクの伝送特性（伝搬遅延・帯域・損失率）と、リンク上を移動中のパケットの管理
+// 帯域はリンク上で送信中のパケットで等分し、サイズの大きいレスポンスほど長く占有する
+// 損失の判定とノードへの到達処理はSimulationStateが行う
+// =============================================================================
+
+use serde::{Deserialize, Serialize};
+
+// 現在の利用率を平滑化する時定数
+const UTILIZATION_TIME_CONSTANT_MS: f64 = 1000.0;
+
+/// リンクの伝送特性（ステージJSONではリンク定義に直接書く）
+/// すべて0なら従来どおりパケットの移動速度で移動する（リンクは経路と集計だけに使う）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkProfile {
+    pub latency_ms: f64, // 伝搬遅延（0 = 距離とパケットの移動速度から決まる）
+    pub bandwidth: f64,  // 帯域（パケットサイズ/秒。リクエスト = 1.0、レスポンス = 10.0。0 = 無制限）
+    pub loss_rate: f32,  // 損失率（0.0 - 1.0）
+}
+
+impl LinkProfile {
+    /// 伝送特性を持つか（持たないリンク上のパケットは移動速度で動く）
+    pub fn is_modeled(&self) -> bool {
+        self.latency_ms > 0.0 || self.bandwidth > 0.0 || self.loss_rate > 0.0
+    }
+}
+
+/// リンク上を移動中のパケット
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LinkTransit {
+    pub packet_idx: usize,
+    pub origin: [f32; 2],          // 送り出した位置（送信元ノード）
+    pub remaining_size: f64,       // 送信し終わっていないサイズ
+    pub remaining_latency_ms: f64, // 送信し終わってから届くまでの残り時間
+    pub elapsed_ms: f64,           // 送り出してからの時間
+    pub progress: f32,             // 描画用の進み具合（0.0 - 1.0、戻らない）
+}
+
+/// リンクの累計集計
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkStats {
+    pub sent: u32,           // 送り出したパケット数
+    pub delivered: u32,      // 届いたパケット数
+    pub lost: u32,           // 損失したパケット数
+    pub size_sent: f64,      // 送り出したサイズの合計
+    pub size_delivered: f64, // 届いたサイズの合計
+    pub busy_ms: f64,        // 帯域を使い切っていた時間（使った割合で按分）
+    pub observed_ms: f64,    // 集計を始めてからの時間
+    pub utilization: f64,    // 現在の利用率（平滑化、0.0 - 1.0）
+}
+
+/// ノード間リンク（有向エッジ）
+/// リクエストは from -> to の向きに流れ、レスポンスは通過履歴を逆順に辿る（同じリンクの帯域を共有する）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Link {
+    pub from_id: u32, // 送信元ノードID
+    pub to_id: u32,   // 宛先ノードID
+    pub profile: LinkProfile,
+    pub in_flight: Vec<LinkTransit>, // 移動中のパケット（伝送特性を持つリンクのみ）
+    pub stats: LinkStats,
+}
+
+/// リンクの状態（JS・ヘッドレス実行向け）
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LinkStatus {
+    pub from_id: u32,
+    pub to_id: u32,
+    pub in_flight: u32,
+    pub sent: u32,
+    pub delivered: u32,
+    pub lost: u32,
+    pub utilization: f64,     // 現在の利用率（平滑化）
+    pub avg_utilization: f64, // 集計開始からの平均利用率
+    pub throughput: f64,      // 集計開始からの平均スループット（届いたサイズ/秒）
+}
+
+impl Link {
+    pub fn new(from_id: u32, to_id: u32, profile: LinkProfile) -> Self {
+        Link {
+            from_id,
+            to_id,
+            profile,
+            in_flight: Vec::new(),
+            stats: LinkStats::default(),
+        }
+    }
+
+    /// 2つのノードを結ぶリンクか（向きは問わない）
+    pub fn connects(&self, a: u32, b: u32) -> bool {
+        (self.from_id == a && self.to_id == b) || (self.from_id == b && self.to_id == a)
+    }
+
+    /// パケットを送り出したことを記録（伝送特性がなければ集計だけ）
+    pub fn record_sent(&mut self, size: f32) {
+        self.stats.sent += 1;
+        self.stats.size_sent += size as f64;
+    }
+
+    /// パケットを送り出す（propagation_ms: latency_ms が0のときに使う伝搬遅延）
+    pub fn enter(&mut self, packet_idx: usize, origin: [f32; 2], size: f32, propagation_ms: f64) {
+        let latency_ms = if self.profile.latency_ms > 0.0 {
+            self.profile.latency_ms
+        } else {
+            propagation_ms
+        };
+        self.in_flight.push(LinkTransit {
+            packet_idx,
+            origin,
+            remaining_size: if self.profile.bandwidth > 0.0 { size as f64 } else { 0.0 },
+            remaining_latency_ms: latency_ms,
+            elapsed_ms: 0.0,
+            progress: 0.0,
+        });
+    }
+
+    /// 時間を進め、届いたパケットを delivered に追加して取り除く
+    /// 帯域は送信中のパケットで等分し、送り終わったパケットの余りは残りのパケットで分け直す
+    pub fn advance(&mut self, delta_ms: f64, delivered: &mut Vec<usize>) {
+        let mut tick_utilization = 0.0;
+        if self.profile.bandwidth > 0.0 {
+            let capacity = self.profile.bandwidth * delta_ms / 1000.0;
+            let mut budget = capacity;
+            loop {
+                let sending = self.in_flight.iter().filter(|t| t.remaining_size > 0.0).count();
+                if sending == 0 || budget <= 0.0 {
+                    break;
+                }
+                let share = budget / sending as f64;
+                let mut finished = false;
+                for transit in self.in_flight.iter_mut().filter(|t| t.remaining_size > 0.0) {
+                    let sent = transit.remaining_size.min(share);
+                    transit.remaining_size -= sent;
+                    budget -= sent;
+                    finished |= transit.remaining_size <= 0.0;
+                }
+                if !finished {
+                    break;
+                }
+            }
+            tick_utilization = ((capacity - budget.max(0.0)) / capacity).clamp(0.0, 1.0);
+        }
+        self.stats.busy_ms += delta_ms * tick_utilization;
+        self.stats.observed_ms += delta_ms;
+        let smoothing = 1.0 - (-delta_ms / UTILIZATION_TIME_CONSTANT_MS).exp();
+        self.stats.utilization += (tick_utilization - self.stats.utilization) * smoothing;
+
+        // 送信し終わったものは伝搬遅延を待つ
+        let sending = self.in_flight.iter().filter(|t| t.remaining_size > 0.0).count();
+        let rate_per_ms = if sending > 0 {
+            self.profile.bandwidth / 1000.0 / sending as f64
+        } else {
+            0.0
+        };
+        self.in_flight.retain_mut(|transit| {
+            transit.elapsed_ms += delta_ms;
+            if transit.remaining_size <= 0.0 {
+                transit.remaining_latency_ms -= delta_ms;
+                if transit.remaining_latency_ms <= 0.0 {
+                    delivered.push(transit.packet_idx);
+                    return false;
+                }
+            }
+            // 残り時間の見込みから進み具合を出す（後から送信中のパケットが増えても戻さない）
+            let remaining_ms = if transit.remaining_size > 0.0 {
+                transit.remaining_size / rate_per_ms + transit.remaining_latency_ms
+            } else {
+                transit.remaining_latency_ms
+            };
+            let progress = (transit.elapsed_ms / (transit.elapsed_ms + remaining_ms)) as f32;
+            transit.progress = transit.progress.max(progress);
+            true
+        });
+    }
+
+    /// 届いたパケットを集計（lost: 損失したか）
+    pub fn record_delivery(&mut self, size: f32, lost: bool) {
+        if lost {
+            self.stats.lost += 1;
+        } else {
+            self.stats.delivered += 1;
+            self.stats.size_delivered += size as f64;
+        }
+    }
+
+    /// 移動中のパケットを取り除く（ドロップ時）
+    pub fn remove_packet(&mut self, packet_idx: usize) {
+        self.in_flight.retain(|t| t.packet_idx != packet_idx);
+    }
+
+    /// 移動中のパケットと集計を捨てる
+    pub fn reset(&mut self) {
+        self.in_flight.clear();
+        self.stats = LinkStats::default();
+    }
+
+    pub fn status(&self) -> LinkStatus {
+        let stats = &self.stats;
+        let (avg_utilization, throughput) = if stats.observed_ms > 0.0 {
+            (
+                stats.busy_ms / stats.observed_ms,
+                stats.size_delivered * 1000.0 / stats.observed_ms,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        LinkStatus {
+            from_id: self.from_id,
+            to_id: self.to_id,
+            in_flight: self.in_flight.len() as u32,
+            sent: stats.sent,
+            delivered: stats.delivered,
+            lost: stats.lost,
+            utilization: stats.utilization,
+            avg_utilization,
+            throughput,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(latency_ms: f64, bandwidth: f64) -> Link {
+        Link::new(
+            0,
+            1,
+            LinkProfile {
+                latency_ms,
+                bandwidth,
+                loss_rate: 0.0,
+            },
+        )
+    }
+
+    // 1msずつ進め、届いた時刻（ms）をパケット順に返す
+    fn delivery_times(link: &mut Link, count: usize) -> Vec<u32> {
+        let mut times = vec![0; count];
+        let mut delivered = Vec::new();
+        for ms in 1..=1000 {
+            delivered.clear();
+            link.advance(1.0, &mut delivered);
+            for &idx in &delivered {
+                times[idx] = ms;
+            }
+            if link.in_flight.is_empty() {
+                return times;
+            }
+        }
+        panic!("packets were not delivered");
+    }
+
+    #[test]
+    fn bandwidth_is_shared_and_reallocated_when_a_packet_finishes() {
+        // 1000/秒 = 1msあたり1.0
+        let mut alone = link(0.0, 1000.0);
+        alone.enter(0, [0.0, 0.0], 10.0, 0.0);
+        assert_eq!(delivery_times(&mut alone, 1), vec![10]);
+
+        // 2つで等分し（0.5/ms）、先に送り終えた分の帯域は残りが使う
+        let mut shared = link(0.0, 1000.0);
+        shared.enter(0, [0.0, 0.0], 10.0, 0.0);
+        shared.enter(1, [0.0, 0.0], 30.0, 0.0);
+        assert_eq!(delivery_times(&mut shared, 2), vec![20, 40]);
+    }
+
+    #[test]
+    fn latency_overrides_the_propagation_delay() {
+        let mut modeled = link(50.0, 0.0);
+        modeled.enter(0, [0.0, 0.0], 10.0, 500.0);
+        assert_eq!(delivery_times(&mut modeled, 1), vec![50]);
+
+        // latency_ms が0なら距離と移動速度から決まる伝搬遅延を使う
+        let mut unmodeled = link(0.0, 0.0);
+        unmodeled.enter(0, [0.0, 0.0], 10.0, 30.0);
+        assert_eq!(delivery_times(&mut unmodeled, 1), vec![30]);
+    }
+
+    #[test]
+    fn progress_only_moves_forward() {
+        let mut link = link(0.0, 1000.0);
+        let mut delivered = Vec::new();
+        link.enter(0, [0.0, 0.0], 20.0, 0.0);
+        link.advance(5.0, &mut delivered);
+        let before = link.in_flight[0].progress;
+        assert!((before - 0.25).abs() < 1e-6);
+
+        // 後から送信中のパケットが増えて見込みが延びても、描画位置は戻らない
+        link.enter(1, [0.0, 0.0], 20.0, 0.0);
+        link.advance(1.0, &mut delivered);
+        assert!(link.in_flight[0].progress >= before);
+    }
+
+    #[test]
+    fn utilization_counts_the_used_share_of_the_bandwidth() {
+        let mut link = link(0.0, 1000.0);
+        let mut delivered = Vec::new();
+        link.record_sent(10.0);
+        link.enter(0, [0.0, 0.0], 10.0, 0.0);
+        for _ in 0..20 {
+            link.advance(1.0, &mut delivered);
+        }
+        for &idx in &delivered {
+            assert_eq!(idx, 0);
+            link.record_delivery(10.0, false);
+        }
+
+        // 20msのうち10msだけ帯域を使い切っていた
+        let status = link.status();
+        assert_eq!((status.sent, status.delivered, status.lost), (1, 1, 0));
+        assert!((status.avg_utilization - 0.5).abs() < 1e-9);
+        assert!((status.throughput - 500.0).abs() < 1e-9);
+        assert_eq!(status.in_flight, 0);
+    }
+}
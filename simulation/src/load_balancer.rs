@@ -0,0 +1,363 @@
+// =============================================================================
+// LOAD BALANCER - ロードバランシング戦略担当
+// LBノードごとに振り分けアルゴリズムと、その選択状態を管理する
+// =============================================================================
+
+use crate::rng::mix64;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// ロードバランシング戦略の列挙型
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum LbStrategy {
+    #[default]
+    LeastLoad = 0,          // 負荷率（(処理中 + キュー) / 同時処理数）が最小のノード
+    RoundRobin = 1,         // 順番に振り分け
+    WeightedRoundRobin = 2, // 同時処理数を重みとした平滑化重み付きラウンドロビン
+    Random = 3,             // ランダムに振り分け
+    PowerOfTwoChoices = 4,  // ランダムに2つ選び、負荷率の低い方
+    LeastConnections = 5,   // 処理中 + キューの絶対数が最小のノード
+    ConsistentHash = 6,     // クライアントキーによる一貫性ハッシュ（Rendezvous方式）
+}
+
+impl LbStrategy {
+    /// 数値（JSから渡される値）から戦略を取得
+    pub fn from_u32(value: u32) -> Option<LbStrategy> {
+        match value {
+            0 => Some(LbStrategy::LeastLoad),
+            1 => Some(LbStrategy::RoundRobin),
+            2 => Some(LbStrategy::WeightedRoundRobin),
+            3 => Some(LbStrategy::Random),
+            4 => Some(LbStrategy::PowerOfTwoChoices),
+            5 => Some(LbStrategy::LeastConnections),
+            6 => Some(LbStrategy::ConsistentHash),
+            _ => None,
+        }
+    }
+
+    /// 名前（ステージJSONの値）から戦略を取得
+    pub fn from_name(name: &str) -> Option<LbStrategy> {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "least_load" => Some(LbStrategy::LeastLoad),
+            "round_robin" | "rr" => Some(LbStrategy::RoundRobin),
+            "weighted_round_robin" | "wrr" => Some(LbStrategy::WeightedRoundRobin),
+            "random" => Some(LbStrategy::Random),
+            "power_of_two_choices" | "p2c" => Some(LbStrategy::PowerOfTwoChoices),
+            "least_connections" => Some(LbStrategy::LeastConnections),
+            "consistent_hash" => Some(LbStrategy::ConsistentHash),
+            _ => None,
+        }
+    }
+}
+
+/// 振り分け先の候補ノード
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+    pub node_idx: usize,  // ノードのインデックス
+    pub node_id: u32,     // ノードID（ハッシュ・重み状態のキー）
+    pub load_rate: f32,   // (処理中 + キュー) / 同時処理数
+    pub connections: u32, // 処理中 + キュー
+    pub weight: u32,      // 重み（同時処理数）
+}
+
+/// LBノードごとの選択状態
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LbState {
+    rr_cursor: usize,             // ラウンドロビンの次の位置
+    wrr_current: Vec<(u32, i64)>, // 重み付きラウンドロビンの現在重み (node_id, current_weight)
+}
+
+impl LbState {
+    /// 戦略に従って候補からノードを1つ選び、そのインデックスを返す
+    /// random: [0, 1) の乱数を返す関数
+    pub fn select(
+        &mut self,
+        strategy: LbStrategy,
+        candidates: &[Candidate],
+        client_key: u32,
+        random: &mut dyn FnMut() -> f32,
+    ) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let chosen = match strategy {
+            LbStrategy::LeastLoad => min_by_key_f32(candidates, |c| c.load_rate),
+            LbStrategy::RoundRobin => {
+                let c = &candidates[self.rr_cursor % candidates.len()];
+                self.rr_cursor = self.rr_cursor.wrapping_add(1);
+                c
+            }
+            LbStrategy::WeightedRoundRobin => self.select_weighted(candidates),
+            LbStrategy::Random => &candidates[random_index(random, candidates.len())],
+            LbStrategy::PowerOfTwoChoices => {
+                let first = random_index(random, candidates.len());
+                if candidates.len() == 1 {
+                    return Some(candidates[first].node_idx);
+                }
+                // 2つ目は残りの len - 1 個から選び、1つ目以降はずらして重複を避ける
+                let mut second = random_index(random, candidates.len() - 1);
+                if second >= first {
+                    second += 1;
+                }
+                let (a, b) = (&candidates[first], &candidates[second]);
+                if b.load_rate < a.load_rate {
+                    b
+                } else {
+                    a
+                }
+            }
+            LbStrategy::LeastConnections => min_by_key_f32(candidates, |c| c.connections as f32),
+            LbStrategy::ConsistentHash => candidates
+                .iter()
+                .max_by_key(|c| mix64(((client_key as u64) << 32) | c.node_id as u64))
+                .unwrap_or(&candidates[0]),
+        };
+
+        Some(chosen.node_idx)
+    }
+
+    /// 平滑化重み付きラウンドロビン（nginx方式）
+    /// 各候補の現在重みに重みを加算し、最大のものを選んで合計重みを引く
+    fn select_weighted<'a>(&mut self, candidates: &'a [Candidate]) -> &'a Candidate {
+        // 候補から外れたノードの状態を捨てる
+        self.wrr_current
+            .retain(|(id, _)| candidates.iter().any(|c| c.node_id == *id));
+
+        let total: i64 = candidates.iter().map(|c| c.weight.max(1) as i64).sum();
+        let mut best: Option<(usize, i64)> = None;
+
+        for (i, c) in candidates.iter().enumerate() {
+            let entry = match self.wrr_current.iter_mut().find(|(id, _)| *id == c.node_id) {
+                Some(entry) => entry,
+                None => {
+                    self.wrr_current.push((c.node_id, 0));
+                    self.wrr_current.last_mut().unwrap()
+                }
+            };
+            entry.1 += c.weight.max(1) as i64;
+            if best.is_none_or(|(_, w)| entry.1 > w) {
+                best = Some((i, entry.1));
+            }
+        }
+
+        let (best_idx, _) = best.unwrap_or((0, 0));
+        let best_id = candidates[best_idx].node_id;
+        if let Some(entry) = self.wrr_current.iter_mut().find(|(id, _)| *id == best_id) {
+            entry.1 -= total;
+        }
+        &candidates[best_idx]
+    }
+}
+
+/// 指定キーが最小の候補を返す（同値なら先頭側）
+fn min_by_key_f32(candidates: &[Candidate], key: impl Fn(&Candidate) -> f32) -> &Candidate {
+    candidates
+        .iter()
+        .min_by(|a, b| key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or(&candidates[0])
+}
+
+/// [0, len) の乱数インデックス
+fn random_index(random: &mut dyn FnMut() -> f32, len: usize) -> usize {
+    ((random() * len as f32) as usize).min(len - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(node_id: u32, load_rate: f32, connections: u32, weight: u32) -> Candidate {
+        Candidate {
+            node_idx: node_id as usize,
+            node_id,
+            load_rate,
+            connections,
+            weight,
+        }
+    }
+
+    // 乱数を使わない戦略用
+    fn no_random() -> f32 {
+        panic!("random should not be used")
+    }
+
+    fn pick(
+        state: &mut LbState,
+        strategy: LbStrategy,
+        candidates: &[Candidate],
+        key: u32,
+    ) -> usize {
+        state
+            .select(strategy, candidates, key, &mut no_random)
+            .unwrap()
+    }
+
+    // 決められた値を順番に返す乱数
+    fn scripted(values: &[f32]) -> impl FnMut() -> f32 + '_ {
+        let mut iter = values.iter();
+        move || *iter.next().expect("random called too many times")
+    }
+
+    #[test]
+    fn empty_candidates_select_nothing() {
+        let mut state = LbState::default();
+        assert_eq!(
+            state.select(LbStrategy::RoundRobin, &[], 0, &mut no_random),
+            None
+        );
+    }
+
+    #[test]
+    fn round_robin_cycles_in_order() {
+        let candidates = [
+            candidate(1, 0.0, 0, 1),
+            candidate(2, 0.9, 9, 1),
+            candidate(3, 0.5, 5, 1),
+        ];
+        let mut state = LbState::default();
+        let order: Vec<usize> = (0..7)
+            .map(|_| pick(&mut state, LbStrategy::RoundRobin, &candidates, 0))
+            .collect();
+        // 負荷に関係なく順番に回る
+        assert_eq!(order, vec![1, 2, 3, 1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn smooth_weighted_round_robin_interleaves_by_weight() {
+        let candidates = [candidate(1, 0.0, 0, 3), candidate(2, 0.0, 0, 1)];
+        let mut state = LbState::default();
+        let order: Vec<usize> = (0..8)
+            .map(|_| pick(&mut state, LbStrategy::WeightedRoundRobin, &candidates, 0))
+            .collect();
+        // 3:1 を固めずに散らして配る（nginx方式の並び）
+        assert_eq!(order, vec![1, 1, 2, 1, 1, 1, 2, 1]);
+    }
+
+    #[test]
+    fn consistent_hash_keeps_keys_when_a_candidate_is_removed() {
+        let all: Vec<Candidate> = (1..=5).map(|id| candidate(id, 0.0, 0, 1)).collect();
+        let mut state = LbState::default();
+        let before: Vec<usize> = (0..200)
+            .map(|key| pick(&mut state, LbStrategy::ConsistentHash, &all, key))
+            .collect();
+        // 同じキーは何度選んでも同じノードへ
+        for (key, &chosen) in before.iter().enumerate() {
+            assert_eq!(
+                pick(&mut state, LbStrategy::ConsistentHash, &all, key as u32),
+                chosen
+            );
+        }
+
+        // ノード3を外しても、3以外に割り当てられていたキーは動かない
+        let without: Vec<Candidate> = all.iter().copied().filter(|c| c.node_id != 3).collect();
+        let mut moved = 0;
+        for (key, &chosen) in before.iter().enumerate() {
+            let after = pick(&mut state, LbStrategy::ConsistentHash, &without, key as u32);
+            if chosen == 3 {
+                assert_ne!(after, 3);
+                moved += 1;
+            } else {
+                assert_eq!(after, chosen);
+            }
+        }
+        assert!(moved > 0);
+    }
+
+    #[test]
+    fn least_load_breaks_ties_toward_the_first_candidate() {
+        let candidates = [
+            candidate(1, 0.5, 4, 8),
+            candidate(2, 0.25, 4, 16),
+            candidate(3, 0.25, 2, 8),
+        ];
+        let mut state = LbState::default();
+        assert_eq!(pick(&mut state, LbStrategy::LeastLoad, &candidates, 0), 2);
+        assert_eq!(
+            pick(&mut state, LbStrategy::LeastConnections, &candidates, 0),
+            3
+        );
+
+        let tied = [candidate(4, 0.5, 3, 1), candidate(5, 0.5, 3, 1)];
+        assert_eq!(pick(&mut state, LbStrategy::LeastLoad, &tied, 0), 4);
+        assert_eq!(pick(&mut state, LbStrategy::LeastConnections, &tied, 0), 4);
+    }
+
+    #[test]
+    fn random_maps_the_draw_onto_a_candidate() {
+        let candidates = [
+            candidate(1, 0.0, 0, 1),
+            candidate(2, 0.0, 0, 1),
+            candidate(3, 0.0, 0, 1),
+        ];
+        let mut state = LbState::default();
+        let draws = [0.0, 0.34, 0.67, 0.999_999];
+        let mut random = scripted(&draws);
+        let order: Vec<usize> = (0..draws.len())
+            .map(|_| {
+                state
+                    .select(LbStrategy::Random, &candidates, 0, &mut random)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(order, vec![1, 2, 3, 3]);
+    }
+
+    #[test]
+    fn power_of_two_choices_compares_two_distinct_candidates() {
+        let candidates = [
+            candidate(1, 0.9, 0, 1),
+            candidate(2, 0.1, 0, 1),
+            candidate(3, 0.5, 0, 1),
+        ];
+        let mut state = LbState::default();
+
+        // 同じ位置を2回引いても、2つ目は次の候補へずれる
+        let mut random = scripted(&[0.0, 0.0]);
+        assert_eq!(
+            state.select(LbStrategy::PowerOfTwoChoices, &candidates, 0, &mut random),
+            Some(2)
+        );
+        let mut random = scripted(&[0.5, 0.6]);
+        assert_eq!(
+            state.select(LbStrategy::PowerOfTwoChoices, &candidates, 0, &mut random),
+            Some(2)
+        );
+        // 1つ目より前を引いた場合はそのまま
+        let mut random = scripted(&[0.9, 0.0]);
+        assert_eq!(
+            state.select(LbStrategy::PowerOfTwoChoices, &candidates, 0, &mut random),
+            Some(3)
+        );
+
+        // 候補が1つなら乱数は1回だけ
+        let single = [candidate(4, 0.5, 0, 1)];
+        let mut random = scripted(&[0.7]);
+        assert_eq!(
+            state.select(LbStrategy::PowerOfTwoChoices, &single, 0, &mut random),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn power_of_two_choices_never_picks_the_same_pair_member_twice() {
+        let candidates = [candidate(1, 0.2, 0, 1), candidate(2, 0.8, 0, 1)];
+        let mut state = LbState::default();
+        let mut seed = 12345u64;
+        let mut random = move || {
+            seed = mix64(seed);
+            (seed >> 40) as f32 / (1u64 << 24) as f32
+        };
+        // 候補2つなら必ず両方を比べるので、負荷の低い1が常に選ばれる
+        for _ in 0..200 {
+            assert_eq!(
+                state.select(LbStrategy::PowerOfTwoChoices, &candidates, 0, &mut random),
+                Some(1)
+            );
+        }
+    }
+}
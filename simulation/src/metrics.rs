@@ -0,0 +1,311 @@
+// =============================================================================
+// METRICS - シミュレーション統計・レイテンシ計測担当
+// =============================================================================
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// シミュレーション統計
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct SimulationStats {
+    pub packets_spawned: u32,   // 生成されたパケット総数
+    pub packets_processed: u32, // 正常に処理完了したパケット数（レスポンスがGatewayに到達）
+    pub packets_dropped: u32,   // ドロップ/失敗したパケット数
+    pub packets_in_flight: u32, // 現在処理中のパケット数
+    pub attacks_spawned: u32,   // 生成された攻撃パケット数（SynFlood / Killer、spawnedに含まれる）
+    pub attacks_absorbed: u32,  // 応答せずに消えた攻撃パケット数（SYN占有の終了・Killerの着弾）
+    pub packets_filtered: u32,  // Firewallで遮断されたパケット数（誤検知を含む、droppedには含まない）
+    pub attacks_filtered: u32,  // うち攻撃パケット数（packets_filtered - attacks_filtered = 誤検知）
+    pub cache_hits: u32,        // Cacheでヒットしたリクエスト数
+    pub cache_misses: u32,      // Cacheでミスしたリクエスト数
+    pub packets_rate_limited: u32, // RateLimiterで拒否されたリクエスト数（429応答を含む、droppedには含まない）
+    pub packets_timed_out: u32, // クライアントがタイムアウトした回数（送り直しごとに数える）
+    pub packets_retried: u32,   // クライアントが送り直したリクエスト数（spawnedには含まない）
+    pub queue_tail_drops: u32,     // キューが満杯でドロップした数（droppedに含まれる、以下同様）
+    pub queue_priority_drops: u32, // 優先度の高いパケットに押し出された数
+    pub queue_red_drops: u32,      // REDの早期廃棄でドロップした数
+    pub queue_codel_drops: u32,    // CoDelの滞在時間超過でドロップした数
+}
+
+// =============================================================================
+// LATENCY HISTOGRAM - レイテンシ分布（HDR方式の対数線形バケット）
+// =============================================================================
+
+// 2のべき乗ごとのサブバケット数のビット数（32分割 = 相対誤差 約3%）
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+
+/// レイテンシのヒストグラム
+/// 値はマイクロ秒単位で記録し、2のべき乗の区間をそれぞれ SUB_BUCKET_COUNT 個に等分する
+/// メモリは記録された最大値に応じて伸びる（1時間 = 約1000バケット）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    counts: Vec<u32>,
+    total_count: u64,
+    sum_us: u64,
+    min_us: u64,
+    max_us: u64,
+}
+
+/// パーセンタイルのレポート（ミリ秒）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct LatencyReport {
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// レイテンシ（ミリ秒）を1件記録
+    pub fn record(&mut self, latency_ms: f64) {
+        let value_us = (latency_ms.max(0.0) * 1000.0).round() as u64;
+        let idx = bucket_index(value_us);
+        if idx >= self.counts.len() {
+            self.counts.resize(idx + 1, 0);
+        }
+        self.counts[idx] += 1;
+
+        if self.total_count == 0 || value_us < self.min_us {
+            self.min_us = value_us;
+        }
+        self.max_us = self.max_us.max(value_us);
+        self.total_count += 1;
+        self.sum_us += value_us;
+    }
+
+    /// 記録件数
+    pub fn count(&self) -> u64 {
+        self.total_count
+    }
+
+    /// 平均（ミリ秒）
+    pub fn mean_ms(&self) -> f64 {
+        if self.total_count == 0 {
+            return 0.0;
+        }
+        self.sum_us as f64 / self.total_count as f64 / 1000.0
+    }
+
+    /// 最大値（ミリ秒、正確な値）
+    pub fn max_ms(&self) -> f64 {
+        self.max_us as f64 / 1000.0
+    }
+
+    /// パーセンタイル（ミリ秒）
+    /// percentile: 0.0〜100.0。該当バケットの中央値を返す（最小・最大の範囲に丸める）
+    pub fn percentile_ms(&self, percentile: f64) -> f64 {
+        if self.total_count == 0 {
+            return 0.0;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.total_count as f64).ceil() as u64;
+        let rank = rank.max(1);
+
+        let mut seen = 0u64;
+        for (idx, &count) in self.counts.iter().enumerate() {
+            seen += count as u64;
+            if seen >= rank {
+                let (low, high) = bucket_range(idx);
+                let value_us = (low + (high - low) / 2).clamp(self.min_us, self.max_us);
+                return value_us as f64 / 1000.0;
+            }
+        }
+        self.max_ms()
+    }
+
+    /// p50 / p90 / p99 / max をまとめて取得
+    pub fn report(&self) -> LatencyReport {
+        LatencyReport {
+            count: self.total_count,
+            mean_ms: self.mean_ms(),
+            p50_ms: self.percentile_ms(50.0),
+            p90_ms: self.percentile_ms(90.0),
+            p99_ms: self.percentile_ms(99.0),
+            max_ms: self.max_ms(),
+        }
+    }
+
+    /// 記録をすべて消去
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// 値（マイクロ秒）からバケットのインデックスを求める
+/// 0..SUB_BUCKET_COUNT は1刻み、それ以上は最上位ビット以下 SUB_BUCKET_BITS ビットで分割
+fn bucket_index(value_us: u64) -> usize {
+    if value_us < SUB_BUCKET_COUNT {
+        return value_us as usize;
+    }
+    let msb = 63 - value_us.leading_zeros();
+    let shift = msb - SUB_BUCKET_BITS;
+    (shift as u64 * SUB_BUCKET_COUNT + (value_us >> shift)) as usize
+}
+
+/// バケットが表す値の範囲 [low, high]（マイクロ秒）
+fn bucket_range(idx: usize) -> (u64, u64) {
+    let idx = idx as u64;
+    if idx < SUB_BUCKET_COUNT * 2 {
+        return (idx, idx);
+    }
+    let shift = idx / SUB_BUCKET_COUNT - 1;
+    let sub = idx - shift * SUB_BUCKET_COUNT;
+    let low = sub << shift;
+    (low, low + (1 << shift) - 1)
+}
+
+/// 全体とWaveごとのレイテンシ
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LatencyMetrics {
+    pub overall: LatencyHistogram,
+    pub per_wave: Vec<LatencyHistogram>, // wave_id -> ヒストグラム
+}
+
+/// レイテンシレポート全体（JSON出力用）
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LatencySummary {
+    pub overall: LatencyReport,
+    pub waves: Vec<WaveLatencyReport>,
+}
+
+/// Waveごとのレイテンシレポート
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WaveLatencyReport {
+    pub wave_id: u32,
+    #[serde(flatten)]
+    pub report: LatencyReport,
+}
+
+impl LatencyMetrics {
+    /// 新しいWaveのヒストグラムを確保し、wave_idを返す
+    pub fn register_wave(&mut self) -> u32 {
+        self.per_wave.push(LatencyHistogram::new());
+        (self.per_wave.len() - 1) as u32
+    }
+
+    /// レイテンシを記録（wave_idが範囲外の場合は全体のみ）
+    pub fn record(&mut self, wave_id: u32, latency_ms: f64) {
+        self.overall.record(latency_ms);
+        if let Some(hist) = self.per_wave.get_mut(wave_id as usize) {
+            hist.record(latency_ms);
+        }
+    }
+
+    /// Waveのヒストグラムを取得
+    pub fn wave(&self, wave_id: u32) -> Option<&LatencyHistogram> {
+        self.per_wave.get(wave_id as usize)
+    }
+
+    /// 記録を消去（登録済みのWaveは残す）
+    pub fn clear_records(&mut self) {
+        self.overall.clear();
+        for hist in self.per_wave.iter_mut() {
+            hist.clear();
+        }
+    }
+
+    /// 全体とWaveごとのレポート
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            overall: self.overall.report(),
+            waves: self
+                .per_wave
+                .iter()
+                .enumerate()
+                .map(|(id, hist)| WaveLatencyReport {
+                    wave_id: id as u32,
+                    report: hist.report(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(samples: &[(f64, usize)]) -> LatencyHistogram {
+        let mut hist = LatencyHistogram::new();
+        for &(latency_ms, count) in samples {
+            for _ in 0..count {
+                hist.record(latency_ms);
+            }
+        }
+        hist
+    }
+
+    #[test]
+    fn empty_histogram_reports_zero() {
+        let hist = LatencyHistogram::new();
+        assert_eq!(hist.report(), LatencyReport::default());
+        assert_eq!(hist.percentile_ms(99.0), 0.0);
+    }
+
+    #[test]
+    fn single_sample_is_every_percentile() {
+        let hist = histogram(&[(12.345, 1)]);
+        // バケットの中央値は最小・最大に丸められるので、1件なら正確な値になる
+        let report = hist.report();
+        assert_eq!(report.count, 1);
+        assert_eq!(report.mean_ms, 12.345);
+        assert_eq!(report.p50_ms, 12.345);
+        assert_eq!(report.p90_ms, 12.345);
+        assert_eq!(report.p99_ms, 12.345);
+        assert_eq!(report.max_ms, 12.345);
+        assert_eq!(hist.percentile_ms(0.0), 12.345);
+    }
+
+    #[test]
+    fn p99_switches_bucket_at_the_rank_boundary() {
+        // 1000件中990件目までが10ms → p99は10msのバケット
+        let hist = histogram(&[(10.0, 990), (1000.0, 10)]);
+        let p99 = hist.percentile_ms(99.0);
+        assert!(
+            (p99 - 10.0).abs() / 10.0 < 1.0 / SUB_BUCKET_COUNT as f64,
+            "{p99}"
+        );
+
+        // 1件ずらすと990件目が1000msになる（最大値に丸められる）
+        let hist = histogram(&[(10.0, 989), (1000.0, 11)]);
+        assert_eq!(hist.percentile_ms(99.0), 1000.0);
+        assert_eq!(hist.percentile_ms(100.0), 1000.0);
+        assert_eq!(hist.max_ms(), 1000.0);
+    }
+
+    #[test]
+    fn small_values_are_exact() {
+        // SUB_BUCKET_COUNT * 2 未満のマイクロ秒は1刻みのバケット
+        let hist = histogram(&[(0.001, 1), (0.002, 1), (0.063, 1)]);
+        assert_eq!(hist.percentile_ms(1.0), 0.001);
+        assert_eq!(hist.percentile_ms(50.0), 0.002);
+        assert_eq!(hist.percentile_ms(100.0), 0.063);
+    }
+
+    #[test]
+    fn buckets_are_contiguous_and_contain_their_values() {
+        let mut expected_low = 0;
+        for idx in 0..(SUB_BUCKET_COUNT as usize * 20) {
+            let (low, high) = bucket_range(idx);
+            if idx >= SUB_BUCKET_COUNT as usize && idx < SUB_BUCKET_COUNT as usize * 2 {
+                // 2つ目の区間は1つ目と同じ1刻み（インデックスと値が一致）
+                assert_eq!((low, high), (idx as u64, idx as u64));
+            }
+            assert_eq!(low, expected_low, "bucket {idx}");
+            assert!(high >= low);
+            assert_eq!(bucket_index(low), idx);
+            assert_eq!(bucket_index(high), idx);
+            // 相対誤差は 1 / SUB_BUCKET_COUNT 以内
+            assert!((high - low) * SUB_BUCKET_COUNT <= low.max(1));
+            expected_low = high + 1;
+        }
+    }
+}
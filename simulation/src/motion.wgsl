@@ -0,0 +1,96 @@
+// =============================================================================
+// WGSL Compute Shader - パケットの移動（GPU移動モード）
+// 1スレッド = 1スロット。移動中のパケットをターゲットノードへ進め、
+// 到達・消滅したものだけをイベントとして書き出す（CPUはイベントだけを読み戻す）
+// =============================================================================
+
+struct Params {
+    count: u32,          // 処理するスロット数
+    node_count: u32,
+    max_events: u32,     // events.items に書ける最大数
+    delta_ms: f32,       // 前回の移動からの経過時間（ミリ秒）
+    bounds_min: vec2<f32>, // 座標指定モードで消滅する範囲（画面外）
+    bounds_max: vec2<f32>,
+}
+
+// CPUから送る1スロット分の移動状態（Rust側の GpuPacketMotion と同じレイアウト）
+struct PacketMotion {
+    velocity: vec2<f32>, // 座標指定モードの速度（ピクセル/ミリ秒）
+    speed: f32,          // ノード指定モードの移動速度（ピクセル/ミリ秒）
+    target_node: i32,    // -1 = 座標指定モード
+    seq: u32,            // CPU側の移動状態の番号（イベントにそのまま載せる）
+    moving: u32,         // 1 = 移動中（到達・消滅したらGPUが0にする）
+    _padding: vec2<u32>,
+}
+
+struct MotionEvent {
+    slot: u32,
+    seq: u32,
+    kind: u32, // 0 = 到達, 1 = 消滅
+    _padding: u32,
+}
+
+struct EventLog {
+    count: atomic<u32>,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+    items: array<MotionEvent>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> positions: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> motion: array<PacketMotion>;
+@group(0) @binding(3) var<storage, read> nodes: array<vec2<f32>>;
+@group(0) @binding(4) var<storage, read_write> events: EventLog;
+
+// 到達判定の半径（simulation.rs の ARRIVAL_RADIUS と同じ）
+const ARRIVAL_RADIUS: f32 = 5.0;
+
+const EVENT_ARRIVED: u32 = 0u;
+const EVENT_VANISHED: u32 = 1u;
+
+// イベントを書き出して移動を止める（書ききれない場合は止めずに次の回で書き直す）
+fn emit(slot: u32, kind: u32) {
+    let i = atomicAdd(&events.count, 1u);
+    if (i < params.max_events) {
+        events.items[i] = MotionEvent(slot, motion[slot].seq, kind, 0u);
+        motion[slot].moving = 0u;
+    }
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let slot = id.x;
+    if (slot >= params.count || motion[slot].moving == 0u) {
+        return;
+    }
+
+    let m = motion[slot];
+    var pos = positions[slot];
+    if (m.target_node >= 0 && u32(m.target_node) < params.node_count) {
+        // ノード指定モード: ターゲットへ向かって speed * delta_ms だけ進む
+        // 到達半径に入るならノード位置で止める（通り過ぎない）
+        let target = nodes[m.target_node];
+        let delta = target - pos;
+        let dist = length(delta);
+        let step = m.speed * params.delta_ms;
+        if (dist < ARRIVAL_RADIUS || step >= dist - ARRIVAL_RADIUS) {
+            positions[slot] = target;
+            emit(slot, EVENT_ARRIVED);
+            return;
+        }
+        pos += (delta / dist) * step;
+    } else if (m.target_node == -1) {
+        // 座標指定モード: velocity * delta_ms で進み、画面外に出たら消滅
+        pos += m.velocity * params.delta_ms;
+        if (any(pos < params.bounds_min) || any(pos > params.bounds_max)) {
+            emit(slot, EVENT_VANISHED);
+        }
+    } else {
+        // ターゲットが無効ならその場で消滅
+        emit(slot, EVENT_VANISHED);
+        return;
+    }
+    positions[slot] = pos;
+}
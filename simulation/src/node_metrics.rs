@@ -0,0 +1,230 @@
+// =============================================================================
+// NODE METRICS - ノードごとの時系列メトリクス担当
+// 一定間隔ごとにスループット・キュー長・利用率・ドロップ数・平均処理時間を固定長のリングバッファへ記録する
+// バッファは f32 の連続領域なので、JSはWasmメモリ上をそのままFloat32Arrayで参照できる（コピー不要）
+// =============================================================================
+
+use serde::{Deserialize, Serialize};
+
+// 1サンプルの項目数（並びは NodeMetricField の順）
+pub const NODE_METRIC_FIELDS: usize = 6;
+
+// デフォルトの記録間隔とサンプル数（1秒ごと・直近2分）
+pub const DEFAULT_METRICS_INTERVAL_MS: f64 = 1000.0;
+pub const DEFAULT_METRICS_CAPACITY: usize = 120;
+
+/// 1サンプル内の項目の位置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeMetricField {
+    TimeMs = 0,       // 記録した時刻（シミュレーション時刻）
+    Throughput = 1,   // 処理完了数/秒（記録間隔での平均）
+    QueueDepth = 2,   // 記録した時点の待機キュー長
+    Utilization = 3,  // 処理枠の使用率（記録間隔での平均、0.0 - 1.0）
+    Drops = 4,        // 記録間隔でのドロップ数
+    AvgServiceMs = 5, // 記録間隔で処理完了したパケットの平均処理時間（完了なしなら0）
+}
+
+/// 記録間隔とサンプル数（全ノード共通）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeMetricsConfig {
+    pub interval_ms: f64, // 記録間隔
+    pub capacity: usize,  // リングバッファのサンプル数（古いものから上書き）
+}
+
+impl Default for NodeMetricsConfig {
+    fn default() -> Self {
+        NodeMetricsConfig {
+            interval_ms: DEFAULT_METRICS_INTERVAL_MS,
+            capacity: DEFAULT_METRICS_CAPACITY,
+        }
+    }
+}
+
+/// 1ノード分の記録（集計中の値とリングバッファ）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NodeMetrics {
+    samples: Vec<f32>, // [time, throughput, queue, utilization, drops, service] × capacity（最初の記録で確保）
+    head: usize,       // 次に書き込むサンプル位置（満杯なら最も古いサンプル）
+    count: usize,      // 記録済みのサンプル数（capacityで頭打ち）
+    // 集計中の記録間隔
+    last_processed: u32, // 前回記録した時点の処理完了数（Node.total_processed）
+    last_dropped: u32,   // 前回記録した時点のドロップ数（Node.total_dropped）
+    busy_ms: f64,        // 処理中パケット数 × 経過時間 の合計
+    service_ms: f64,     // 処理完了したパケットの処理時間の合計
+    service_count: u32,  // 処理完了したパケット数
+}
+
+impl NodeMetrics {
+    /// tickごとに処理枠の使用状況を足し込む
+    pub fn accumulate_busy(&mut self, processing: usize, delta_ms: f64) {
+        self.busy_ms += processing as f64 * delta_ms;
+    }
+
+    /// 処理完了したパケットの処理時間を足し込む
+    pub fn record_service(&mut self, process_time_ms: f64) {
+        self.service_ms += process_time_ms;
+        self.service_count += 1;
+    }
+
+    /// 記録間隔ぶんの集計を1サンプルとして書き込み、次の集計を始める
+    /// window_ms: 前回の記録からの経過時間
+    #[allow(clippy::too_many_arguments)]
+    pub fn push_sample(
+        &mut self,
+        capacity: usize,
+        time_ms: f64,
+        window_ms: f64,
+        total_processed: u32,
+        total_dropped: u32,
+        queue_depth: usize,
+        max_concurrent: u32,
+    ) {
+        if capacity == 0 {
+            return;
+        }
+        if self.samples.len() != capacity * NODE_METRIC_FIELDS {
+            self.samples = vec![0.0; capacity * NODE_METRIC_FIELDS];
+            self.head = 0;
+            self.count = 0;
+        }
+
+        let processed = total_processed.wrapping_sub(self.last_processed);
+        let dropped = total_dropped.wrapping_sub(self.last_dropped);
+        let window_ms = window_ms.max(f64::EPSILON);
+        let utilization = if max_concurrent > 0 {
+            (self.busy_ms / (max_concurrent as f64 * window_ms)).min(1.0)
+        } else {
+            0.0
+        };
+        let avg_service_ms = if self.service_count > 0 {
+            self.service_ms / self.service_count as f64
+        } else {
+            0.0
+        };
+
+        let base = self.head * NODE_METRIC_FIELDS;
+        let sample = &mut self.samples[base..base + NODE_METRIC_FIELDS];
+        sample[NodeMetricField::TimeMs as usize] = time_ms as f32;
+        sample[NodeMetricField::Throughput as usize] =
+            (processed as f64 * 1000.0 / window_ms) as f32;
+        sample[NodeMetricField::QueueDepth as usize] = queue_depth as f32;
+        sample[NodeMetricField::Utilization as usize] = utilization as f32;
+        sample[NodeMetricField::Drops as usize] = dropped as f32;
+        sample[NodeMetricField::AvgServiceMs as usize] = avg_service_ms as f32;
+
+        self.head = (self.head + 1) % capacity;
+        self.count = (self.count + 1).min(capacity);
+        self.start_window(total_processed, total_dropped);
+    }
+
+    /// 集計中の値を捨てて、今の累計から集計をやり直す
+    pub fn start_window(&mut self, total_processed: u32, total_dropped: u32) {
+        self.last_processed = total_processed;
+        self.last_dropped = total_dropped;
+        self.busy_ms = 0.0;
+        self.service_ms = 0.0;
+        self.service_count = 0;
+    }
+
+    /// 記録をすべて消す（バッファは次の記録で確保し直す）
+    pub fn clear(&mut self) {
+        *self = NodeMetrics::default();
+    }
+
+    /// リングバッファの先頭ポインタ（JSからゼロコピーで参照する用、未記録ならnull）
+    pub fn as_ptr(&self) -> *const f32 {
+        if self.samples.is_empty() {
+            std::ptr::null()
+        } else {
+            self.samples.as_ptr()
+        }
+    }
+
+    /// 次に書き込むサンプル位置（満杯なら最も古いサンプル）
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    /// 記録済みのサンプル数
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// 古い順にサンプルを返す
+    pub fn samples(&self) -> impl Iterator<Item = &[f32]> + '_ {
+        let capacity = self.samples.len() / NODE_METRIC_FIELDS;
+        let start = (self.head + capacity - self.count) % capacity.max(1);
+        (0..self.count).map(move |i| {
+            let base = ((start + i) % capacity) * NODE_METRIC_FIELDS;
+            &self.samples[base..base + NODE_METRIC_FIELDS]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(metrics: &NodeMetrics) -> Vec<f32> {
+        metrics
+            .samples()
+            .map(|sample| sample[NodeMetricField::TimeMs as usize])
+            .collect()
+    }
+
+    #[test]
+    fn ring_buffer_wraps_and_keeps_the_newest_samples() {
+        let mut metrics = NodeMetrics::default();
+        assert!(metrics.as_ptr().is_null());
+        assert_eq!((metrics.head(), metrics.count()), (0, 0));
+
+        for i in 1..=2 {
+            metrics.push_sample(3, i as f64 * 1000.0, 1000.0, 0, 0, 0, 1);
+        }
+        assert_eq!((metrics.head(), metrics.count()), (2, 2));
+        assert_eq!(times(&metrics), vec![1000.0, 2000.0]);
+
+        // 満杯になったら最も古いサンプルから上書きし、head はその位置を指す
+        for i in 3..=5 {
+            metrics.push_sample(3, i as f64 * 1000.0, 1000.0, 0, 0, 0, 1);
+        }
+        assert_eq!((metrics.head(), metrics.count()), (2, 3));
+        assert_eq!(times(&metrics), vec![3000.0, 4000.0, 5000.0]);
+        assert!(!metrics.as_ptr().is_null());
+    }
+
+    #[test]
+    fn capacity_change_starts_a_new_buffer() {
+        let mut metrics = NodeMetrics::default();
+        for i in 1..=4 {
+            metrics.push_sample(3, i as f64 * 1000.0, 1000.0, 0, 0, 0, 1);
+        }
+        metrics.push_sample(5, 5000.0, 1000.0, 0, 0, 0, 1);
+        assert_eq!((metrics.head(), metrics.count()), (1, 1));
+        assert_eq!(times(&metrics), vec![5000.0]);
+
+        metrics.clear();
+        assert_eq!(metrics.count(), 0);
+        assert!(metrics.as_ptr().is_null());
+    }
+
+    #[test]
+    fn sample_covers_only_the_last_window() {
+        let mut metrics = NodeMetrics::default();
+        metrics.start_window(10, 2);
+        // 処理枠2つのうち平均1つを使い、2件を平均30msで処理した
+        metrics.accumulate_busy(2, 250.0);
+        metrics.accumulate_busy(0, 250.0);
+        metrics.record_service(20.0);
+        metrics.record_service(40.0);
+        metrics.push_sample(4, 500.0, 500.0, 12, 5, 7, 2);
+
+        let sample: Vec<f32> = metrics.samples().next().unwrap().to_vec();
+        assert_eq!(sample, vec![500.0, 4.0, 7.0, 0.5, 3.0, 30.0]);
+
+        // 次の記録間隔は0から数え直す
+        metrics.push_sample(4, 1000.0, 500.0, 12, 5, 0, 2);
+        let sample: Vec<f32> = metrics.samples().nth(1).unwrap().to_vec();
+        assert_eq!(sample, vec![1000.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,765 @@
+// =============================================================================
+// PACKET STORE - パケット状態のSoA（Structure of Arrays）保持・スロット管理担当
+// フィールドごとに列（Vec）を持ち、移動・描画のように一部の列だけを舐める処理をキャッシュ効率よく回す
+// position / appearance 列はそのまま頂点バッファのレイアウトになっていて、描画時にコピーせず転送できる
+// =============================================================================
+
+use crate::simulation::{Packet, PacketState, MAX_ROUTE_HOPS};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+/// 描画用の見た目 [r, g, b, size]: リクエスト（白・小）
+pub const REQUEST_APPEARANCE: [f32; 4] = [1.0, 1.0, 1.0, 3.0];
+/// 描画用の見た目 [r, g, b, size]: レスポンス（水色 #00d9ff・大）
+pub const RESPONSE_APPEARANCE: [f32; 4] = [0.0, 0.85, 1.0, 6.0];
+/// 描画用の見た目 [r, g, b, size]: 非アクティブ（サイズ0で何も描かれない）
+pub const HIDDEN_APPEARANCE: [f32; 4] = [0.0; 4];
+
+/// スナップショットから復元できるスロット数の上限（壊れたファイルで巨大な領域を確保しないため）
+pub const MAX_SNAPSHOT_SLOTS: usize = 1 << 20;
+
+/// GPUへ送る1スロット分の移動状態（GPU移動モードで、CPU側で変わったスロットだけ送る）
+#[derive(Clone, Copy, Debug)]
+pub struct MotionUpdate {
+    pub slot: usize,
+    pub position: [f32; 2],
+    pub appearance: [f32; 4],
+    pub velocity: [f32; 2],
+    pub speed: f32,
+    pub target_node_idx: i32,
+    pub seq: u32,     // 送るたびに増える番号（古い状態に対するGPUのイベントを捨てるため）
+    pub moving: bool, // GPUで動かすか（アクティブかつ移動中）
+}
+
+/// GPUで移動させたパケットに起きたこと
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionEvent {
+    pub slot: usize,
+    pub seq: u32,       // GPUが持っていた移動状態の番号
+    pub vanished: bool, // true: 画面外・ターゲット無効で消滅、false: ターゲットノードに到達
+}
+
+/// パケット状態の列ストア
+/// 1つのパケットは全列で同じインデックス（スロット）を持つ
+/// 個別のパケットをまとめて読み書きするときは get / set で Packet に詰め替える
+#[derive(Clone, Debug, Default)]
+pub struct PacketStore {
+    pub position: Vec<[f32; 2]>,   // 描画列: x, y（頂点バッファにそのまま転送）
+    pub appearance: Vec<[f32; 4]>, // 描画列: r, g, b, size（is_response・activeから決まる）
+    pub velocity: Vec<[f32; 2]>,   // 座標指定モードの速度（ピクセル/ミリ秒）
+    pub speed: Vec<f32>,           // ノード指定モードの移動速度（ピクセル/ミリ秒）
+    pub active: Vec<u32>,
+    pub state: Vec<PacketState>,
+    pub target_node_idx: Vec<i32>,
+    pub current_node_idx: Vec<i32>,
+    pub packet_type: Vec<u32>,
+    pub complexity: Vec<u8>,
+    pub is_response: Vec<bool>,
+    pub size: Vec<f32>,
+    pub origin_server_idx: Vec<i32>,
+    pub route: Vec<[u16; MAX_ROUTE_HOPS]>,
+    pub route_len: Vec<u8>,
+    pub client_id: Vec<u32>,
+    pub spawn_time_ms: Vec<f64>,
+    pub wave_id: Vec<u32>,
+    pub rejected: Vec<bool>,
+    pub request_id: Vec<u32>,
+    pub deadline_ms: Vec<f64>,
+    pub retry: Vec<u32>,
+    pub abandoned: Vec<bool>,
+    free_slots: Vec<usize>, // 空きスロットのインデックス（末尾から取り出す）
+    active_count: usize,    // アクティブなパケット数（確保・解放のたびに更新）
+    node_refs: Vec<u32>,    // ノードインデックス -> アクティブなパケットからの参照数（ターゲット・現在地・通過履歴）
+    wave_packets: HashMap<u32, u32>, // Wave ID -> アクティブなパケット数（0になったら消す）
+    slot_count: usize,      // 一度でも使ったスロットの上限（描画はここまで転送すればよい）
+    track_motion: bool,     // 移動状態の変更を記録するか（GPU移動モード）
+    motion_seq: Vec<u32>,   // スロットごとの移動状態の番号
+    motion_dirty: Vec<bool>,
+    dirty_slots: Vec<usize>, // GPUへ送っていない変更があるスロット
+}
+
+impl PacketStore {
+    /// capacity個のスロットを持つ空のストアを作成
+    pub fn new(capacity: usize) -> PacketStore {
+        let mut store = PacketStore::default();
+        let row = Packet::default();
+        for _ in 0..capacity {
+            store.push_row(&row);
+        }
+        store.free_slots = (0..capacity).rev().collect();
+        store
+    }
+
+    fn push_row(&mut self, p: &Packet) {
+        self.position.push([p.x, p.y]);
+        self.appearance.push(appearance_of(p.active == 1, p.is_response));
+        self.velocity.push([p.velocity_x, p.velocity_y]);
+        self.speed.push(p.speed);
+        self.active.push(p.active);
+        self.state.push(p.state);
+        self.target_node_idx.push(p.target_node_idx);
+        self.current_node_idx.push(p.current_node_idx);
+        self.packet_type.push(p.packet_type);
+        self.complexity.push(p.complexity);
+        self.is_response.push(p.is_response);
+        self.size.push(p.size);
+        self.origin_server_idx.push(p.origin_server_idx);
+        self.route.push(p.route);
+        self.route_len.push(p.route_len);
+        self.client_id.push(p.client_id);
+        self.spawn_time_ms.push(p.spawn_time_ms);
+        self.wave_id.push(p.wave_id);
+        self.rejected.push(p.rejected);
+        self.request_id.push(p.request_id);
+        self.deadline_ms.push(p.deadline_ms);
+        self.retry.push(p.retry);
+        self.abandoned.push(p.abandoned);
+        self.motion_seq.push(0);
+        self.motion_dirty.push(false);
+    }
+
+    /// スロット数
+    pub fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    /// アクティブなパケット数
+    pub fn active_count(&self) -> usize {
+        self.active_count
+    }
+
+    /// 一度でも使ったスロットの上限（これより後ろは常に非アクティブ）
+    pub fn slot_count(&self) -> usize {
+        self.slot_count
+    }
+
+    pub fn is_active(&self, idx: usize) -> bool {
+        self.active.get(idx).is_some_and(|&a| a == 1)
+    }
+
+    /// ノードを参照しているアクティブなパケットの数（ターゲット・現在地・通過履歴の合計）
+    pub fn node_refs(&self, node_idx: usize) -> u32 {
+        self.node_refs.get(node_idx).copied().unwrap_or(0)
+    }
+
+    /// 1つのパケットがノードを参照している数（node_refs と同じ数え方）
+    pub fn refs_to(&self, idx: usize, node_idx: usize) -> u32 {
+        let node_idx = node_idx as i32;
+        (self.target_node_idx[idx] == node_idx) as u32
+            + (self.current_node_idx[idx] == node_idx) as u32
+            + self
+                .route_of(idx)
+                .iter()
+                .filter(|&&hop| hop as i32 == node_idx)
+                .count() as u32
+    }
+
+    /// Waveに属するアクティブなパケットの数
+    pub fn wave_packets(&self, wave_id: u32) -> u32 {
+        self.wave_packets.get(&wave_id).copied().unwrap_or(0)
+    }
+
+    /// 1つのパケットの全列を Packet に集める
+    pub fn get(&self, idx: usize) -> Packet {
+        let [x, y] = self.position[idx];
+        let [velocity_x, velocity_y] = self.velocity[idx];
+        Packet {
+            x,
+            y,
+            velocity_x,
+            velocity_y,
+            active: self.active[idx],
+            packet_type: self.packet_type[idx],
+            complexity: self.complexity[idx],
+            target_node_idx: self.target_node_idx[idx],
+            speed: self.speed[idx],
+            state: self.state[idx],
+            current_node_idx: self.current_node_idx[idx],
+            is_response: self.is_response[idx],
+            size: self.size[idx],
+            origin_server_idx: self.origin_server_idx[idx],
+            route: self.route[idx],
+            route_len: self.route_len[idx],
+            client_id: self.client_id[idx],
+            spawn_time_ms: self.spawn_time_ms[idx],
+            wave_id: self.wave_id[idx],
+            rejected: self.rejected[idx],
+            request_id: self.request_id[idx],
+            deadline_ms: self.deadline_ms[idx],
+            retry: self.retry[idx],
+            abandoned: self.abandoned[idx],
+        }
+    }
+
+    /// Packet の内容を各列に書き戻す（active はスロット管理が持つので書き換えない）
+    pub fn set(&mut self, idx: usize, p: &Packet) {
+        let active = self.active[idx] == 1;
+        if active {
+            self.count_refs(idx, false);
+        }
+        self.position[idx] = [p.x, p.y];
+        self.velocity[idx] = [p.velocity_x, p.velocity_y];
+        self.speed[idx] = p.speed;
+        self.state[idx] = p.state;
+        self.target_node_idx[idx] = p.target_node_idx;
+        self.current_node_idx[idx] = p.current_node_idx;
+        self.packet_type[idx] = p.packet_type;
+        self.complexity[idx] = p.complexity;
+        self.is_response[idx] = p.is_response;
+        self.size[idx] = p.size;
+        self.origin_server_idx[idx] = p.origin_server_idx;
+        self.route[idx] = p.route;
+        self.route_len[idx] = p.route_len;
+        self.client_id[idx] = p.client_id;
+        self.spawn_time_ms[idx] = p.spawn_time_ms;
+        self.wave_id[idx] = p.wave_id;
+        self.rejected[idx] = p.rejected;
+        self.request_id[idx] = p.request_id;
+        self.deadline_ms[idx] = p.deadline_ms;
+        self.retry[idx] = p.retry;
+        self.abandoned[idx] = p.abandoned;
+        self.appearance[idx] = appearance_of(active, p.is_response);
+        if active {
+            self.count_refs(idx, true);
+        }
+        self.mark_motion_dirty(idx);
+    }
+
+    /// 空きスロットを1つ確保し、初期値のリクエストとしてアクティブにする（満杯ならNone）
+    pub fn alloc(&mut self) -> Option<usize> {
+        let idx = self.free_slots.pop()?;
+        // 解放済みの列の中身は参照数に入っていないので、非アクティブのまま初期化してから数える
+        self.set(idx, &Packet::default());
+        self.activate(idx);
+        self.active_count += 1;
+        self.slot_count = self.slot_count.max(idx + 1);
+        Some(idx)
+    }
+
+    /// パケットを非アクティブにしてスロットを空きに戻す（非アクティブならfalse）
+    pub fn release(&mut self, idx: usize) -> bool {
+        if !self.is_active(idx) {
+            return false;
+        }
+        self.count_refs(idx, false);
+        self.active[idx] = 0;
+        self.appearance[idx] = HIDDEN_APPEARANCE;
+        self.free_slots.push(idx);
+        self.active_count -= 1;
+        self.mark_motion_dirty(idx);
+        true
+    }
+
+    /// すべてのスロットを空きに戻す（列の中身は次の確保時に初期化される）
+    pub fn clear(&mut self) {
+        for idx in 0..self.slot_count {
+            self.mark_motion_dirty(idx);
+        }
+        self.active.fill(0);
+        self.appearance.fill(HIDDEN_APPEARANCE);
+        self.free_slots = (0..self.len()).rev().collect();
+        self.active_count = 0;
+        self.node_refs.clear();
+        self.wave_packets.clear();
+        self.slot_count = 0;
+    }
+
+    /// 列に書き込んだパケットをアクティブにして、参照数に入れる
+    fn activate(&mut self, idx: usize) {
+        self.active[idx] = 1;
+        self.appearance[idx] = appearance_of(true, self.is_response[idx]);
+        self.count_refs(idx, true);
+    }
+
+    /// パケットが持つノード参照とWaveを参照数に足す（add = false なら引く）
+    fn count_refs(&mut self, idx: usize, add: bool) {
+        self.count_node_ref(self.target_node_idx[idx], add);
+        self.count_node_ref(self.current_node_idx[idx], add);
+        for hop in 0..self.route_len[idx] as usize {
+            self.count_node_ref(self.route[idx][hop] as i32, add);
+        }
+        let wave_id = self.wave_id[idx];
+        if add {
+            *self.wave_packets.entry(wave_id).or_insert(0) += 1;
+        } else if let Some(count) = self.wave_packets.get_mut(&wave_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.wave_packets.remove(&wave_id);
+            }
+        }
+    }
+
+    /// ノードへの参照を1つ足す・引く（-1 = ノードなし）
+    fn count_node_ref(&mut self, node_idx: i32, add: bool) {
+        if node_idx < 0 {
+            return;
+        }
+        let node_idx = node_idx as usize;
+        if add {
+            if node_idx >= self.node_refs.len() {
+                self.node_refs.resize(node_idx + 1, 0);
+            }
+            self.node_refs[node_idx] += 1;
+        } else {
+            self.node_refs[node_idx] -= 1;
+        }
+    }
+
+    /// 滞在中のノードを変える（-1 = どのノードにもいない）
+    pub fn set_current_node(&mut self, idx: usize, node_idx: i32) {
+        self.count_node_ref(self.current_node_idx[idx], false);
+        self.current_node_idx[idx] = node_idx;
+        self.count_node_ref(node_idx, true);
+    }
+
+    /// ノードが1つ削除されたので、それより後ろのノードを指すインデックスを1つずつ詰める
+    /// 削除したノードを参照しているパケットは先にドロップしておくこと
+    pub fn remove_node(&mut self, removed_idx: usize) {
+        debug_assert_eq!(self.node_refs(removed_idx), 0);
+        if removed_idx < self.node_refs.len() {
+            self.node_refs.remove(removed_idx);
+        }
+        let shift = |idx: i32| if idx > removed_idx as i32 { idx - 1 } else { idx };
+        for idx in 0..self.slot_count {
+            if self.active[idx] == 0 {
+                continue;
+            }
+            let target_node_idx = shift(self.target_node_idx[idx]);
+            if target_node_idx != self.target_node_idx[idx] {
+                self.target_node_idx[idx] = target_node_idx;
+                self.mark_motion_dirty(idx);
+            }
+            self.current_node_idx[idx] = shift(self.current_node_idx[idx]);
+            self.origin_server_idx[idx] = shift(self.origin_server_idx[idx]);
+            let len = self.route_len[idx] as usize;
+            for hop in self.route[idx][..len].iter_mut() {
+                if *hop as usize > removed_idx {
+                    *hop -= 1;
+                }
+            }
+        }
+    }
+
+    /// レスポンスに変換（見た目も切り替える）
+    pub fn make_response(&mut self, idx: usize, size: f32) {
+        self.is_response[idx] = true;
+        self.size[idx] = size;
+        self.appearance[idx] = appearance_of(self.active[idx] == 1, true);
+        self.mark_motion_dirty(idx);
+    }
+
+    /// 次のノードへ向けて移動を始める
+    pub fn start_moving(&mut self, idx: usize, target_node_idx: usize, position: [f32; 2]) {
+        self.set_target_node(idx, target_node_idx);
+        self.set_current_node(idx, -1); // 移動中
+        self.state[idx] = PacketState::Moving;
+        self.position[idx] = position;
+        self.mark_motion_dirty(idx);
+    }
+
+    /// 伝送特性を持つリンクに乗せる（位置はリンク上の進み具合で決まり、GPUでは動かさない）
+    pub fn enter_link(&mut self, idx: usize, target_node_idx: usize, position: [f32; 2]) {
+        self.set_target_node(idx, target_node_idx);
+        self.set_current_node(idx, -1); // 移動中
+        self.state[idx] = PacketState::OnLink;
+        self.position[idx] = position;
+        self.mark_motion_dirty(idx);
+    }
+
+    /// 向かう先のノードを変える
+    fn set_target_node(&mut self, idx: usize, target_node_idx: usize) {
+        self.count_node_ref(self.target_node_idx[idx], false);
+        self.target_node_idx[idx] = target_node_idx as i32;
+        self.count_node_ref(target_node_idx as i32, true);
+    }
+
+    /// 移動状態の変更の記録を切り替える（有効にしたときは使用中のスロットをすべて送り直す）
+    pub fn set_motion_tracking(&mut self, enabled: bool) {
+        self.track_motion = enabled;
+        for dirty in self.motion_dirty.iter_mut() {
+            *dirty = false;
+        }
+        self.dirty_slots.clear();
+        for idx in 0..self.slot_count {
+            self.mark_motion_dirty(idx);
+        }
+    }
+
+    /// スロットの移動状態が変わったことを記録（GPUへ送り直す）
+    /// 番号を進めるので、変更前の状態に対してGPUが出したイベントは無視される
+    pub fn mark_motion_dirty(&mut self, idx: usize) {
+        if !self.track_motion {
+            return;
+        }
+        self.motion_seq[idx] = self.motion_seq[idx].wrapping_add(1);
+        if !self.motion_dirty[idx] {
+            self.motion_dirty[idx] = true;
+            self.dirty_slots.push(idx);
+        }
+    }
+
+    /// GPUのイベントが今の移動状態に対するものか
+    pub fn is_current_motion(&self, idx: usize, seq: u32) -> bool {
+        self.motion_seq.get(idx) == Some(&seq)
+    }
+
+    /// GPUへ送っていない変更を取り出す（スロット順）
+    pub fn take_motion_updates(&mut self, out: &mut Vec<MotionUpdate>) {
+        self.dirty_slots.sort_unstable();
+        for &idx in &self.dirty_slots {
+            self.motion_dirty[idx] = false;
+            out.push(MotionUpdate {
+                slot: idx,
+                position: self.position[idx],
+                appearance: self.appearance[idx],
+                velocity: self.velocity[idx],
+                speed: self.speed[idx],
+                target_node_idx: self.target_node_idx[idx],
+                seq: self.motion_seq[idx],
+                moving: self.active[idx] == 1 && self.state[idx] == PacketState::Moving,
+            });
+        }
+        self.dirty_slots.clear();
+    }
+
+    /// 通過履歴にノードを積む（上限を超えた場合はfalse）
+    pub fn push_route(&mut self, idx: usize, node_idx: usize) -> bool {
+        let len = self.route_len[idx] as usize;
+        if len >= MAX_ROUTE_HOPS {
+            return false;
+        }
+        self.route[idx][len] = node_idx as u16;
+        self.route_len[idx] += 1;
+        self.count_node_ref(node_idx as i32, true);
+        true
+    }
+
+    /// 通過履歴の末尾を取り出す
+    pub fn pop_route(&mut self, idx: usize) -> Option<usize> {
+        if self.route_len[idx] == 0 {
+            return None;
+        }
+        self.route_len[idx] -= 1;
+        let node_idx = self.route[idx][self.route_len[idx] as usize] as usize;
+        self.count_node_ref(node_idx as i32, false);
+        Some(node_idx)
+    }
+
+    /// 通過履歴の末尾（直前に通過したノード）を参照
+    pub fn last_route(&self, idx: usize) -> Option<usize> {
+        match self.route_len[idx] as usize {
+            0 => None,
+            len => Some(self.route[idx][len - 1] as usize),
+        }
+    }
+
+    /// 通過履歴（有効な部分）
+    pub fn route_of(&self, idx: usize) -> &[u16] {
+        &self.route[idx][..self.route_len[idx] as usize]
+    }
+}
+
+/// スナップショット上のパケット状態
+/// 一度でも使ったスロット（slot_count まで）だけを行で持ち、残りは初期値として復元する
+#[derive(Serialize, Deserialize)]
+struct StoreSnapshot {
+    capacity: usize,
+    rows: Vec<Packet>,
+    free_tail: Vec<usize>, // 空きスロットのうち、使ったことのあるもの（取り出す順序を保つ）
+}
+
+// 使ったことのないスロットは空きスロットの底に (capacity - 1) から slot_count まで並んだままなので、
+// それより上に積まれた部分だけを保存すれば取り出し順まで復元できる
+impl Serialize for PacketStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let untouched = self.len() - self.slot_count;
+        StoreSnapshot {
+            capacity: self.len(),
+            rows: (0..self.slot_count).map(|idx| self.get(idx)).collect(),
+            free_tail: self.free_slots[untouched..].to_vec(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PacketStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = StoreSnapshot::deserialize(deserializer)?;
+        if snapshot.capacity > MAX_SNAPSHOT_SLOTS {
+            return Err(D::Error::custom(format!(
+                "too many packet slots {} (max {})",
+                snapshot.capacity, MAX_SNAPSHOT_SLOTS
+            )));
+        }
+        let slot_count = snapshot.rows.len();
+        if slot_count > snapshot.capacity {
+            return Err(D::Error::custom("more packet rows than slots"));
+        }
+        if snapshot.rows.iter().any(|row| row.active > 1) {
+            return Err(D::Error::custom("invalid packet active flag"));
+        }
+        if snapshot.rows.iter().any(|row| row.route_len as usize > MAX_ROUTE_HOPS) {
+            return Err(D::Error::custom("packet route longer than MAX_ROUTE_HOPS"));
+        }
+        // 通過履歴と同じく、ノードインデックスは u16 に収まる（参照数の表を巨大にしないため）
+        let node_idx_limit = u16::MAX as i32;
+        if snapshot.rows.iter().any(|row| {
+            row.target_node_idx > node_idx_limit || row.current_node_idx > node_idx_limit
+        }) {
+            return Err(D::Error::custom("packet node index out of range"));
+        }
+        let mut store = PacketStore::new(snapshot.capacity);
+        for (idx, row) in snapshot.rows.iter().enumerate() {
+            store.set(idx, row);
+            if row.active == 1 {
+                store.activate(idx);
+            }
+        }
+        store.active_count = snapshot.rows.iter().filter(|row| row.active == 1).count();
+        store.slot_count = slot_count;
+        store.free_slots.truncate(snapshot.capacity - slot_count);
+        // 同じスロットが2回あると、別々のパケットに同じスロットを渡してしまう
+        let mut seen = vec![false; slot_count];
+        for &idx in &snapshot.free_tail {
+            if idx >= slot_count || store.active[idx] == 1 || seen[idx] {
+                return Err(D::Error::custom(format!("invalid free packet slot {}", idx)));
+            }
+            seen[idx] = true;
+            store.free_slots.push(idx);
+        }
+        if store.free_slots.len() + store.active_count != snapshot.capacity {
+            return Err(D::Error::custom("free packet slots do not match active packets"));
+        }
+        Ok(store)
+    }
+}
+
+/// パケットの見た目（非アクティブなら描かない）
+fn appearance_of(active: bool, is_response: bool) -> [f32; 4] {
+    if !active {
+        HIDDEN_APPEARANCE
+    } else if is_response {
+        RESPONSE_APPEARANCE
+    } else {
+        REQUEST_APPEARANCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::NO_WAVE;
+
+    // 確保と解放を混ぜて、空きスロットの取り出し順が素直でない状態にする
+    fn used_store() -> PacketStore {
+        let mut store = PacketStore::new(16);
+        for _ in 0..6 {
+            let idx = store.alloc().unwrap();
+            store.packet_type[idx] = idx as u32;
+            store.push_route(idx, idx);
+        }
+        store.release(1);
+        store.release(4);
+        store.release(2);
+        store.make_response(3, 10.0);
+        store
+    }
+
+    #[test]
+    fn node_and_wave_counts_follow_packets() {
+        let mut store = PacketStore::new(4);
+        let a = store.alloc().unwrap();
+        let b = store.alloc().unwrap();
+        store.set(
+            b,
+            &Packet {
+                wave_id: 7,
+                ..Packet::default()
+            },
+        );
+        assert_eq!(store.wave_packets(7), 1);
+
+        store.start_moving(a, 2, [0.0, 0.0]);
+        store.set_current_node(b, 2);
+        store.push_route(b, 1);
+        assert_eq!(store.node_refs(2), 2);
+        assert_eq!(store.node_refs(1), 1);
+
+        // 移動先を変える・経路を戻ると古いノードの参照は消える
+        store.start_moving(b, 3, [0.0, 0.0]);
+        assert_eq!(store.pop_route(b), Some(1));
+        assert_eq!(store.node_refs(1), 0);
+        assert_eq!(store.node_refs(2), 1);
+        assert_eq!(store.node_refs(3), 1);
+
+        // ノード2を削除すると、後ろのノードの参照が1つ前に詰まる
+        store.release(a);
+        store.remove_node(2);
+        assert_eq!(store.node_refs(2), 1);
+        assert_eq!(store.target_node_idx[b], 2);
+
+        // 読み込み直しても同じ数になる
+        let text: PacketStore =
+            serde_json::from_str(&serde_json::to_string(&store).unwrap()).unwrap();
+        assert_eq!(text.node_refs(2), 1);
+        assert_eq!(text.wave_packets(7), 1);
+
+        store.release(b);
+        assert_eq!(store.node_refs(2), 0);
+        assert_eq!(store.wave_packets(7), 0);
+    }
+
+    #[test]
+    fn released_slots_are_reused_last_in_first_out() {
+        let mut store = PacketStore::new(4);
+        let slots: Vec<usize> = (0..4).map(|_| store.alloc().unwrap()).collect();
+        assert_eq!(slots, vec![0, 1, 2, 3]);
+        assert_eq!(store.alloc(), None);
+        assert_eq!(store.active_count(), 4);
+
+        assert!(store.release(1));
+        assert!(store.release(3));
+        // 二重解放は無視し、数を崩さない
+        assert!(!store.release(3));
+        assert_eq!(store.active_count(), 2);
+        assert_eq!(store.slot_count(), 4);
+
+        // 最後に空いたスロットから使う
+        assert_eq!(store.alloc(), Some(3));
+        assert_eq!(store.alloc(), Some(1));
+        assert_eq!(store.alloc(), None);
+        assert_eq!(store.active_count(), 4);
+    }
+
+    #[test]
+    fn reused_slot_starts_from_a_fresh_packet() {
+        let mut store = PacketStore::new(2);
+        let idx = store.alloc().unwrap();
+        store.wave_id[idx] = 7;
+        store.push_route(idx, 1);
+        store.make_response(idx, 10.0);
+        assert!(store.release(idx));
+        assert_eq!(store.appearance[idx], HIDDEN_APPEARANCE);
+
+        // 前のパケットの経路・Wave・レスポンス状態は引き継がない
+        assert_eq!(store.alloc(), Some(idx));
+        assert_eq!(store.route_len[idx], 0);
+        assert!(!store.is_response[idx]);
+        assert_eq!(store.appearance[idx], REQUEST_APPEARANCE);
+        assert_eq!(store.wave_id[idx], NO_WAVE);
+        assert_eq!(store.node_refs(1), 0);
+    }
+
+    #[test]
+    fn clear_frees_every_slot() {
+        let mut store = used_store();
+        assert_eq!(store.active_count(), 3);
+        store.clear();
+        assert_eq!(store.active_count(), 0);
+        assert_eq!(store.slot_count(), 0);
+        assert!((0..store.len()).all(|idx| !store.is_active(idx)));
+        assert_eq!(store.node_refs(0), 0);
+
+        // 空きは先頭から取り出す順に戻る
+        assert_eq!(store.alloc(), Some(0));
+        assert_eq!(store.alloc(), Some(1));
+        assert_eq!(store.slot_count(), 2);
+    }
+
+    fn assert_same(a: &PacketStore, b: &PacketStore) {
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.active_count(), b.active_count());
+        assert_eq!(a.slot_count(), b.slot_count());
+        assert_eq!(a.free_slots, b.free_slots);
+        assert_eq!(a.active, b.active);
+        assert_eq!(a.packet_type, b.packet_type);
+        assert_eq!(a.route_len, b.route_len);
+        assert_eq!(a.is_response, b.is_response);
+        assert_eq!(a.appearance, b.appearance);
+    }
+
+    // 保存形式をJSONで組み立てて読み込む
+    fn restore(
+        capacity: usize,
+        rows: Vec<Packet>,
+        free_tail: Vec<usize>,
+    ) -> Result<PacketStore, String> {
+        let json = serde_json::to_value(StoreSnapshot {
+            capacity,
+            rows,
+            free_tail,
+        })
+        .unwrap();
+        PacketStore::deserialize(json).map_err(|e| e.to_string())
+    }
+
+    fn active_row() -> Packet {
+        Packet {
+            active: 1,
+            ..Packet::default()
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_slots_and_free_order() {
+        let mut store = used_store();
+
+        let bytes = rmp_serde::to_vec(&store).unwrap();
+        let mut binary: PacketStore = rmp_serde::from_slice(&bytes).unwrap();
+        let json = serde_json::to_string(&store).unwrap();
+        let mut text: PacketStore = serde_json::from_str(&json).unwrap();
+        assert_same(&store, &binary);
+        assert_same(&store, &text);
+
+        // 解放した順の逆から、その後で未使用のスロットを確保する
+        for expected in [2, 4, 1, 6, 7] {
+            assert_eq!(store.alloc(), Some(expected));
+            assert_eq!(binary.alloc(), Some(expected));
+            assert_eq!(text.alloc(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_free_slot() {
+        // 重複1つ + 欠け1つでも数は合うので、重複そのものを見て弾く
+        let rows = vec![Packet::default(), Packet::default(), active_row()];
+        let err = restore(4, rows.clone(), vec![0, 0]).unwrap_err();
+        assert!(err.contains("invalid free packet slot 0"), "{}", err);
+        assert!(restore(4, rows, vec![1, 0]).is_ok());
+    }
+
+    #[test]
+    fn rejects_inconsistent_slots() {
+        let err = restore(4, vec![active_row()], vec![0]).unwrap_err();
+        assert!(err.contains("invalid free packet slot 0"), "{}", err);
+
+        let err = restore(4, vec![Packet::default()], vec![]).unwrap_err();
+        assert!(err.contains("do not match"), "{}", err);
+
+        let err = restore(1, vec![active_row(), active_row()], vec![]).unwrap_err();
+        assert!(err.contains("more packet rows than slots"), "{}", err);
+
+        let row = Packet {
+            active: 2,
+            ..Packet::default()
+        };
+        let err = restore(4, vec![row], vec![]).unwrap_err();
+        assert!(err.contains("active flag"), "{}", err);
+
+        let row = Packet {
+            target_node_idx: i32::MAX,
+            ..active_row()
+        };
+        let err = restore(4, vec![row], vec![]).unwrap_err();
+        assert!(err.contains("node index out of range"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_huge_capacity_before_allocating() {
+        let err = restore(usize::MAX, vec![], vec![]).unwrap_err();
+        assert!(err.contains("too many packet slots"), "{}", err);
+        assert!(restore(MAX_SNAPSHOT_SLOTS + 1, vec![], vec![]).is_err());
+    }
+}
@@ -0,0 +1,606 @@
+// =============================================================================
+// QUEUE - ノードの待機キュー（キュー規律・アクティブキュー管理）担当
+// FIFO / LIFO / 優先度 / RED / CoDel を切り替え、規律ごとのドロップ数を数える
+// パケットの破棄そのものはSimulationStateが行い、ここではどれを捨てるかを決める
+// =============================================================================
+
+use crate::simulation::{Packet, PacketType};
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// 優先度のクラス分け（小さいほど先に処理される）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriorityClasses {
+    pub response: u8, // レスポンス（パケットタイプによらない）
+    pub normal: u8,
+    pub heavy_task: u8,
+    pub syn_flood: u8,
+    pub killer: u8,
+}
+
+impl Default for PriorityClasses {
+    fn default() -> Self {
+        PriorityClasses {
+            response: 0,
+            normal: 1,
+            heavy_task: 2,
+            syn_flood: 3,
+            killer: 3,
+        }
+    }
+}
+
+impl PriorityClasses {
+    /// パケットのクラス
+    pub fn class_of(&self, packet: &Packet) -> u8 {
+        if packet.is_response {
+            return self.response;
+        }
+        match packet.packet_type {
+            t if t == PacketType::SynFlood as u32 => self.syn_flood,
+            t if t == PacketType::HeavyTask as u32 => self.heavy_task,
+            t if t == PacketType::Killer as u32 => self.killer,
+            _ => self.normal,
+        }
+    }
+}
+
+/// REDのパラメータ（しきい値はキュー容量に対する割合）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedConfig {
+    pub min_fill: f64,        // 平均キュー長がこれを超えると確率的に捨て始める
+    pub max_fill: f64,        // 平均キュー長がこれを超えるとすべて捨てる
+    pub max_probability: f64, // max_fill 直前での廃棄確率
+    pub weight: f64,          // 平均キュー長（指数移動平均）の重み
+}
+
+impl Default for RedConfig {
+    fn default() -> Self {
+        RedConfig {
+            min_fill: 0.3,
+            max_fill: 0.9,
+            max_probability: 0.1,
+            weight: 0.1,
+        }
+    }
+}
+
+/// CoDelのパラメータ
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodelConfig {
+    pub target_ms: f64,   // 許容するキュー滞在時間
+    pub interval_ms: f64, // 滞在時間が target_ms を超え続けたら捨て始めるまでの時間
+}
+
+impl Default for CodelConfig {
+    fn default() -> Self {
+        CodelConfig {
+            target_ms: 100.0,
+            interval_ms: 1000.0,
+        }
+    }
+}
+
+/// キュー規律（ステージJSONでは "discipline" で選び、同じオブジェクトにパラメータを書く）
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "discipline", rename_all = "snake_case")]
+pub enum QueuePolicy {
+    #[default]
+    Fifo, // 先着順。満杯なら到着したものを捨てる（drop-tail）
+    Lifo, // 新しいものから処理。満杯なら到着したものを捨てる
+    Priority(PriorityClasses), // クラスの小さいものから処理。満杯なら低いクラスを押し出す
+    Red(RedConfig),            // 平均キュー長に応じて到着時に確率的に捨てる（処理は先着順）
+    Codel(CodelConfig),        // 取り出し時に滞在時間を見て捨てる（処理は先着順）
+}
+
+/// キューでのドロップの原因
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueueDrop {
+    Tail,     // 満杯で到着したものを捨てた
+    Priority, // 優先度の高いものに押し出された
+    Red,      // REDの早期廃棄
+    Codel,    // CoDelの滞在時間超過
+}
+
+/// 原因ごとのドロップ数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueDrops {
+    pub tail: u32,
+    pub priority: u32,
+    pub red: u32,
+    pub codel: u32,
+}
+
+impl QueueDrops {
+    pub fn record(&mut self, cause: QueueDrop) {
+        match cause {
+            QueueDrop::Tail => self.tail += 1,
+            QueueDrop::Priority => self.priority += 1,
+            QueueDrop::Red => self.red += 1,
+            QueueDrop::Codel => self.codel += 1,
+        }
+    }
+}
+
+/// キュー内で待機中のパケット
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct QueuedPacket {
+    pub packet_idx: usize,
+    pub enqueued_ms: f64, // キューに入った時刻（CoDelの滞在時間）
+    pub class: u8,        // 優先度のクラス（Priority以外は0）
+}
+
+/// キューへの追加結果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Enqueue {
+    Queued,
+    Evicted(usize),    // 追加した代わりに押し出したパケットのインデックス
+    Dropped(QueueDrop), // 到着したパケットを捨てた
+}
+
+/// CoDelの状態
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+struct CodelState {
+    first_above_ms: Option<f64>, // 滞在時間が target を超えたままなら、捨て始めてよくなる時刻
+    dropping: bool,              // 廃棄モード中か
+    drop_next_ms: f64,           // 廃棄モードで次に捨てる時刻
+    count: u32,                  // 廃棄モードで捨てた数（間隔を interval / sqrt(count) に縮める）
+    last_count: u32,             // 前回の廃棄モードを抜けたときの count
+}
+
+/// ノードの待機キュー
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PacketQueue {
+    policy: QueuePolicy,
+    // クラスごとの列（Priority以外はクラス0の1本だけ）。各列の先頭が最も古い
+    // （FIFOは先頭、LIFOは末尾から取り出す。Priorityは最も小さいクラスの先頭から取り出し、最も大きいクラスの末尾を押し出す）
+    lanes: Vec<VecDeque<QueuedPacket>>,
+    red_avg: f64,      // REDの平均キュー長
+    codel: CodelState, // CoDelの状態
+    pub drops: QueueDrops,
+}
+
+impl PacketQueue {
+    pub fn policy(&self) -> QueuePolicy {
+        self.policy
+    }
+
+    /// 規律を変更（待機中のパケットはそのまま、AQMの状態はやり直し）
+    /// Priorityとそれ以外を切り替えたときは、到着順に並べ直して列を組み直す
+    pub fn set_policy(&mut self, policy: QueuePolicy) {
+        let regroup = matches!(self.policy, QueuePolicy::Priority(_))
+            != matches!(policy, QueuePolicy::Priority(_));
+        self.policy = policy;
+        self.red_avg = 0.0;
+        self.codel = CodelState::default();
+        if regroup {
+            let mut items: Vec<QueuedPacket> = self.drain().collect();
+            items.sort_by(|a, b| a.enqueued_ms.total_cmp(&b.enqueued_ms));
+            for item in items {
+                self.push_back(item);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(VecDeque::is_empty)
+    }
+
+    /// 待機中のパケットとAQMの状態を捨てる
+    pub fn clear(&mut self) {
+        self.lanes.clear();
+        self.red_avg = 0.0;
+        self.codel = CodelState::default();
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&QueuedPacket) -> bool) {
+        for lane in self.lanes.iter_mut() {
+            lane.retain(&mut f);
+        }
+    }
+
+    /// 待機中のパケット（クラスの小さい順、同じクラスは先着順）
+    pub fn iter(&self) -> impl Iterator<Item = &QueuedPacket> {
+        self.lanes.iter().flatten()
+    }
+
+    /// 待機中のパケットをすべて取り出す（クラスの小さい順、同じクラスは先着順）
+    pub fn drain(&mut self) -> impl Iterator<Item = QueuedPacket> + '_ {
+        self.lanes.iter_mut().flat_map(|lane| lane.drain(..))
+    }
+
+    /// パケットが並ぶ列（Priority以外はすべてクラス0の列）
+    fn lane_of(&self, item: &QueuedPacket) -> usize {
+        match self.policy {
+            QueuePolicy::Priority(_) => item.class as usize,
+            _ => 0,
+        }
+    }
+
+    fn push_back(&mut self, item: QueuedPacket) {
+        let lane = self.lane_of(&item);
+        if lane >= self.lanes.len() {
+            self.lanes.resize_with(lane + 1, VecDeque::new);
+        }
+        self.lanes[lane].push_back(item);
+    }
+
+    /// パケットの優先度クラス（Priority以外は0）
+    pub fn class_of(&self, packet: &Packet) -> u8 {
+        match self.policy {
+            QueuePolicy::Priority(classes) => classes.class_of(packet),
+            _ => 0,
+        }
+    }
+
+    /// パケットをキューに入れる（capacity: 今使えるキュー枠）
+    /// random: [0, 1) の乱数を返す関数（REDのみ使用）
+    pub fn offer(
+        &mut self,
+        item: QueuedPacket,
+        capacity: usize,
+        random: &mut dyn FnMut() -> f32,
+    ) -> Enqueue {
+        let len = self.len();
+        let full = len >= capacity;
+        let result = match self.policy {
+            QueuePolicy::Red(config) => {
+                self.red_avg += config.weight * (len as f64 - self.red_avg);
+                let min = config.min_fill * capacity as f64;
+                let max = config.max_fill * capacity as f64;
+                if full {
+                    Enqueue::Dropped(QueueDrop::Tail)
+                } else {
+                    // min以下は廃棄なし、minからmaxまで確率を max_probability まで上げ、max以上はすべて廃棄
+                    let early_drop = if self.red_avg >= max {
+                        true
+                    } else if self.red_avg > min {
+                        let probability = config.max_probability * (self.red_avg - min) / (max - min);
+                        (random() as f64) < probability
+                    } else {
+                        false
+                    };
+                    if early_drop {
+                        Enqueue::Dropped(QueueDrop::Red)
+                    } else {
+                        Enqueue::Queued
+                    }
+                }
+            }
+            QueuePolicy::Priority(_) if full => {
+                // 自分より低いクラスのうち、最も低く最も新しいものを押し出す
+                let lower = self.lanes.iter_mut().skip(item.class as usize + 1);
+                match lower.rev().find_map(VecDeque::pop_back) {
+                    Some(victim) => Enqueue::Evicted(victim.packet_idx),
+                    None => Enqueue::Dropped(QueueDrop::Tail),
+                }
+            }
+            _ if full => Enqueue::Dropped(QueueDrop::Tail),
+            _ => Enqueue::Queued,
+        };
+
+        match result {
+            Enqueue::Dropped(cause) => self.drops.record(cause),
+            Enqueue::Evicted(_) => {
+                self.drops.record(QueueDrop::Priority);
+                self.push_back(item);
+            }
+            Enqueue::Queued => self.push_back(item),
+        }
+        result
+    }
+
+    /// 次に処理するパケットを取り出す
+    /// CoDelで捨てたパケットのインデックスは dropped に追加する
+    pub fn pop(&mut self, now_ms: f64, dropped: &mut Vec<usize>) -> Option<QueuedPacket> {
+        match self.policy {
+            QueuePolicy::Lifo => self.lanes.first_mut()?.pop_back(),
+            QueuePolicy::Codel(config) => self.pop_codel(config, now_ms, dropped),
+            QueuePolicy::Fifo | QueuePolicy::Red(_) | QueuePolicy::Priority(_) => self.pop_front(),
+        }
+    }
+
+    /// 最も小さいクラスの列の先頭を取り出す（Priority以外は列が1本なので先着順）
+    fn pop_front(&mut self) -> Option<QueuedPacket> {
+        self.lanes.iter_mut().find_map(VecDeque::pop_front)
+    }
+
+    /// CoDel（RFC 8289）の取り出し
+    /// 滞在時間が interval の間ずっと target を超えていたら捨て始め、
+    /// 下回るまで interval / sqrt(count) ごとに1つずつ捨てる
+    fn pop_codel(
+        &mut self,
+        config: CodelConfig,
+        now_ms: f64,
+        dropped: &mut Vec<usize>,
+    ) -> Option<QueuedPacket> {
+        let Some(mut item) = self.pop_front() else {
+            self.codel.dropping = false;
+            return None;
+        };
+        let ok_to_drop = self.codel_ok_to_drop(&item, config, now_ms);
+
+        if self.codel.dropping {
+            if !ok_to_drop {
+                self.codel.dropping = false;
+            }
+            while self.codel.dropping && now_ms >= self.codel.drop_next_ms {
+                dropped.push(item.packet_idx);
+                self.drops.record(QueueDrop::Codel);
+                self.codel.count += 1;
+                let Some(next) = self.pop_front() else {
+                    self.codel.dropping = false;
+                    return None;
+                };
+                item = next;
+                if self.codel_ok_to_drop(&item, config, now_ms) {
+                    self.codel.drop_next_ms =
+                        control_law(self.codel.drop_next_ms, config, self.codel.count);
+                } else {
+                    self.codel.dropping = false;
+                }
+            }
+        } else if ok_to_drop {
+            dropped.push(item.packet_idx);
+            self.drops.record(QueueDrop::Codel);
+            // 直前まで廃棄モードだったなら、そのときの間隔の近くから再開する
+            let delta = self.codel.count.saturating_sub(self.codel.last_count);
+            self.codel.count = if delta > 1
+                && now_ms - self.codel.drop_next_ms < 16.0 * config.interval_ms
+            {
+                delta
+            } else {
+                1
+            };
+            self.codel.last_count = self.codel.count;
+            self.codel.dropping = true;
+            self.codel.drop_next_ms = control_law(now_ms, config, self.codel.count);
+            return self.pop_front();
+        }
+        Some(item)
+    }
+
+    /// 滞在時間が interval の間ずっと target を超えているか
+    fn codel_ok_to_drop(&mut self, item: &QueuedPacket, config: CodelConfig, now_ms: f64) -> bool {
+        let sojourn_ms = now_ms - item.enqueued_ms;
+        if sojourn_ms < config.target_ms || self.is_empty() {
+            // 滞在時間が短い、または残りがない（捨てても混雑は解消しない）
+            self.codel.first_above_ms = None;
+            return false;
+        }
+        match self.codel.first_above_ms {
+            None => {
+                self.codel.first_above_ms = Some(now_ms + config.interval_ms);
+                false
+            }
+            Some(t) => now_ms >= t,
+        }
+    }
+}
+
+/// 次に捨てる時刻（捨てるほど間隔が縮む）
+fn control_law(t_ms: f64, config: CodelConfig, count: u32) -> f64 {
+    t_ms + config.interval_ms / (count.max(1) as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(packet_idx: usize, enqueued_ms: f64, class: u8) -> QueuedPacket {
+        QueuedPacket {
+            packet_idx,
+            enqueued_ms,
+            class,
+        }
+    }
+
+    fn queue(policy: QueuePolicy) -> PacketQueue {
+        let mut queue = PacketQueue::default();
+        queue.set_policy(policy);
+        queue
+    }
+
+    #[test]
+    fn red_drop_probability_rises_between_min_and_max() {
+        // weight 1.0 で平均キュー長 = 現在のキュー長。容量10なら min=2, max=8
+        let config = RedConfig {
+            min_fill: 0.2,
+            max_fill: 0.8,
+            max_probability: 0.5,
+            weight: 1.0,
+        };
+        let mut q = queue(QueuePolicy::Red(config));
+
+        // min以下では乱数によらず廃棄しない
+        for i in 0..3 {
+            assert_eq!(q.offer(item(i, 0.0, 0), 10, &mut || 0.0), Enqueue::Queued);
+        }
+        // 平均3: 確率 0.5 * (3 - 2) / 6
+        let p3 = 0.5 / 6.0;
+        assert_eq!(
+            q.offer(item(3, 0.0, 0), 10, &mut || p3 as f32 + 0.001),
+            Enqueue::Queued
+        );
+        // 平均4: 確率 0.5 * (4 - 2) / 6
+        let p4 = 0.5 * 2.0 / 6.0;
+        assert_eq!(
+            q.offer(item(4, 0.0, 0), 10, &mut || p4 as f32 - 0.001),
+            Enqueue::Dropped(QueueDrop::Red)
+        );
+        assert_eq!(
+            q.offer(item(4, 0.0, 0), 10, &mut || p4 as f32 + 0.001),
+            Enqueue::Queued
+        );
+        for i in 5..8 {
+            assert_eq!(q.offer(item(i, 0.0, 0), 10, &mut || 0.99), Enqueue::Queued);
+        }
+        // max以上は乱数によらずすべて廃棄
+        assert_eq!(
+            q.offer(item(8, 0.0, 0), 10, &mut || 0.99),
+            Enqueue::Dropped(QueueDrop::Red)
+        );
+        assert_eq!(q.len(), 8);
+        assert_eq!(q.drops.red, 2);
+        assert_eq!(q.drops.tail, 0);
+    }
+
+    #[test]
+    fn red_drops_tail_when_full() {
+        let mut q = queue(QueuePolicy::Red(RedConfig {
+            weight: 0.0, // 平均キュー長を0のままにして早期廃棄を起こさない
+            ..RedConfig::default()
+        }));
+        for i in 0..4 {
+            assert_eq!(q.offer(item(i, 0.0, 0), 4, &mut || 0.0), Enqueue::Queued);
+        }
+        assert_eq!(
+            q.offer(item(4, 0.0, 0), 4, &mut || 0.0),
+            Enqueue::Dropped(QueueDrop::Tail)
+        );
+        assert_eq!(q.drops.tail, 1);
+    }
+
+    #[test]
+    fn priority_evicts_lowest_class_newest_packet() {
+        let mut q = queue(QueuePolicy::Priority(PriorityClasses::default()));
+        let mut never = || -> f32 { unreachable!("priority does not use random") };
+        for (idx, class) in [(0, 3), (1, 2), (2, 3)] {
+            assert_eq!(
+                q.offer(item(idx, 0.0, class), 3, &mut never),
+                Enqueue::Queued
+            );
+        }
+
+        // 満杯: クラス3のうち新しい方 -> もう一方のクラス3 -> クラス2 の順に押し出す
+        assert_eq!(q.offer(item(3, 0.0, 1), 3, &mut never), Enqueue::Evicted(2));
+        assert_eq!(q.offer(item(4, 0.0, 1), 3, &mut never), Enqueue::Evicted(0));
+        assert_eq!(q.offer(item(5, 0.0, 1), 3, &mut never), Enqueue::Evicted(1));
+        // 自分より低いクラスがなければ到着したものを捨てる
+        assert_eq!(
+            q.offer(item(6, 0.0, 1), 3, &mut never),
+            Enqueue::Dropped(QueueDrop::Tail)
+        );
+        assert_eq!(q.drops.priority, 3);
+        assert_eq!(q.drops.tail, 1);
+
+        // 同じクラスは先着順
+        let mut dropped = Vec::new();
+        let order: Vec<usize> = std::iter::from_fn(|| q.pop(0.0, &mut dropped))
+            .map(|q| q.packet_idx)
+            .collect();
+        assert_eq!(order, vec![3, 4, 5]);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn priority_pops_highest_class_first() {
+        let mut q = queue(QueuePolicy::Priority(PriorityClasses::default()));
+        for (idx, class) in [(0, 2), (1, 0), (2, 1), (3, 0)] {
+            q.offer(item(idx, 0.0, class), 10, &mut || 0.0);
+        }
+        let mut dropped = Vec::new();
+        let order: Vec<usize> = std::iter::from_fn(|| q.pop(0.0, &mut dropped))
+            .map(|q| q.packet_idx)
+            .collect();
+        assert_eq!(order, vec![1, 3, 2, 0]);
+    }
+
+    #[test]
+    fn codel_enters_and_leaves_drop_mode() {
+        let config = CodelConfig {
+            target_ms: 100.0,
+            interval_ms: 1000.0,
+        };
+        let mut q = queue(QueuePolicy::Codel(config));
+        // 0..6 は t=0 に、6..10 は t=3400 に到着
+        for i in 0..10 {
+            let enqueued_ms = if i < 6 { 0.0 } else { 3400.0 };
+            q.offer(item(i, enqueued_ms, 0), 100, &mut || 0.0);
+        }
+        let mut dropped = Vec::new();
+
+        // target を超えても、interval の間超え続けるまでは捨てない
+        assert_eq!(q.pop(200.0, &mut dropped).unwrap().packet_idx, 0);
+        assert!(dropped.is_empty());
+        assert!(!q.codel.dropping);
+
+        // interval 経過: 1つ捨てて廃棄モードに入る。次は interval / sqrt(1) 後
+        assert_eq!(q.pop(1200.0, &mut dropped).unwrap().packet_idx, 2);
+        assert_eq!(dropped, vec![1]);
+        assert!(q.codel.dropping);
+        assert_eq!(q.codel.count, 1);
+        assert_eq!(q.codel.drop_next_ms, 2200.0);
+
+        // 予定時刻前は捨てない
+        assert_eq!(q.pop(2100.0, &mut dropped).unwrap().packet_idx, 3);
+        assert_eq!(dropped, vec![1]);
+
+        // 2つ目を捨てると間隔は interval / sqrt(2) に縮む
+        assert_eq!(q.pop(2200.0, &mut dropped).unwrap().packet_idx, 5);
+        assert_eq!(dropped, vec![1, 4]);
+        assert_eq!(q.codel.count, 2);
+        assert_eq!(q.codel.drop_next_ms, 2200.0 + 1000.0 / 2f64.sqrt());
+
+        // 滞在時間が target を下回ったパケットに届いたら廃棄モードを抜ける
+        assert_eq!(q.pop(3450.0, &mut dropped).unwrap().packet_idx, 6);
+        assert_eq!(dropped, vec![1, 4]);
+        assert!(!q.codel.dropping);
+        assert_eq!(q.drops.codel, 2);
+    }
+
+    #[test]
+    fn codel_drop_interval_shrinks_with_count() {
+        let config = CodelConfig {
+            target_ms: 10.0,
+            interval_ms: 100.0,
+        };
+        let mut q = queue(QueuePolicy::Codel(config));
+        for i in 0..50 {
+            q.offer(item(i, 0.0, 0), 100, &mut || 0.0);
+        }
+        let mut dropped = Vec::new();
+        q.pop(50.0, &mut dropped); // 超え始め（first_above = 150）
+        q.pop(150.0, &mut dropped); // 廃棄モードに入る（count = 1）
+
+        // 予定時刻ちょうどに取り出すたびに1つ捨て、間隔は interval / sqrt(count)
+        let mut expected_next = 150.0 + 100.0;
+        for count in 2..6u32 {
+            let now = q.codel.drop_next_ms;
+            assert_eq!(now, expected_next);
+            q.pop(now, &mut dropped);
+            assert_eq!(q.codel.count, count);
+            expected_next = now + 100.0 / (count as f64).sqrt();
+        }
+        assert_eq!(dropped.len(), 5);
+        assert_eq!(q.drops.codel, 5);
+    }
+
+    #[test]
+    fn switching_out_of_priority_restores_arrival_order() {
+        let mut q = queue(QueuePolicy::Priority(PriorityClasses::default()));
+        for (idx, class) in [(0, 2), (1, 0), (2, 1)] {
+            q.offer(item(idx, idx as f64, class), 10, &mut || 0.0);
+        }
+        let by_class: Vec<usize> = q.iter().map(|q| q.packet_idx).collect();
+        assert_eq!(by_class, vec![1, 2, 0]);
+
+        // LIFOに切り替えると到着順に並び直し、最も新しいものから取り出す
+        q.set_policy(QueuePolicy::Lifo);
+        assert_eq!(q.len(), 3);
+        let mut dropped = Vec::new();
+        let order: Vec<usize> = std::iter::from_fn(|| q.pop(0.0, &mut dropped))
+            .map(|q| q.packet_idx)
+            .collect();
+        assert_eq!(order, vec![2, 1, 0]);
+        assert!(q.is_empty());
+    }
+}
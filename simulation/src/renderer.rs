@@ -2,8 +2,12 @@
 // WEBGPU RENDERER - 描画担当
 // =============================================================================
 
+use crate::packet_store::{MotionEvent, MotionUpdate};
+use crate::simulation::{HEIGHT, OFFSCREEN_MARGIN, WIDTH};
 use bytemuck::{Pod, Zeroable};
 use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 use web_sys::HtmlCanvasElement;
 use wgpu::util::DeviceExt;
@@ -22,8 +26,16 @@ pub struct GpuRenderer {
     pub device: Device,
     pub queue: Queue,
     pub render_pipeline: RenderPipeline,
-    pub packet_buffer: Buffer,
+    pub packet_position_buffer: Buffer,   // パケットの位置列 [x, y]
+    pub packet_appearance_buffer: Buffer, // パケットの見た目列 [r, g, b, size]
+    pub coords_appearance_buffer: Buffer, // 座標だけで描くとき（render_packets_gpu）の固定の見た目
+    pub node_position_buffer: Buffer,     // ノードの位置 [x, y]（縁と本体で2つずつ）
+    pub node_appearance_buffer: Buffer,   // ノードの見た目 [r, g, b, size]
     pub packet_count: u32,
+    pub node_count: u32,
+    pub coords_only: bool, // 直近の描画が座標だけか（見た目は coords_appearance_buffer を使う）
+    pub compute_supported: bool,     // コンピュートシェーダーが使えるか
+    pub motion: Option<GpuMotion>,   // GPU移動モードの資源（有効なときだけ）
     pub surface: Surface<'static>,
     #[allow(dead_code)]
     pub surface_config: SurfaceConfiguration,
@@ -37,15 +49,28 @@ pub struct GpuRenderer {
 
 // 初期化したGpuRendererインスタンスをプログラムのどこからでもアクセスできるように保持しておく場所。
 thread_local! {
-    pub static GPU_RENDERER: RefCell<Option<GpuRenderer>> = RefCell::new(None);
+    pub static GPU_RENDERER: RefCell<Option<GpuRenderer>> = const { RefCell::new(None) };
 }
 
 // WGSL言語で記述された頂点シェーダーとフラグメントシェーダーのソースコード（外部ファイルから読み込み）
 const SHADER_SOURCE: &str = include_str!("shader.wgsl");
 
+// GPU移動モードのコンピュートシェーダー
+const MOTION_SHADER_SOURCE: &str = include_str!("motion.wgsl");
+
 // 一度に描画できるパケットの最大数
 pub const MAX_PACKETS: usize = 100_000;
 
+// 一度に描画できるノード描画要素の最大数（1ノードにつき縁と本体の2つ）
+pub const MAX_NODE_ENTITIES: usize = 4_096;
+
+// 頂点バッファ1要素あたりのバイト数: 位置 [x, y] / 見た目 [r, g, b, size]
+const POSITION_STRIDE: u64 = std::mem::size_of::<[f32; 2]>() as u64;
+const APPEARANCE_STRIDE: u64 = std::mem::size_of::<[f32; 4]>() as u64;
+
+// 座標だけで描くときの見た目（白・小）
+const COORDS_APPEARANCE: [f32; 4] = [1.0, 1.0, 1.0, 3.0];
+
 // 背景色（#0d1117）
 const BG_COLOR: Color = Color {
     r: 0.050980392156862744,
@@ -109,13 +134,28 @@ pub async fn init_gpu_internal(canvas_id: &str) -> Result<(), JsValue> {
         }
     };
 
+    // コンピュートシェーダーが使えるなら、GPU移動モード用にストレージバッファの上限をアダプタに合わせる
+    let compute_supported = adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(DownlevelFlags::COMPUTE_SHADERS);
+    let required_limits = if compute_supported {
+        Limits {
+            max_storage_buffer_binding_size: adapter.limits().max_storage_buffer_binding_size,
+            max_buffer_size: adapter.limits().max_buffer_size,
+            ..Limits::downlevel_defaults()
+        }
+        .using_resolution(adapter.limits())
+    } else {
+        Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
+    };
+
     let (device, queue) = match adapter
         .request_device(
             &DeviceDescriptor {
                 label: None,
                 required_features: Features::empty(),
-                required_limits: Limits::downlevel_webgl2_defaults()
-                    .using_resolution(adapter.limits()),
+                required_limits,
                 memory_hints: MemoryHints::default(),
             },
             None,
@@ -196,37 +236,46 @@ pub async fn init_gpu_internal(canvas_id: &str) -> Result<(), JsValue> {
         source: ShaderSource::Wgsl(SHADER_SOURCE.into()),
     });
 
-    // 新しいバッファレイアウト: [x, y, r, g, b, size] = 6 floats per entity
+    // バッファレイアウト: スロット0 = 位置列 [x, y]、スロット1 = 見た目列 [r, g, b, size]
+    // パケットはシミュレーションの列をそのまま転送するため、列ごとに別の頂点バッファにする
     let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
         label: Some("Entity Render Pipeline"),
         layout: Some(&render_pipeline_layout),
         vertex: VertexState {
             module: &shader,
             entry_point: Some("vs_main"),
-            buffers: &[VertexBufferLayout {
-                array_stride: std::mem::size_of::<f32>() as u64 * 6, // x, y, r, g, b, size
-                step_mode: VertexStepMode::Instance,
-                attributes: &[
-                    // position (x, y)
-                    VertexAttribute {
-                        offset: 0,
-                        shader_location: 0,
-                        format: VertexFormat::Float32x2,
-                    },
-                    // color (r, g, b)
-                    VertexAttribute {
-                        offset: std::mem::size_of::<f32>() as u64 * 2,
-                        shader_location: 1,
-                        format: VertexFormat::Float32x3,
-                    },
-                    // size
-                    VertexAttribute {
-                        offset: std::mem::size_of::<f32>() as u64 * 5,
-                        shader_location: 2,
-                        format: VertexFormat::Float32,
-                    },
-                ],
-            }],
+            buffers: &[
+                VertexBufferLayout {
+                    array_stride: POSITION_STRIDE, // x, y
+                    step_mode: VertexStepMode::Instance,
+                    attributes: &[
+                        // position (x, y)
+                        VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: VertexFormat::Float32x2,
+                        },
+                    ],
+                },
+                VertexBufferLayout {
+                    array_stride: APPEARANCE_STRIDE, // r, g, b, size
+                    step_mode: VertexStepMode::Instance,
+                    attributes: &[
+                        // color (r, g, b)
+                        VertexAttribute {
+                            offset: 0,
+                            shader_location: 1,
+                            format: VertexFormat::Float32x3,
+                        },
+                        // size
+                        VertexAttribute {
+                            offset: std::mem::size_of::<f32>() as u64 * 3,
+                            shader_location: 2,
+                            format: VertexFormat::Float32,
+                        },
+                    ],
+                },
+            ],
             compilation_options: PipelineCompilationOptions::default(),
         },
         fragment: Some(FragmentState {
@@ -258,21 +307,46 @@ pub async fn init_gpu_internal(canvas_id: &str) -> Result<(), JsValue> {
         cache: None,
     });
 
-    // バッファサイズ: エンティティ数 * 6 floats (x, y, r, g, b, size)
-    let max_entities = 100_000;
-    let packet_buffer = device.create_buffer(&BufferDescriptor {
-        label: Some("Entity Buffer"),
-        size: (max_entities * 6 * std::mem::size_of::<f32>()) as u64,
-        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-        mapped_at_creation: false,
+    // 列ごとの頂点バッファ（パケットはMAX_PACKETS個、ノードはMAX_NODE_ENTITIES個まで）
+    let create_vertex_buffer = |label: &str, size: u64| {
+        device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    };
+    let packet_position_buffer =
+        create_vertex_buffer("Packet Position Buffer", MAX_PACKETS as u64 * POSITION_STRIDE);
+    let packet_appearance_buffer =
+        create_vertex_buffer("Packet Appearance Buffer", MAX_PACKETS as u64 * APPEARANCE_STRIDE);
+    let node_position_buffer =
+        create_vertex_buffer("Node Position Buffer", MAX_NODE_ENTITIES as u64 * POSITION_STRIDE);
+    let node_appearance_buffer = create_vertex_buffer(
+        "Node Appearance Buffer",
+        MAX_NODE_ENTITIES as u64 * APPEARANCE_STRIDE,
+    );
+    // 座標だけの描画は見た目が固定なので、初期化時に一度だけ書き込む
+    let coords_appearance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Coords Appearance Buffer"),
+        contents: bytemuck::cast_slice(&vec![COORDS_APPEARANCE; MAX_PACKETS]),
+        usage: BufferUsages::VERTEX,
     });
 
     let renderer = GpuRenderer {
         device,
         queue,
         render_pipeline,
-        packet_buffer,
+        packet_position_buffer,
+        packet_appearance_buffer,
+        coords_appearance_buffer,
+        node_position_buffer,
+        node_appearance_buffer,
         packet_count: 0,
+        node_count: 0,
+        coords_only: false,
+        compute_supported,
+        motion: None,
         surface,
         surface_config,
         canvas_width,
@@ -313,10 +387,13 @@ pub fn render_packets_gpu(coords: &[f32]) {
             }
 
             renderer.queue.write_buffer(
-                &renderer.packet_buffer,
+                &renderer.packet_position_buffer,
                 0,
                 bytemuck::cast_slice(coords_to_render),
             );
+            renderer.packet_count = packet_count as u32;
+            renderer.node_count = 0;
+            renderer.coords_only = true;
 
             let current_time = (now() / 1000.0) as f32;
             let time_data = TimeUniform {
@@ -368,18 +445,13 @@ pub fn render_packets_gpu(coords: &[f32]) {
                         timestamp_writes: None,
                     });
 
-                    render_pass.set_pipeline(&renderer.render_pipeline);
-                    render_pass.set_bind_group(0, &renderer.time_bind_group, &[]);
-                    let buffer_size = (packet_count * 2 * std::mem::size_of::<f32>()) as u64;
-                    render_pass.set_vertex_buffer(0, renderer.packet_buffer.slice(0..buffer_size));
-                    render_pass.draw(0..4, 0..packet_count as u32);
+                    draw_entities(&mut render_pass, renderer);
                 }
 
                 renderer.queue.submit(Some(encoder.finish()));
             }
 
             surface_texture.present();
-            renderer.packet_count = packet_count as u32;
             log(&format!(
                 "[Rust/Wasm] Rendered {} packets successfully",
                 packet_count
@@ -395,8 +467,7 @@ pub fn render_frame_internal() {
     GPU_RENDERER.with(|renderer_ref| {
         let mut renderer_opt = renderer_ref.borrow_mut();
         if let Some(renderer) = renderer_opt.as_mut() {
-            let packet_count = renderer.packet_count as usize;
-            if packet_count == 0 {
+            if renderer.packet_count == 0 && renderer.node_count == 0 {
                 return;
             }
 
@@ -444,11 +515,7 @@ pub fn render_frame_internal() {
                         timestamp_writes: None,
                     });
 
-                    render_pass.set_pipeline(&renderer.render_pipeline);
-                    render_pass.set_bind_group(0, &renderer.time_bind_group, &[]);
-                    let buffer_size = (packet_count * 2 * std::mem::size_of::<f32>()) as u64;
-                    render_pass.set_vertex_buffer(0, renderer.packet_buffer.slice(0..buffer_size));
-                    render_pass.draw(0..4, 0..packet_count as u32);
+                    draw_entities(&mut render_pass, renderer);
                 }
 
                 renderer.queue.submit(Some(encoder.finish()));
@@ -459,15 +526,23 @@ pub fn render_frame_internal() {
     });
 }
 
-/// エンティティデータ形式: [x, y, r, g, b, size] の配列
-/// ノードとパケットを一緒に描画
-pub fn render_simulation_frame_internal(entity_data: &[f32]) {
+/// ノードとパケットを一緒に描画（ノードが下、パケットが上）
+/// 位置 [x, y] と見た目 [r, g, b, size] は同じ長さの列で渡す
+/// パケットの列はシミュレーションが保持しているものをそのまま頂点バッファへ転送する（詰め直し・コピーなし）
+pub fn render_simulation_frame_internal(
+    node_positions: &[[f32; 2]],
+    node_appearance: &[[f32; 4]],
+    packet_positions: &[[f32; 2]],
+    packet_appearance: &[[f32; 4]],
+) {
     GPU_RENDERER.with(|renderer_ref| {
         let mut renderer_opt = renderer_ref.borrow_mut();
         if let Some(renderer) = renderer_opt.as_mut() {
-            // エンティティ数を計算（6 floats per entity）
-            let entity_count = entity_data.len() / 6;
-            let entity_count = entity_count.min(MAX_PACKETS);
+            let node_count = node_positions.len().min(node_appearance.len()).min(MAX_NODE_ENTITIES);
+            let packet_count = packet_positions
+                .len()
+                .min(packet_appearance.len())
+                .min(MAX_PACKETS);
 
             // タイムユニフォームを更新
             let current_time = (now() / 1000.0) as f32;
@@ -491,15 +566,35 @@ pub fn render_simulation_frame_internal(entity_data: &[f32]) {
                 .texture
                 .create_view(&TextureViewDescriptor::default());
 
-            // エンティティがある場合はバッファに書き込み
-            if entity_count > 0 {
-                let data_to_render = &entity_data[0..(entity_count * 6)];
+            // 列をそのままバッファに書き込み
+            if node_count > 0 {
                 renderer.queue.write_buffer(
-                    &renderer.packet_buffer,
+                    &renderer.node_position_buffer,
                     0,
-                    bytemuck::cast_slice(data_to_render),
+                    bytemuck::cast_slice(&node_positions[..node_count]),
+                );
+                renderer.queue.write_buffer(
+                    &renderer.node_appearance_buffer,
+                    0,
+                    bytemuck::cast_slice(&node_appearance[..node_count]),
+                );
+            }
+            // GPU移動モードではパケットの列はGPU側にあるので転送しない
+            if packet_count > 0 && renderer.motion.is_none() {
+                renderer.queue.write_buffer(
+                    &renderer.packet_position_buffer,
+                    0,
+                    bytemuck::cast_slice(&packet_positions[..packet_count]),
+                );
+                renderer.queue.write_buffer(
+                    &renderer.packet_appearance_buffer,
+                    0,
+                    bytemuck::cast_slice(&packet_appearance[..packet_count]),
                 );
             }
+            renderer.node_count = node_count as u32;
+            renderer.packet_count = packet_count as u32;
+            renderer.coords_only = false;
 
             {
                 let mut encoder =
@@ -525,20 +620,466 @@ pub fn render_simulation_frame_internal(entity_data: &[f32]) {
                         timestamp_writes: None,
                     });
 
-                    if entity_count > 0 {
-                        render_pass.set_pipeline(&renderer.render_pipeline);
-                        render_pass.set_bind_group(0, &renderer.time_bind_group, &[]);
-                        let buffer_size = (entity_count * 6 * std::mem::size_of::<f32>()) as u64;
-                        render_pass.set_vertex_buffer(0, renderer.packet_buffer.slice(0..buffer_size));
-                        render_pass.draw(0..4, 0..entity_count as u32);
-                    }
+                    draw_entities(&mut render_pass, renderer);
                 }
 
                 renderer.queue.submit(Some(encoder.finish()));
             }
 
             surface_texture.present();
-            renderer.packet_count = entity_count as u32;
         }
     });
 }
+
+// バッファに書き込み済みのノードとパケットを描画する（ノードが下、パケットが上）
+fn draw_entities(render_pass: &mut RenderPass<'_>, renderer: &GpuRenderer) {
+    render_pass.set_pipeline(&renderer.render_pipeline);
+    render_pass.set_bind_group(0, &renderer.time_bind_group, &[]);
+
+    let node_count = renderer.node_count as u64;
+    if node_count > 0 {
+        render_pass.set_vertex_buffer(
+            0,
+            renderer.node_position_buffer.slice(0..node_count * POSITION_STRIDE),
+        );
+        render_pass.set_vertex_buffer(
+            1,
+            renderer.node_appearance_buffer.slice(0..node_count * APPEARANCE_STRIDE),
+        );
+        render_pass.draw(0..4, 0..node_count as u32);
+    }
+
+    // GPU移動モード: コンピュートシェーダーが更新した位置バッファをそのまま描く
+    if let Some(motion) = renderer.motion.as_ref() {
+        let slot_count = motion.slot_count as u64;
+        if slot_count > 0 {
+            render_pass.set_vertex_buffer(
+                0,
+                motion.position_buffer.slice(0..slot_count * POSITION_STRIDE),
+            );
+            render_pass.set_vertex_buffer(
+                1,
+                motion.appearance_buffer.slice(0..slot_count * APPEARANCE_STRIDE),
+            );
+            render_pass.draw(0..4, 0..slot_count as u32);
+        }
+        return;
+    }
+
+    let packet_count = renderer.packet_count as u64;
+    if packet_count > 0 {
+        let appearance_buffer = if renderer.coords_only {
+            &renderer.coords_appearance_buffer
+        } else {
+            &renderer.packet_appearance_buffer
+        };
+        render_pass.set_vertex_buffer(
+            0,
+            renderer.packet_position_buffer.slice(0..packet_count * POSITION_STRIDE),
+        );
+        render_pass.set_vertex_buffer(1, appearance_buffer.slice(0..packet_count * APPEARANCE_STRIDE));
+        render_pass.draw(0..4, 0..packet_count as u32);
+    }
+}
+
+// =============================================================================
+// GPU MOTION - コンピュートシェーダーでのパケット移動
+// 位置・速度・ターゲットをストレージバッファに置いてGPUで進め、到達・消滅のイベントだけを読み戻す
+// CPUからは変わったスロットだけを送る（MotionUpdate）
+// =============================================================================
+
+// 移動先として参照できるノードの最大数
+pub const MAX_GPU_NODES: usize = 4_096;
+
+// 1回の読み戻しで受け取れるイベントの最大数（あふれたパケットは止まらずに次の回で書き直す）
+pub const MAX_MOTION_EVENTS: usize = 65_536;
+
+// コンピュートシェーダーのワークグループサイズ（motion.wgsl と同じ）
+const MOTION_WORKGROUP_SIZE: u32 = 64;
+
+// イベントログ: 先頭16バイトが件数（残りはパディング）、以降16バイトずつ [slot, seq, kind, _]
+const EVENT_HEADER_SIZE: u64 = 16;
+const EVENT_SIZE: u64 = 16;
+const EVENT_LOG_SIZE: u64 = EVENT_HEADER_SIZE + MAX_MOTION_EVENTS as u64 * EVENT_SIZE;
+
+// 読み戻し用バッファの状態
+const READBACK_IDLE: u32 = 0; // 次の移動でコピーできる
+const READBACK_PENDING: u32 = 1; // マップ待ち
+const READBACK_READY: u32 = 2; // マップ済み（イベントを取り出せる）
+
+// CPUから送る1スロット分の移動状態（motion.wgsl の PacketMotion と同じレイアウト）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct GpuPacketMotion {
+    velocity: [f32; 2],
+    speed: f32,
+    target_node: i32,
+    seq: u32,
+    moving: u32,
+    _padding: [u32; 2],
+}
+
+// コンピュートシェーダーのパラメータ（motion.wgsl の Params と同じレイアウト）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct MotionParams {
+    count: u32,
+    node_count: u32,
+    max_events: u32,
+    delta_ms: f32,
+    bounds_min: [f32; 2],
+    bounds_max: [f32; 2],
+}
+
+// GPU移動モードの資源
+pub struct GpuMotion {
+    pipeline: ComputePipeline,
+    bind_group: BindGroup,
+    params_buffer: Buffer,
+    pub position_buffer: Buffer,   // パケットの位置 [x, y]（GPUが更新し、そのまま頂点バッファとして描く）
+    pub appearance_buffer: Buffer, // パケットの見た目 [r, g, b, size]
+    motion_buffer: Buffer,         // パケットの移動状態（GpuPacketMotion）
+    node_buffer: Buffer,           // ノード位置 [x, y]
+    event_buffer: Buffer,          // 到達・消滅イベントのログ
+    readback_buffer: Buffer,       // イベントログの読み戻し先
+    readback_state: Arc<AtomicU32>,
+    capacity: usize,               // スロット数
+    pub slot_count: u32,           // 直近の移動で処理したスロット数
+    scratch_positions: Vec<[f32; 2]>, // 連続したスロットをまとめて送るための作業領域
+    scratch_appearance: Vec<[f32; 4]>,
+    scratch_motion: Vec<GpuPacketMotion>,
+}
+
+impl GpuMotion {
+    fn new(device: &Device, capacity: usize) -> GpuMotion {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Motion Shader"),
+            source: ShaderSource::Wgsl(MOTION_SHADER_SOURCE.into()),
+        });
+
+        let storage_entry = |binding: u32, read_only: bool| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("motion_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, false), // positions
+                storage_entry(2, false), // motion
+                storage_entry(3, true),  // nodes
+                storage_entry(4, false), // events
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Motion Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Motion Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let create_buffer = |label: &str, size: u64, usage: BufferUsages| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let slots = capacity as u64;
+        let params_buffer = create_buffer(
+            "Motion Params Buffer",
+            std::mem::size_of::<MotionParams>() as u64,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        );
+        let position_buffer = create_buffer(
+            "Motion Position Buffer",
+            slots * POSITION_STRIDE,
+            BufferUsages::STORAGE | BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        );
+        let appearance_buffer = create_buffer(
+            "Motion Appearance Buffer",
+            slots * APPEARANCE_STRIDE,
+            BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        );
+        let motion_buffer = create_buffer(
+            "Motion State Buffer",
+            slots * std::mem::size_of::<GpuPacketMotion>() as u64,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        );
+        let node_buffer = create_buffer(
+            "Motion Node Buffer",
+            MAX_GPU_NODES as u64 * POSITION_STRIDE,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        );
+        let event_buffer = create_buffer(
+            "Motion Event Buffer",
+            EVENT_LOG_SIZE,
+            BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+        );
+        let readback_buffer = create_buffer(
+            "Motion Readback Buffer",
+            EVENT_LOG_SIZE,
+            BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        );
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("motion_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: position_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: motion_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: node_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: event_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        GpuMotion {
+            pipeline,
+            bind_group,
+            params_buffer,
+            position_buffer,
+            appearance_buffer,
+            motion_buffer,
+            node_buffer,
+            event_buffer,
+            readback_buffer,
+            readback_state: Arc::new(AtomicU32::new(READBACK_IDLE)),
+            capacity,
+            slot_count: 0,
+            scratch_positions: Vec::new(),
+            scratch_appearance: Vec::new(),
+            scratch_motion: Vec::new(),
+        }
+    }
+}
+
+// GPU移動モードの資源を用意する（capacity: シミュレーションのスロット数）
+// 使用中のスロットはシミュレーション側が有効化時にすべて送り直す
+pub fn init_gpu_motion(capacity: usize) -> Result<(), String> {
+    GPU_RENDERER.with(|renderer_ref| {
+        let mut renderer_opt = renderer_ref.borrow_mut();
+        let Some(renderer) = renderer_opt.as_mut() else {
+            return Err("GPU renderer not initialized".to_string());
+        };
+        if !renderer.compute_supported {
+            return Err("Compute shaders are not supported by this adapter".to_string());
+        }
+        let limits = renderer.device.limits();
+        let motion_size = capacity as u64 * std::mem::size_of::<GpuPacketMotion>() as u64;
+        if motion_size > limits.max_storage_buffer_binding_size as u64
+            || motion_size > limits.max_buffer_size
+        {
+            return Err(format!(
+                "{} packet slots exceed the storage buffer limit ({} bytes)",
+                capacity, limits.max_storage_buffer_binding_size
+            ));
+        }
+        renderer.motion = Some(GpuMotion::new(&renderer.device, capacity.max(1)));
+        log(&format!(
+            "[Rust/Wasm] GPU motion initialized for {} packet slots",
+            capacity
+        ));
+        Ok(())
+    })
+}
+
+// GPU移動モードの資源を捨てる（描画は通常のパケット列の転送に戻る）
+pub fn disable_gpu_motion() {
+    GPU_RENDERER.with(|renderer_ref| {
+        if let Some(renderer) = renderer_ref.borrow_mut().as_mut() {
+            renderer.motion = None;
+        }
+    });
+}
+
+// 読み戻しが済んでいれば、到達・消滅イベントを out に追加する
+pub fn take_gpu_motion_events(out: &mut Vec<MotionEvent>) {
+    GPU_RENDERER.with(|renderer_ref| {
+        let mut renderer_opt = renderer_ref.borrow_mut();
+        let Some(motion) = renderer_opt.as_mut().and_then(|r| r.motion.as_mut()) else {
+            return;
+        };
+        if motion.readback_state.load(Ordering::Acquire) != READBACK_READY {
+            return;
+        }
+        {
+            let data = motion.readback_buffer.slice(..).get_mapped_range();
+            let words: &[u32] = bytemuck::cast_slice(&data);
+            let count = (words[0] as usize).min(MAX_MOTION_EVENTS);
+            let header_words = (EVENT_HEADER_SIZE / 4) as usize;
+            for item in words[header_words..].chunks_exact(4).take(count) {
+                out.push(MotionEvent {
+                    slot: item[0] as usize,
+                    seq: item[1],
+                    vanished: item[2] == 1,
+                });
+            }
+        }
+        motion.readback_buffer.unmap();
+        motion.readback_state.store(READBACK_IDLE, Ordering::Release);
+    });
+}
+
+// 変わったスロットとノード位置を送り、コンピュートシェーダーでパケットを1ステップ進める
+// 読み戻し用バッファが空いていれば、イベントログをコピーして非同期に読み戻す
+pub fn step_gpu_motion(
+    updates: &[MotionUpdate],
+    nodes: &[[f32; 2]],
+    slot_count: usize,
+    delta_ms: f64,
+) {
+    GPU_RENDERER.with(|renderer_ref| {
+        let mut renderer_opt = renderer_ref.borrow_mut();
+        let Some(renderer) = renderer_opt.as_mut() else {
+            return;
+        };
+        let Some(motion) = renderer.motion.as_mut() else {
+            return;
+        };
+        let queue = &renderer.queue;
+
+        // 連続したスロットはまとめて1回で送る（updates はスロット順）
+        let mut start = 0;
+        while start < updates.len() {
+            let mut end = start + 1;
+            while end < updates.len() && updates[end].slot == updates[end - 1].slot + 1 {
+                end += 1;
+            }
+            let run = &updates[start..end];
+            start = end;
+            let first = run[0].slot;
+            if first + run.len() > motion.capacity {
+                continue;
+            }
+
+            motion.scratch_positions.clear();
+            motion.scratch_appearance.clear();
+            motion.scratch_motion.clear();
+            for update in run {
+                motion.scratch_positions.push(update.position);
+                motion.scratch_appearance.push(update.appearance);
+                motion.scratch_motion.push(GpuPacketMotion {
+                    velocity: update.velocity,
+                    speed: update.speed,
+                    target_node: update.target_node_idx,
+                    seq: update.seq,
+                    moving: update.moving as u32,
+                    _padding: [0; 2],
+                });
+            }
+            queue.write_buffer(
+                &motion.position_buffer,
+                first as u64 * POSITION_STRIDE,
+                bytemuck::cast_slice(&motion.scratch_positions),
+            );
+            queue.write_buffer(
+                &motion.appearance_buffer,
+                first as u64 * APPEARANCE_STRIDE,
+                bytemuck::cast_slice(&motion.scratch_appearance),
+            );
+            queue.write_buffer(
+                &motion.motion_buffer,
+                first as u64 * std::mem::size_of::<GpuPacketMotion>() as u64,
+                bytemuck::cast_slice(&motion.scratch_motion),
+            );
+        }
+
+        let node_count = nodes.len().min(MAX_GPU_NODES);
+        if node_count > 0 {
+            queue.write_buffer(&motion.node_buffer, 0, bytemuck::cast_slice(&nodes[..node_count]));
+        }
+
+        let count = slot_count.min(motion.capacity);
+        let params = MotionParams {
+            count: count as u32,
+            node_count: node_count as u32,
+            max_events: MAX_MOTION_EVENTS as u32,
+            delta_ms: delta_ms as f32,
+            bounds_min: [-OFFSCREEN_MARGIN, -OFFSCREEN_MARGIN],
+            bounds_max: [WIDTH + OFFSCREEN_MARGIN, HEIGHT + OFFSCREEN_MARGIN],
+        };
+        queue.write_buffer(&motion.params_buffer, 0, bytemuck::cast_slice(&[params]));
+
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Motion Encoder"),
+            });
+        if count > 0 {
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Motion Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&motion.pipeline);
+            compute_pass.set_bind_group(0, &motion.bind_group, &[]);
+            compute_pass.dispatch_workgroups((count as u32).div_ceil(MOTION_WORKGROUP_SIZE), 1, 1);
+        }
+
+        // 前回の読み戻しが終わっていなければ、イベントはログに溜めたまま次の回に回す
+        let readback = motion.readback_state.load(Ordering::Acquire) == READBACK_IDLE;
+        if readback {
+            encoder.copy_buffer_to_buffer(
+                &motion.event_buffer,
+                0,
+                &motion.readback_buffer,
+                0,
+                EVENT_LOG_SIZE,
+            );
+            encoder.clear_buffer(&motion.event_buffer, 0, Some(EVENT_HEADER_SIZE));
+        }
+        queue.submit(Some(encoder.finish()));
+
+        if readback {
+            motion.readback_state.store(READBACK_PENDING, Ordering::Release);
+            let state = Arc::clone(&motion.readback_state);
+            motion
+                .readback_buffer
+                .slice(..)
+                .map_async(MapMode::Read, move |result| {
+                    let next = if result.is_ok() { READBACK_READY } else { READBACK_IDLE };
+                    state.store(next, Ordering::Release);
+                });
+        }
+        motion.slot_count = count as u32;
+    });
+}
@@ -0,0 +1,691 @@
+// =============================================================================
+// REPLAY - 外部からの操作の記録・再生担当
+// JSから受けた操作（ノード追加・移動・パケット生成・ステージロード・tick等）を受けた時点のシミュレーション時刻とともに記録し、
+// 同じ順序で適用し直すことで同じ実行を再現する（スコアの検証・不具合報告への添付用）
+// =============================================================================
+
+use crate::simulation::SimulationState;
+use crate::stage::{LayoutConfig, LoadedStage, StageConfig};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// 記録形式のバージョン（操作の種類・項目を変えたら上げる。違うバージョンは再生しない）
+pub const REPLAY_VERSION: u16 = 1;
+
+/// 外部からの操作（wasm APIの1呼び出しに対応）
+/// 結果に影響しない取得系・描画系の呼び出しは記録しない
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Command {
+    CreateSimulation {
+        max_packets: usize,
+        seed: u64,
+    },
+    LoadStage {
+        config: Box<StageConfig>,
+    },
+    /// レイアウトファイルの一括配置（ヘッドレス実行の --layout）
+    ApplyLayout {
+        layout: LayoutConfig,
+    },
+    ResetStageWaves,
+    TriggerWavesUntil {
+        time_ms: u32,
+    },
+    Tick {
+        delta_ms: f64,
+    },
+    Reset,
+    AddNode {
+        id: u32,
+        x: f32,
+        y: f32,
+        node_type: u32,
+    },
+    ClearNodes,
+    UpdateNodePosition {
+        id: u32,
+        x: f32,
+        y: f32,
+    },
+    PlaceNode {
+        node_type: String,
+        grade: String,
+        x: f32,
+        y: f32,
+    },
+    RemoveNode {
+        id: u32,
+    },
+    UpgradeNode {
+        id: u32,
+        grade: String,
+    },
+    SpawnWave {
+        x: f32,
+        y: f32,
+        target_x: f32,
+        target_y: f32,
+        count: usize,
+        duration_ms: f64,
+        base_speed: f32,
+        speed_variance: f32,
+        packet_type: u32,
+        complexity: u8,
+    },
+    SpawnWaveToNode {
+        x: f32,
+        y: f32,
+        target_node_idx: i32,
+        count: usize,
+        duration_ms: f64,
+        base_speed: f32,
+        speed_variance: f32,
+        packet_type: u32,
+        complexity: u8,
+    },
+    DebugSpawn {
+        x: f32,
+        y: f32,
+        count: usize,
+    },
+    SetLbStrategy {
+        id: u32,
+        strategy: u32,
+    },
+    CrashNode {
+        id: u32,
+    },
+    DegradeNode {
+        id: u32,
+    },
+    SetNodeHealthConfig {
+        id: u32,
+        degraded_time_ms: f64,
+        down_time_ms: f64,
+        restart_time_ms: f64,
+        crash_policy: u32,
+    },
+    SetFirewallConfig {
+        id: u32,
+        detection_rate: f32,
+        false_positive_rate: f32,
+        throughput_per_sec: f64,
+    },
+    SetRateLimitConfig {
+        id: u32,
+        rate_per_sec: f64,
+        burst: f64,
+        reject_policy: u32,
+        per_client: bool,
+    },
+    SetQueuePolicy {
+        id: u32,
+        policy_json: String,
+    },
+    SetCacheConfig {
+        id: u32,
+        hit_ratio: f32,
+        ttl_ms: f64,
+    },
+    FlushCache {
+        id: u32,
+    },
+    SetRetryPolicy {
+        timeout_ms: f64,
+        max_retries: u32,
+        backoff_base_ms: f64,
+        backoff_multiplier: f64,
+        backoff_max_ms: f64,
+        jitter: f64,
+    },
+    SetClientCount {
+        count: u32,
+    },
+    SetWaveClient {
+        wave_id: u32,
+        client_id: u32,
+    },
+    AddLink {
+        from_id: u32,
+        to_id: u32,
+    },
+    SetLinkProfile {
+        from_id: u32,
+        to_id: u32,
+        latency_ms: f64,
+        bandwidth: f64,
+        loss_rate: f32,
+    },
+    RemoveLink {
+        from_id: u32,
+        to_id: u32,
+    },
+    ClearLinks,
+}
+
+/// 記録した操作1つ
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedCommand {
+    pub at_ms: f64, // 受けた時点のシミュレーション時刻（シミュレーション未作成なら0）
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+/// 操作の記録（JSON形式で書き出す）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u16,
+    pub gpu_motion: bool, // GPU移動モードを使ったか（使った記録はCPUで再生すると一致しないことがある）
+    pub commands: Vec<RecordedCommand>,
+}
+
+/// 再生中のシミュレーションとステージ（wasm APIのグローバル状態と同じ組）
+#[derive(Default)]
+pub struct Session {
+    pub sim: Option<SimulationState>,
+    pub stage: Option<LoadedStage>,
+}
+
+/// 記録の読み込み・再生のエラー
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// 対応していないバージョン
+    UnsupportedVersion { version: u16 },
+    /// JSONとして読めない・構造が合わない
+    Decode(String),
+    /// 操作を受けた時刻が記録と違う（再生した実行が記録した実行と食い違った）
+    Diverged {
+        index: usize,
+        expected_ms: f64,
+        actual_ms: f64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::UnsupportedVersion { version } => write!(
+                f,
+                "unsupported replay version {} (expected {})",
+                version, REPLAY_VERSION
+            ),
+            ReplayError::Decode(msg) => write!(f, "failed to decode replay: {}", msg),
+            ReplayError::Diverged {
+                index,
+                expected_ms,
+                actual_ms,
+            } => write!(
+                f,
+                "replay diverged at command {}: recorded at {}ms, replayed at {}ms",
+                index, expected_ms, actual_ms
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            gpu_motion: false,
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl Replay {
+    /// 操作を記録
+    pub fn record(&mut self, at_ms: f64, command: Command) {
+        self.commands.push(RecordedCommand { at_ms, command });
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Replay, ReplayError> {
+        let replay: Replay =
+            serde_json::from_str(json).map_err(|e| ReplayError::Decode(e.to_string()))?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion {
+                version: replay.version,
+            });
+        }
+        Ok(replay)
+    }
+
+    /// 記録した操作を順に適用し、最後の状態を返す
+    /// 各操作の前に時刻を記録と突き合わせ、ずれたらそこで止める
+    pub fn run(&self) -> Result<Session, ReplayError> {
+        let mut session = Session::default();
+        for (index, recorded) in self.commands.iter().enumerate() {
+            let actual_ms = session.current_time();
+            if actual_ms.to_bits() != recorded.at_ms.to_bits() {
+                return Err(ReplayError::Diverged {
+                    index,
+                    expected_ms: recorded.at_ms,
+                    actual_ms,
+                });
+            }
+            recorded.command.apply(&mut session);
+        }
+        Ok(session)
+    }
+}
+
+impl Session {
+    /// 現在のシミュレーション時刻（シミュレーション未作成なら0）
+    pub fn current_time(&self) -> f64 {
+        self.sim.as_ref().map_or(0.0, |sim| sim.get_current_time())
+    }
+}
+
+impl Command {
+    /// 操作を適用（wasm APIの同名の関数と同じ処理。未作成・未ロードなら何もしない）
+    pub fn apply(&self, session: &mut Session) {
+        let Session { sim, stage } = session;
+        match self {
+            Command::CreateSimulation { max_packets, seed } => {
+                *sim = Some(SimulationState::with_seed(*max_packets, *seed));
+            }
+            Command::LoadStage { config } => {
+                let mut loaded = LoadedStage::new(config.as_ref().clone());
+                if let Some(sim) = sim.as_mut() {
+                    loaded.place_fixed_nodes(sim);
+                }
+                *stage = Some(loaded);
+            }
+            Command::ApplyLayout { layout } => {
+                if let (Some(stage), Some(sim)) = (stage.as_mut(), sim.as_mut()) {
+                    stage.apply_layout(sim, layout);
+                }
+            }
+            Command::ResetStageWaves => {
+                if let Some(stage) = stage.as_mut() {
+                    stage.reset_waves();
+                }
+            }
+            // ステージがあれば固定ステップごとにWaveを発火させながら進める
+            Command::Tick { delta_ms } => match (stage.as_mut(), sim.as_mut()) {
+                (Some(stage), Some(sim)) => {
+                    stage.tick(sim, *delta_ms);
+                }
+                (None, Some(sim)) => sim.tick(*delta_ms),
+                _ => {}
+            },
+            Command::TriggerWavesUntil { time_ms } => {
+                if let (Some(stage), Some(sim)) = (stage.as_mut(), sim.as_mut()) {
+                    stage.trigger_waves_until(sim, *time_ms);
+                }
+            }
+            Command::PlaceNode {
+                node_type,
+                grade,
+                x,
+                y,
+            } => {
+                if let (Some(stage), Some(sim)) = (stage.as_mut(), sim.as_mut()) {
+                    let _ = stage.place_node(sim, node_type, grade, *x, *y);
+                }
+            }
+            Command::RemoveNode { id } => {
+                if let (Some(stage), Some(sim)) = (stage.as_mut(), sim.as_mut()) {
+                    let _ = stage.remove_node(sim, *id);
+                }
+            }
+            Command::UpgradeNode { id, grade } => {
+                if let (Some(stage), Some(sim)) = (stage.as_mut(), sim.as_mut()) {
+                    let _ = stage.upgrade_node(sim, *id, grade);
+                }
+            }
+            command => {
+                if let Some(sim) = sim.as_mut() {
+                    command.apply_to_simulation(sim);
+                }
+            }
+        }
+    }
+
+    /// シミュレーションだけを操作するもの
+    fn apply_to_simulation(&self, sim: &mut SimulationState) {
+        match *self {
+            Command::Reset => sim.reset(),
+            Command::AddNode {
+                id,
+                x,
+                y,
+                node_type,
+            } => sim.add_node(id, x, y, node_type),
+            Command::ClearNodes => sim.clear_nodes(),
+            Command::UpdateNodePosition { id, x, y } => sim.update_node_position(id, x, y),
+            Command::SpawnWave {
+                x,
+                y,
+                target_x,
+                target_y,
+                count,
+                duration_ms,
+                base_speed,
+                speed_variance,
+                packet_type,
+                complexity,
+            } => {
+                sim.spawn_wave(
+                    x,
+                    y,
+                    target_x,
+                    target_y,
+                    count,
+                    duration_ms,
+                    base_speed,
+                    speed_variance,
+                    packet_type,
+                    complexity,
+                );
+            }
+            Command::SpawnWaveToNode {
+                x,
+                y,
+                target_node_idx,
+                count,
+                duration_ms,
+                base_speed,
+                speed_variance,
+                packet_type,
+                complexity,
+            } => {
+                sim.spawn_wave_to_node(
+                    x,
+                    y,
+                    target_node_idx,
+                    count,
+                    duration_ms,
+                    base_speed,
+                    speed_variance,
+                    packet_type,
+                    complexity,
+                );
+            }
+            Command::DebugSpawn { x, y, count } => sim.debug_spawn(x, y, count),
+            Command::SetLbStrategy { id, strategy } => {
+                sim.set_lb_strategy(id, strategy);
+            }
+            Command::CrashNode { id } => {
+                sim.crash_node(id);
+            }
+            Command::DegradeNode { id } => {
+                sim.degrade_node(id);
+            }
+            Command::SetNodeHealthConfig {
+                id,
+                degraded_time_ms,
+                down_time_ms,
+                restart_time_ms,
+                crash_policy,
+            } => {
+                sim.set_node_health_config(
+                    id,
+                    degraded_time_ms,
+                    down_time_ms,
+                    restart_time_ms,
+                    crash_policy,
+                );
+            }
+            Command::SetFirewallConfig {
+                id,
+                detection_rate,
+                false_positive_rate,
+                throughput_per_sec,
+            } => {
+                sim.set_firewall_config(
+                    id,
+                    detection_rate,
+                    false_positive_rate,
+                    throughput_per_sec,
+                );
+            }
+            Command::SetRateLimitConfig {
+                id,
+                rate_per_sec,
+                burst,
+                reject_policy,
+                per_client,
+            } => {
+                sim.set_rate_limit_config(id, rate_per_sec, burst, reject_policy, per_client);
+            }
+            Command::SetQueuePolicy {
+                id,
+                ref policy_json,
+            } => {
+                sim.set_queue_policy_json(id, policy_json);
+            }
+            Command::SetCacheConfig {
+                id,
+                hit_ratio,
+                ttl_ms,
+            } => {
+                sim.set_cache_config(id, hit_ratio, ttl_ms);
+            }
+            Command::FlushCache { id } => {
+                sim.flush_cache(id);
+            }
+            Command::SetRetryPolicy {
+                timeout_ms,
+                max_retries,
+                backoff_base_ms,
+                backoff_multiplier,
+                backoff_max_ms,
+                jitter,
+            } => sim.set_retry_policy(
+                timeout_ms,
+                max_retries,
+                backoff_base_ms,
+                backoff_multiplier,
+                backoff_max_ms,
+                jitter,
+            ),
+            Command::SetClientCount { count } => sim.set_client_count(count),
+            Command::SetWaveClient { wave_id, client_id } => {
+                sim.set_wave_client(wave_id, client_id);
+            }
+            Command::AddLink { from_id, to_id } => {
+                sim.add_link(from_id, to_id);
+            }
+            Command::SetLinkProfile {
+                from_id,
+                to_id,
+                latency_ms,
+                bandwidth,
+                loss_rate,
+            } => {
+                sim.set_link_profile(from_id, to_id, latency_ms, bandwidth, loss_rate);
+            }
+            Command::RemoveLink { from_id, to_id } => {
+                sim.remove_link(from_id, to_id);
+            }
+            Command::ClearLinks => sim.clear_links(),
+            // ステージを伴う操作は apply で処理済み
+            Command::CreateSimulation { .. }
+            | Command::LoadStage { .. }
+            | Command::ApplyLayout { .. }
+            | Command::ResetStageWaves
+            | Command::Tick { .. }
+            | Command::TriggerWavesUntil { .. }
+            | Command::PlaceNode { .. }
+            | Command::RemoveNode { .. }
+            | Command::UpgradeNode { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::PacketType;
+
+    const STAGE_JSON: &str = r#"{
+        "meta": { "title": "Replay", "description": "", "budget": 300, "sla_target": 0.9 },
+        "grades": {
+            "server": { "small": { "max_concurrent": 3, "process_time_ms": 60, "queue_capacity": 8, "cost": 100 } }
+        },
+        "map": { "fixed_nodes": [
+            { "id": "gateway", "type": "gateway", "x": 100, "y": 540 },
+            { "id": "lb", "type": "lb", "x": 400, "y": 540 },
+            { "id": "s1", "type": "server", "grade": "small", "x": 800, "y": 400 }
+        ] },
+        "waves": [
+            { "time_start_ms": 0, "source_id": "gateway", "count": 150, "duration_ms": 1500, "packet_type": "NORMAL", "speed": 0.4 }
+        ]
+    }"#;
+
+    // wasm APIと同じく、受けた時点の時刻で記録してから適用する
+    fn send(session: &mut Session, replay: &mut Replay, command: Command) {
+        replay.record(session.current_time(), command.clone());
+        command.apply(session);
+    }
+
+    // ステージのロード・配置・障害・可変のフレーム時間を含む実行を記録する
+    fn record_session() -> (Session, Replay) {
+        let mut session = Session::default();
+        let mut replay = Replay::default();
+        send(
+            &mut session,
+            &mut replay,
+            Command::CreateSimulation {
+                max_packets: 2000,
+                seed: 11,
+            },
+        );
+        send(
+            &mut session,
+            &mut replay,
+            Command::LoadStage {
+                config: Box::new(serde_json::from_str(STAGE_JSON).unwrap()),
+            },
+        );
+        send(
+            &mut session,
+            &mut replay,
+            Command::PlaceNode {
+                node_type: "server".to_string(),
+                grade: "small".to_string(),
+                x: 800.0,
+                y: 700.0,
+            },
+        );
+        for frame in 0..600u32 {
+            let now_ms = session.current_time() as u32;
+            send(
+                &mut session,
+                &mut replay,
+                Command::TriggerWavesUntil { time_ms: now_ms },
+            );
+            match frame {
+                60 => send(&mut session, &mut replay, Command::CrashNode { id: 3 }),
+                90 => send(
+                    &mut session,
+                    &mut replay,
+                    Command::SpawnWaveToNode {
+                        x: 100.0,
+                        y: 540.0,
+                        target_node_idx: 0,
+                        count: 40,
+                        duration_ms: 300.0,
+                        base_speed: 0.5,
+                        speed_variance: 0.1,
+                        packet_type: PacketType::HeavyTask as u32,
+                        complexity: 30,
+                    },
+                ),
+                _ => {}
+            }
+            let delta_ms = if frame % 3 == 0 { 6.94 } else { 16.67 };
+            send(&mut session, &mut replay, Command::Tick { delta_ms });
+        }
+        (session, replay)
+    }
+
+    fn stats_of(session: &Session) -> String {
+        let sim = session.sim.as_ref().unwrap();
+        let result = session.stage.as_ref().unwrap().evaluate(sim);
+        serde_json::to_string(&(result, sim.latency_summary())).unwrap()
+    }
+
+    #[test]
+    fn replay_reproduces_recorded_session() {
+        let (live, replay) = record_session();
+        let sim = live.sim.as_ref().unwrap();
+        assert!(sim.get_stats_processed() > 0);
+        assert!(sim.get_stats_dropped() > 0);
+
+        let replayed = replay.run().unwrap();
+        assert_eq!(stats_of(&replayed), stats_of(&live));
+        assert_eq!(replayed.current_time(), live.current_time());
+
+        // JSONを経由しても同じ
+        let decoded = Replay::from_json(&replay.to_json()).unwrap();
+        assert_eq!(stats_of(&decoded.run().unwrap()), stats_of(&live));
+    }
+
+    #[test]
+    fn changed_timestamp_is_reported_as_diverged() {
+        let (_, mut replay) = record_session();
+        let index = replay
+            .commands
+            .iter()
+            .position(|c| matches!(c.command, Command::CrashNode { .. }))
+            .unwrap();
+        let recorded_ms = replay.commands[index].at_ms;
+        replay.commands[index].at_ms += 1.0;
+
+        assert_eq!(
+            replay.run().err(),
+            Some(ReplayError::Diverged {
+                index,
+                expected_ms: recorded_ms + 1.0,
+                actual_ms: recorded_ms,
+            })
+        );
+    }
+
+    #[test]
+    fn missing_tick_is_reported_as_diverged() {
+        let (_, mut replay) = record_session();
+        let index = replay
+            .commands
+            .iter()
+            .rposition(|c| matches!(c.command, Command::Tick { .. }))
+            .unwrap()
+            - 10;
+        let removed = replay.commands.remove(index);
+        assert!(matches!(removed.command, Command::Tick { .. }));
+
+        // 消したtickの直後の操作で時刻が合わなくなる
+        assert!(matches!(
+            replay.run(),
+            Err(ReplayError::Diverged { index: i, .. }) if i == index
+        ));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let replay = Replay {
+            version: REPLAY_VERSION + 1,
+            ..Replay::default()
+        };
+        assert_eq!(
+            Replay::from_json(&replay.to_json()).err(),
+            Some(ReplayError::UnsupportedVersion {
+                version: REPLAY_VERSION + 1
+            })
+        );
+        assert!(matches!(
+            Replay::from_json("[]"),
+            Err(ReplayError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+// =============================================================================
+// RNG - シード付き疑似乱数生成担当
+// 同じシードなら同じ乱数列になるため、シミュレーション結果を再現できる
+// =============================================================================
+
+use serde::{Deserialize, Serialize};
+
+// シード未指定時のデフォルト値
+pub const DEFAULT_SEED: u64 = 0x5EED_0000_0000_0001;
+
+// SplitMix64の加算定数（黄金比）
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// シード付き疑似乱数生成器（SplitMix64）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    /// シードから生成器を作成
+    pub fn new(seed: u64) -> Self {
+        SimRng { state: seed }
+    }
+
+    /// 64bitの乱数
+    pub fn next_u64(&mut self) -> u64 {
+        let z = self.state;
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix64(z)
+    }
+
+    /// [0, 1) の乱数（f32）
+    pub fn next_f32(&mut self) -> f32 {
+        // 上位24bitを仮数として使う
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// 64bitハッシュ（SplitMix64の最終化関数）
+pub fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(GOLDEN_GAMMA);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_splitmix64_reference_sequence() {
+        // シード0の既知の出力列
+        let mut rng = SimRng::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+        assert_eq!(rng.next_u64(), 0x06C4_5D18_8009_454F);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        let mut a = SimRng::new(DEFAULT_SEED);
+        let mut b = SimRng::new(DEFAULT_SEED);
+        let mut c = SimRng::new(DEFAULT_SEED + 1);
+        let seq_a: Vec<u64> = (0..100).map(|_| a.next_u64()).collect();
+        let seq_b: Vec<u64> = (0..100).map(|_| b.next_u64()).collect();
+        let seq_c: Vec<u64> = (0..100).map(|_| c.next_u64()).collect();
+        assert_eq!(seq_a, seq_b);
+        assert_ne!(seq_a, seq_c);
+    }
+
+    #[test]
+    fn restored_state_continues_the_sequence() {
+        let mut rng = SimRng::new(42);
+        for _ in 0..10 {
+            rng.next_u64();
+        }
+        let json = serde_json::to_string(&rng).unwrap();
+        let mut restored: SimRng = serde_json::from_str(&json).unwrap();
+        for _ in 0..10 {
+            assert_eq!(restored.next_u64(), rng.next_u64());
+        }
+    }
+
+    #[test]
+    fn f32_stays_in_the_unit_interval() {
+        let mut rng = SimRng::new(7);
+        let values: Vec<f32> = (0..10_000).map(|_| rng.next_f32()).collect();
+        assert!(values.iter().all(|v| (0.0..1.0).contains(v)));
+        // 偏りがない（平均がほぼ0.5）
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!((mean - 0.5).abs() < 0.02);
+    }
+}
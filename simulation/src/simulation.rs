@@ -2,26 +2,70 @@
 // SIMULATION ENGINE - パケット生成・シミュレーションロジック担当
 // =============================================================================
 
+use crate::autoscale::{AutoscalingGroup, AutoscalingStatus, ScaleAction, ScalingPolicy};
+use crate::client::{ClientState, PendingRetry, RequestOrigin, RetryPolicy};
+use crate::console::log;
+use crate::events::{DropReason, EventLog, SimEvent, SimEventKind};
+use crate::link::{Link, LinkProfile, LinkStatus};
+use crate::load_balancer::{Candidate, LbState, LbStrategy};
+use crate::metrics::{LatencyMetrics, LatencySummary, SimulationStats};
+use crate::node_metrics::{NodeMetrics, NodeMetricsConfig};
+use crate::packet_store::{MotionEvent, MotionUpdate, PacketStore};
+use crate::queue::{Enqueue, PacketQueue, QueueDrop, QueueDrops, QueuePolicy, QueuedPacket};
+use crate::rng::{SimRng, DEFAULT_SEED};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 // キャンバスサイズ定数
 pub const WIDTH: f32 = 1920.0;
 pub const HEIGHT: f32 = 1080.0;
 
-// JS側の関数（console.log）をRustで使うための宣言
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+// ノード到達とみなす距離（ピクセル）
+pub const ARRIVAL_RADIUS: f32 = 5.0;
 
-// JavaScriptのMath.random()を使用
-fn js_random() -> f32 {
-    js_sys::Math::random() as f32
-}
+// 1ステップで進める時間（tickの経過時間はこの単位に区切って進め、端数は次のtickへ持ち越す）
+pub const FIXED_STEP_MS: f64 = 1000.0 / 240.0;
+
+// 1回のtickで進める最大ステップ数（1秒分）。バックグラウンドから戻ったタブなどで溜まった分はこれを超えたら捨てる
+pub const MAX_STEPS_PER_TICK: u32 = 240;
+
+// 座標指定モードのパケットが消える範囲（画面の外側にこれだけ余白を取る）
+pub const OFFSCREEN_MARGIN: f32 = 50.0;
+
+// パケットが記録できる経路の最大ホップ数（レスポンスの戻り経路に使用）
+pub const MAX_ROUTE_HOPS: usize = 8;
+
+// リンク未定義時に使う従来の経路順（Gateway -> Firewall -> RateLimiter -> LB -> Server -> Cache -> DB）
+// 存在しないタイプは飛ばす
+const LEGACY_CHAIN: [u32; 7] = [0, 4, 6, 1, 2, 5, 3];
+
+// クライアント数のデフォルト（一貫性ハッシュ等のクライアントキーの種類数）
+const DEFAULT_CLIENT_COUNT: u32 = 64;
+
+// Waveに属さないパケット（debug_spawn等）のwave_id
+pub const NO_WAVE: u32 = u32::MAX;
+
+// RateLimiterが返す429応答のサイズ（本文なしのため通常のリクエストと同じ）
+const REJECT_RESPONSE_SIZE: f32 = 1.0;
+
+// 複雑度の基準値（HeavyTaskは complexity / BASE_COMPLEXITY 倍の処理時間）
+pub const BASE_COMPLEXITY: u8 = 10;
+
+// SYN Floodが接続待ちとしてキュー枠を占有する時間
+pub const SYN_HOLD_TIME_MS: f64 = 3000.0;
+
+// 劣化中（Degraded）のノードの処理時間の倍率
+pub const DEGRADED_SLOWDOWN: f64 = 3.0;
+
+// 状態遷移にかかる時間のデフォルト
+pub const DEFAULT_DEGRADED_TIME_MS: f64 = 5000.0; // Degraded -> Healthy
+pub const DEFAULT_DOWN_TIME_MS: f64 = 3000.0;     // Down -> Restarting
+pub const DEFAULT_RESTART_TIME_MS: f64 = 2000.0;  // Restarting -> Healthy
 
 /// パケットタイプの列挙型
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PacketType {
@@ -31,8 +75,15 @@ pub enum PacketType {
     Killer = 3,
 }
 
+impl PacketType {
+    /// 攻撃パケット（SynFlood / Killer）か。攻撃はSLAの分母に含めない
+    pub fn is_attack(packet_type: u32) -> bool {
+        packet_type == PacketType::SynFlood as u32 || packet_type == PacketType::Killer as u32
+    }
+}
+
 /// ノードタイプの列挙型
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NodeType {
@@ -40,10 +91,188 @@ pub enum NodeType {
     LB = 1,      // ロードバランサー
     Server = 2,  // アプリケーションサーバー
     DB = 3,      // データベース
+    Firewall = 4, // 攻撃パケットを検知して遮断する（WAF）
+    Cache = 5,    // ヒットしたリクエストはDBに行かずに応答する
+    RateLimiter = 6, // トークンバケットで流量を制限する
+}
+
+/// ノードの稼働状態
+/// Healthy -> Degraded -> Healthy（劣化から自然回復）
+/// Healthy/Degraded -> Down -> Restarting -> Healthy（停止から再起動）
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum NodeHealth {
+    #[default]
+    Healthy = 0,    // 正常
+    Degraded = 1,   // 劣化中（処理時間がDEGRADED_SLOWDOWN倍）
+    Down = 2,       // 停止中（受け付けたパケットはドロップ、LBの振り分け対象外）
+    Restarting = 3, // 再起動中（Downと同じく受け付けない）
+}
+
+/// ノード停止時に処理中・待機中だったパケットの扱い
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashPolicy {
+    #[default]
+    Drop = 0,    // すべてドロップ
+    Requeue = 1, // リクエストは直前のノードから別の候補へ送り直す（レスポンスはドロップ）
+}
+
+impl CrashPolicy {
+    /// 数値（JSから渡される値）からポリシーを取得
+    pub fn from_u32(value: u32) -> Option<CrashPolicy> {
+        match value {
+            0 => Some(CrashPolicy::Drop),
+            1 => Some(CrashPolicy::Requeue),
+            _ => None,
+        }
+    }
+}
+
+/// ノードの障害・復旧設定（ステージJSONではグレードごとに指定）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    pub degraded_time_ms: f64, // Degradedが続く時間
+    pub down_time_ms: f64,     // Downが続く時間（その後Restartingへ）
+    pub restart_time_ms: f64,  // Restartingが続く時間（その後Healthyへ）
+    pub crash_policy: CrashPolicy,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            degraded_time_ms: DEFAULT_DEGRADED_TIME_MS,
+            down_time_ms: DEFAULT_DOWN_TIME_MS,
+            restart_time_ms: DEFAULT_RESTART_TIME_MS,
+            crash_policy: CrashPolicy::Drop,
+        }
+    }
+}
+
+/// Firewallの検知設定（ステージJSONではグレードごとに指定）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FirewallConfig {
+    pub detection_rate: f32,      // 攻撃パケットを遮断する確率（0.0 - 1.0）
+    pub false_positive_rate: f32, // 正常なパケットを誤って遮断する確率（0.0 - 1.0）
+    pub throughput_per_sec: f64,  // 1秒あたりに検査できるパケット数（0 = 無制限、超過分はドロップ）
+}
+
+impl Default for FirewallConfig {
+    fn default() -> Self {
+        FirewallConfig {
+            detection_rate: 0.9,
+            false_positive_rate: 0.01,
+            throughput_per_sec: 0.0,
+        }
+    }
+}
+
+/// Cacheの設定（ステージJSONではグレードごとに指定）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub hit_ratio: f32, // キャッシュ可能なリクエストの割合（温まりきったときのヒット率、0.0 - 1.0）
+    pub ttl_ms: f64,    // エントリの有効期間
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            hit_ratio: 0.8,
+            ttl_ms: 10000.0,
+        }
+    }
+}
+
+/// RateLimiterで制限を超えたリクエストの扱い
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectPolicy {
+    #[default]
+    Drop = 0,            // その場でドロップ（クライアントはタイムアウトまで待つ）
+    TooManyRequests = 1, // 429相当の小さな応答をすぐに返す（処理完了には数えない）
+}
+
+impl RejectPolicy {
+    /// 数値（JSから渡される値）からポリシーを取得
+    pub fn from_u32(value: u32) -> Option<RejectPolicy> {
+        match value {
+            0 => Some(RejectPolicy::Drop),
+            1 => Some(RejectPolicy::TooManyRequests),
+            _ => None,
+        }
+    }
+}
+
+/// RateLimiterの設定（ステージJSONではグレードごとに指定）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub rate_per_sec: f64, // 1秒あたりに補充するトークン数（0 = 無制限）
+    pub burst: f64,        // バケットの容量（一度に通せる最大数）
+    pub reject: RejectPolicy,
+    pub per_client: bool, // trueならクライアントキーごとに別のバケットを持つ
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            rate_per_sec: 50.0,
+            burst: 20.0,
+            reject: RejectPolicy::Drop,
+            per_client: false,
+        }
+    }
+}
+
+/// トークンバケット（1秒あたりの通過数の上限）
+/// 容量（burst）までトークンを貯められ、バーストを許容する
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct TokenBucket {
+    tokens: f64,
+    last_refill_ms: f64,
+}
+
+impl TokenBucket {
+    /// 満タンのバケット
+    pub fn full(capacity: f64) -> Self {
+        TokenBucket {
+            tokens: capacity.max(0.0),
+            last_refill_ms: 0.0,
+        }
+    }
+
+    /// 経過時間分のトークンを補充し、1つ取れればtrue（rate_per_sec <= 0 なら無制限）
+    pub fn try_take(&mut self, now_ms: f64, rate_per_sec: f64, capacity: f64) -> bool {
+        if rate_per_sec <= 0.0 {
+            return true;
+        }
+        let elapsed_ms = (now_ms - self.last_refill_ms).max(0.0);
+        self.tokens = (self.tokens + elapsed_ms * rate_per_sec / 1000.0).min(capacity);
+        self.last_refill_ms = now_ms;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 満タンの状態に戻す
+    pub fn reset(&mut self, capacity: f64) {
+        *self = TokenBucket::full(capacity);
+    }
 }
 
 /// ノードスペック（グレードごとの性能）
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct NodeSpec {
     pub max_concurrent: u32,    // 同時処理可能数
     pub process_time_ms: f64,   // 1パケットの処理時間（ミリ秒）
@@ -53,7 +282,7 @@ pub struct NodeSpec {
 }
 
 /// ノード構造体（目的地となるオブジェクト）
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Node {
     pub x: f32,
     pub y: f32,
@@ -62,29 +291,51 @@ pub struct Node {
     pub spec: NodeSpec, // 性能スペック
     // 状態（動的）
     pub processing_packets: Vec<ProcessingPacket>, // 処理中のパケット
-    pub queue: Vec<QueuedPacket>,                  // 待機キュー
+    pub queue: PacketQueue,                        // 待機キュー（キュー規律ごとの取り出し・廃棄）
+    pub syn_backlog: Vec<HeldPacket>,              // SYN Floodが占有しているキュー枠
+    pub health: NodeHealth,                        // 稼働状態
+    pub health_until_ms: f64,                      // 現在の状態（Healthy以外）が終わる時刻
+    pub health_config: HealthConfig,               // 障害・復旧設定
+    pub draining: bool,                            // 削除待ち（新しいリクエストを受けない、オートスケール用）
+    pub firewall: FirewallConfig,                  // 検知設定（Firewallノードのみ使用）
+    pub firewall_bucket: TokenBucket,              // 検査スループットの上限（Firewallノードのみ使用）
+    pub total_filtered: u32,                        // Firewallで遮断した数
+    pub cache: CacheConfig,                         // キャッシュ設定（Cacheノードのみ使用）
+    pub cache_entries: HashMap<u32, f64>,           // クライアントキー -> エントリの期限（Cacheノードのみ使用）
+    pub cache_hits: u32,                            // キャッシュヒット数
+    pub cache_misses: u32,                          // キャッシュミス数
+    pub rate_limit: RateLimitConfig,                // 流量制限の設定（RateLimiterノードのみ使用）
+    pub rate_bucket: TokenBucket,                   // 全体のバケット（per_client = false のとき使用）
+    pub client_buckets: HashMap<u32, TokenBucket>,  // クライアントキー -> バケット（per_client = true のとき使用）
+    pub total_rate_limited: u32,                    // RateLimiterで拒否した数
     pub total_processed: u32,                       // 処理完了数
     pub total_dropped: u32,                         // ドロップ数
+    pub lb_strategy: LbStrategy,                    // 振り分け戦略（LBノードのみ使用）
+    pub lb_state: LbState,                          // 振り分け状態（ラウンドロビン位置など）
+    pub metrics: NodeMetrics,                       // 時系列メトリクス（一定間隔で記録）
+    pub saturated: bool,                            // 飽和中（処理枠もキューも埋まってから回復するまで）
 }
 
 /// 処理中のパケット情報
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ProcessingPacket {
     pub packet_idx: usize,      // パケットのインデックス
     pub remaining_time_ms: f64, // 残り処理時間
+    pub process_time_ms: f64,   // 処理時間（処理開始時に決まった値、平均処理時間の集計用）
     pub packet_size: f32,       // パケットサイズ（帯域計算用）
 }
 
-/// キュー内で待機中のパケット
-#[derive(Clone, Debug)]
-pub struct QueuedPacket {
+/// 応答せずにキュー枠を占有しているパケット（SYN Flood）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeldPacket {
     pub packet_idx: usize,
+    pub remaining_time_ms: f64, // 枠を解放するまでの時間
 }
 
-impl Node {
-    pub fn new(id: u32, x: f32, y: f32, node_type: u32) -> Self {
-        // デフォルトスペック（node_typeに応じて設定）
-        let spec = match node_type {
+impl NodeSpec {
+    /// ノードタイプごとのデフォルトスペック
+    pub fn default_for(node_type: u32) -> NodeSpec {
+        match node_type {
             0 => NodeSpec { // Gateway: 無制限（通過のみ）
                 max_concurrent: 10000,
                 process_time_ms: 0.0,
@@ -113,8 +364,36 @@ impl Node {
                 cost: 200,
                 bandwidth_factor: 0.2, // DB: I/O帯域制限
             },
+            4 => NodeSpec { // Firewall: 検査のみの軽い処理
+                max_concurrent: 200,
+                process_time_ms: 5.0,
+                queue_capacity: 200,
+                cost: 120,
+                bandwidth_factor: 0.1, // Firewall: ヘッダ検査のためサイズ影響小
+            },
+            5 => NodeSpec { // Cache: メモリ上の読み出しで高速
+                max_concurrent: 100,
+                process_time_ms: 5.0,
+                queue_capacity: 100,
+                cost: 100,
+                bandwidth_factor: 0.1, // Cache: メモリ帯域のためサイズ影響小
+            },
+            6 => NodeSpec { // RateLimiter: カウンタを見るだけの軽い処理
+                max_concurrent: 200,
+                process_time_ms: 2.0,
+                queue_capacity: 200,
+                cost: 80,
+                bandwidth_factor: 0.1, // RateLimiter: ヘッダのみ参照のためサイズ影響小
+            },
             _ => NodeSpec::default(),
-        };
+        }
+    }
+}
+
+impl Node {
+    pub fn new(id: u32, x: f32, y: f32, node_type: u32) -> Self {
+        // デフォルトスペック（node_typeに応じて設定）
+        let spec = NodeSpec::default_for(node_type);
 
         Node {
             x,
@@ -123,9 +402,29 @@ impl Node {
             node_type,
             spec,
             processing_packets: Vec::new(),
-            queue: Vec::new(),
+            queue: PacketQueue::default(),
+            syn_backlog: Vec::new(),
+            health: NodeHealth::Healthy,
+            health_until_ms: 0.0,
+            health_config: HealthConfig::default(),
+            draining: false,
+            firewall: FirewallConfig::default(),
+            firewall_bucket: TokenBucket::default(),
+            total_filtered: 0,
+            cache: CacheConfig::default(),
+            cache_entries: HashMap::new(),
+            cache_hits: 0,
+            cache_misses: 0,
+            rate_limit: RateLimitConfig::default(),
+            rate_bucket: TokenBucket::full(RateLimitConfig::default().burst),
+            client_buckets: HashMap::new(),
+            total_rate_limited: 0,
             total_processed: 0,
             total_dropped: 0,
+            lb_strategy: LbStrategy::default(),
+            lb_state: LbState::default(),
+            metrics: NodeMetrics::default(),
+            saturated: false,
         }
     }
 
@@ -134,9 +433,86 @@ impl Node {
         self.processing_packets.len() as u32
     }
 
-    /// キュー内パケット数
+    /// キュー内パケット数（SYN Floodが占有している枠を含む）
     pub fn queue_size(&self) -> u32 {
-        self.queue.len() as u32
+        (self.queue.len() + self.syn_backlog.len()) as u32
+    }
+
+    /// 実際に処理を行うノード（Server / DB）か
+    /// HeavyTask・SYN Flood・Killerの影響を受けるのはこのノードのみ
+    pub fn is_worker(&self) -> bool {
+        self.node_type == NodeType::Server as u32 || self.node_type == NodeType::DB as u32
+    }
+
+    /// Firewallノードか
+    pub fn is_firewall(&self) -> bool {
+        self.node_type == NodeType::Firewall as u32
+    }
+
+    /// Cacheノードか
+    pub fn is_cache(&self) -> bool {
+        self.node_type == NodeType::Cache as u32
+    }
+
+    /// RateLimiterノードか
+    pub fn is_rate_limiter(&self) -> bool {
+        self.node_type == NodeType::RateLimiter as u32
+    }
+
+    /// パケットを受け付けられる状態（Healthy / Degraded）か
+    pub fn is_available(&self) -> bool {
+        matches!(self.health, NodeHealth::Healthy | NodeHealth::Degraded)
+    }
+
+    /// 新しいリクエストを振り分けてよいか（稼働中 かつ 削除待ちでない）
+    pub fn accepts_requests(&self) -> bool {
+        self.is_available() && !self.draining
+    }
+
+    /// 劣化中か
+    pub fn is_degraded(&self) -> bool {
+        self.health == NodeHealth::Degraded
+    }
+
+    /// 状態を変更し、その状態が終わる時刻を設定
+    fn set_health(&mut self, health: NodeHealth, now_ms: f64) {
+        self.health = health;
+        self.health_until_ms = now_ms
+            + match health {
+                NodeHealth::Healthy => 0.0,
+                NodeHealth::Degraded => self.health_config.degraded_time_ms,
+                NodeHealth::Down => self.health_config.down_time_ms,
+                NodeHealth::Restarting => self.health_config.restart_time_ms,
+            };
+    }
+
+    /// 経過時間による状態遷移を進める（状態が変わった場合はtrue）
+    fn advance_health(&mut self, now_ms: f64) -> bool {
+        let before = self.health;
+        while self.health != NodeHealth::Healthy && now_ms >= self.health_until_ms {
+            let next = match self.health {
+                NodeHealth::Down => NodeHealth::Restarting,
+                _ => NodeHealth::Healthy,
+            };
+            self.set_health(next, self.health_until_ms);
+        }
+        self.health != before
+    }
+
+    /// パケット1つの処理時間
+    /// サイズ（帯域）・HeavyTaskの複雑度・劣化による倍率をかける
+    pub fn process_time_for(&self, packet: &Packet) -> f64 {
+        let size_multiplier = 1.0 + (packet.size as f64 - 1.0) * self.spec.bandwidth_factor;
+        let mut time = self.spec.process_time_ms * size_multiplier;
+        if self.is_worker() {
+            if packet.packet_type == PacketType::HeavyTask as u32 && !packet.is_response {
+                time *= packet.complexity as f64 / BASE_COMPLEXITY as f64;
+            }
+            if self.is_degraded() {
+                time *= DEGRADED_SLOWDOWN;
+            }
+        }
+        time
     }
 
     /// 負荷率（0.0 - 1.0+）
@@ -149,30 +525,42 @@ impl Node {
 }
 
 /// パケット状態
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PacketState {
     Moving = 0,     // 移動中
     Processing = 1, // ノードで処理中
     Queued = 2,     // ノードのキューで待機中
+    OnLink = 3,     // 伝送特性を持つリンク上を移動中（位置はリンクが決める）
 }
 
 /// シミュレーション用パケット構造体
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Packet {
     pub x: f32,
     pub y: f32,
-    pub velocity_x: f32,
+    pub velocity_x: f32,      // 座標指定モードの速度（ピクセル/ミリ秒）
     pub velocity_y: f32,
     pub active: u32,          // 0: inactive, 1: active
     pub packet_type: u32,     // PacketType as u32
     pub complexity: u8,       // 処理の重さ係数
     pub target_node_idx: i32, // 目標ノードのインデックス (-1 = 宛先なし)
-    pub speed: f32,           // 移動速度（ピクセル/フレーム）
+    pub speed: f32,           // 移動速度（ピクセル/ミリ秒）
     pub state: PacketState,   // 現在の状態
     pub current_node_idx: i32, // 現在いるノードのインデックス (-1 = 移動中)
     pub is_response: bool,    // レスポンスパケットかどうか
     pub size: f32,            // パケットサイズ（リクエスト: 1.0, レスポンス: 大きい値）
     pub origin_server_idx: i32, // リクエスト時に通過したサーバーのインデックス (-1 = 未設定)
+    pub route: [u16; MAX_ROUTE_HOPS], // リクエストが通過したノードの履歴（ノードインデックス）
+    pub route_len: u8,        // route の有効長
+    pub client_id: u32,       // 送信元クライアントのキー（一貫性ハッシュ用）
+    pub spawn_time_ms: f64,   // リクエストの生成時刻（シミュレーション時間）
+    pub wave_id: u32,         // 生成元のWave (NO_WAVE = Waveなし)
+    pub rejected: bool,       // RateLimiterが返した429応答か（完了しても処理数に数えない）
+    pub request_id: u32,      // クライアントが期限を管理するリクエストID（0 = 未追跡）
+    #[serde(with = "crate::snapshot::non_finite")]
+    pub deadline_ms: f64,     // クライアントが応答を待つ期限（f64::INFINITY = 期限なし）
+    pub retry: u32,           // 何回目の送り直しか（0 = 最初の送信）
+    pub abandoned: bool,      // クライアントがタイムアウトで諦めたか（ノードは処理を続けるが、完了しても数えない）
 }
 
 impl Default for Packet {
@@ -186,19 +574,29 @@ impl Default for Packet {
             packet_type: 0,
             complexity: 0,
             target_node_idx: -1,
-            speed: 3.0,
+            speed: 0.18,
             state: PacketState::Moving,
             current_node_idx: -1,
             is_response: false,
             size: 1.0,  // デフォルトはリクエストサイズ
             origin_server_idx: -1, // 未設定
+            route: [0; MAX_ROUTE_HOPS],
+            route_len: 0,
+            client_id: 0,
+            spawn_time_ms: 0.0,
+            wave_id: NO_WAVE,
+            rejected: false,
+            request_id: 0,
+            deadline_ms: f64::INFINITY,
+            retry: 0,
+            abandoned: false,
         }
     }
 }
 
 /// パケット生成予約タスク
 /// spawn_waveで登録し、tick()で徐々に生成する
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct SpawnTask {
     x: f32,
     y: f32,
@@ -208,53 +606,52 @@ struct SpawnTask {
     total_count: usize,   // 生成する総数
     spawned_count: usize, // 生成済みの数
     duration_ms: f64,     // 何ミリ秒かけて放出するか
-    base_speed: f32,
-    speed_variance: f32,
+    base_speed: f32,     // 移動速度（ピクセル/ミリ秒）
+    speed_variance: f32, // 速度のばらつき（base_speed ± speed_variance）
     packet_type: u32,
     complexity: u8,
     start_time: f64, // タスク開始時刻（performance.now()）
-}
-
-/// シミュレーション統計
-#[wasm_bindgen]
-#[derive(Clone, Copy, Debug, Default)]
-pub struct SimulationStats {
-    pub packets_spawned: u32,    // 生成されたパケット総数
-    pub packets_processed: u32,  // 正常に処理完了したパケット数（DB到達）
-    pub packets_dropped: u32,    // ドロップ/失敗したパケット数
-    pub packets_in_flight: u32,  // 現在処理中のパケット数
+    wave_id: u32,    // レイテンシ集計用のWave ID
+    client_id: Option<u32>, // 固定の送信元クライアントキー（Noneなら順番に割り当て）
 }
 
 /// シミュレーション状態を管理する構造体
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize)]
 pub struct SimulationState {
-    packets: Vec<Packet>,
+    packets: PacketStore, // パケット状態（列ごとに保持）
     nodes: Vec<Node>, // ノード（目的地）のリスト
+    links: Vec<Link>, // ノード間リンク（空の場合は従来のタイプ順ルーティング）
     max_packets: usize,
     spawn_queue: Vec<SpawnTask>,
     current_time: f64,
+    step_remainder_ms: f64, // まだ進めていない経過時間（FIXED_STEP_MS未満）
     stats: SimulationStats, // 統計情報
+    latency: LatencyMetrics, // エンドツーエンドのレイテンシ（全体・Waveごと）
+    client_count: u32,      // クライアントキーの種類数
+    next_client_seq: u32,   // 次に割り当てるクライアントキーの連番
+    seed: u64,              // 乱数シード（reset時に再利用）
+    rng: SimRng,            // シード付き乱数生成器
+    autoscaling_groups: Vec<AutoscalingGroup>, // オートスケーリンググループ
+    client: ClientState,    // クライアントのタイムアウト・リトライ
+    metrics_config: NodeMetricsConfig, // ノードの時系列メトリクスの記録間隔・サンプル数
+    metrics_window_start_ms: f64,      // 集計中の記録間隔が始まった時刻
+    waves_draining: Vec<u32>,          // 生成し終わり、リクエストが残っているWave
+    #[serde(skip)]
+    events: EventLog,       // JSが取り出すまでのイベント（スナップショットには含めない）
+    #[serde(skip)]
+    gpu_motion: bool,       // 移動をGPUのコンピュートシェーダーに任せるか（スナップショットには含めない）
+    #[serde(skip)]
+    motion_events: Vec<MotionEvent>, // GPUから届いた、まだ反映していない到達・消滅
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl SimulationState {
-    /// 新しいSimulationStateを作成
+    /// 新しいSimulationStateを作成（デフォルトシード）
     /// max_packets: 同時に存在できるパケットの最大数
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(max_packets: usize) -> SimulationState {
-        let packets = vec![Packet::default(); max_packets];
-        log(&format!(
-            "[Rust/Wasm] SimulationState created with {} packet slots",
-            max_packets
-        ));
-        SimulationState {
-            packets,
-            nodes: Vec::new(), // ノードリスト初期化
-            max_packets,
-            spawn_queue: Vec::new(),
-            current_time: 0.0,
-            stats: SimulationStats::default(),
-        }
+        SimulationState::with_seed(max_packets, DEFAULT_SEED)
     }
 
     /// ノードを追加（JSから呼び出し）
@@ -268,6 +665,7 @@ impl SimulationState {
     }
 
     /// スペック付きでノードを追加
+    #[allow(clippy::too_many_arguments)]
     pub fn add_node_with_spec(
         &mut self,
         id: u32,
@@ -280,13 +678,7 @@ impl SimulationState {
         cost: u32,
     ) {
         // ノードタイプに応じたデフォルト帯域係数
-        let bandwidth_factor = match node_type {
-            0 => 0.0,  // Gateway: サイズ影響なし
-            1 => 0.5,  // LB: パケットサイズの影響を受ける
-            2 => 0.3,  // Server: 処理能力で帯域制限
-            3 => 0.2,  // DB: I/O帯域制限
-            _ => 0.0,
-        };
+        let bandwidth_factor = NodeSpec::default_for(node_type).bandwidth_factor;
         
         let mut node = Node::new(id, x, y, node_type);
         node.spec = NodeSpec {
@@ -303,12 +695,369 @@ impl SimulationState {
         self.nodes.push(node);
     }
 
-    /// すべてのノードをクリア
+    /// ノードのスペックを変更（グレード変更用、帯域係数は維持）
+    /// 処理中・キュー内のパケットはそのまま残る
+    pub fn set_node_spec(
+        &mut self,
+        id: u32,
+        max_concurrent: u32,
+        process_time_ms: f64,
+        queue_capacity: u32,
+        cost: u32,
+    ) -> bool {
+        let Some(node) = self.nodes.iter_mut().find(|n| n.id == id) else {
+            log(&format!(
+                "[Rust/Wasm] Warning: Node with id={} not found for spec update",
+                id
+            ));
+            return false;
+        };
+        node.spec.max_concurrent = max_concurrent;
+        node.spec.process_time_ms = process_time_ms;
+        node.spec.queue_capacity = queue_capacity;
+        node.spec.cost = cost;
+        log(&format!(
+            "[Rust/Wasm] Node spec updated: id={}, max_concurrent={}, process_time={}ms, queue={}, cost={}",
+            id, max_concurrent, process_time_ms, queue_capacity, cost
+        ));
+        true
+    }
+
+    /// ノードを削除（接続するリンクも削除）
+    /// このノードに向かっている・滞在中・経路に含むパケットはドロップし、
+    /// 残りのパケットが持つノードインデックスを詰め直す
+    pub fn remove_node(&mut self, id: u32) -> bool {
+        let Some(removed_idx) = self.nodes.iter().position(|n| n.id == id) else {
+            log(&format!(
+                "[Rust/Wasm] Warning: Node with id={} not found for removal",
+                id
+            ));
+            return false;
+        };
+
+        // 削除するノードに関わるパケットを集める（参照をすべて見つけたらそこで探すのをやめる）
+        let mut remaining = self.packets.node_refs(removed_idx);
+        let mut doomed = Vec::new();
+        for packet_idx in 0..self.packets.slot_count() {
+            if remaining == 0 {
+                break;
+            }
+            if !self.packets.is_active(packet_idx) {
+                continue;
+            }
+            let refs = self.packets.refs_to(packet_idx, removed_idx);
+            if refs > 0 {
+                remaining -= refs;
+                doomed.push(packet_idx);
+            }
+        }
+        // ノード・リンクからはまとめて1回で取り除き、それからドロップとして数える
+        if !doomed.is_empty() {
+            let mut is_doomed = vec![false; self.packets.slot_count()];
+            for &packet_idx in &doomed {
+                is_doomed[packet_idx] = true;
+            }
+            for node in self.nodes.iter_mut() {
+                node.processing_packets.retain(|proc| !is_doomed[proc.packet_idx]);
+                node.queue.retain(|q| !is_doomed[q.packet_idx]);
+                node.syn_backlog.retain(|held| !is_doomed[held.packet_idx]);
+            }
+            for link in self.links.iter_mut() {
+                link.in_flight.retain(|t| !is_doomed[t.packet_idx]);
+            }
+            for packet_idx in doomed {
+                self.discard_packet(packet_idx, Some(removed_idx), DropReason::NodeRemoved);
+            }
+        }
+        // 生成し終わらずに消えるWaveは、残りのリクエストが終わった時点で終了とする
+        self.waves_draining.extend(
+            self.spawn_queue
+                .iter()
+                .filter(|task| task.target_node_idx == removed_idx as i32)
+                .map(|task| task.wave_id),
+        );
+        self.spawn_queue
+            .retain(|task| task.target_node_idx != removed_idx as i32);
+
+        self.nodes.remove(removed_idx);
+        self.links.retain(|l| l.from_id != id && l.to_id != id);
+
+        // 後ろのノードのインデックスが1つずれる（末尾のノードなら何もずれない）
+        if removed_idx < self.nodes.len() {
+            self.packets.remove_node(removed_idx);
+        }
+        let shift = |idx: i32| if idx > removed_idx as i32 { idx - 1 } else { idx };
+        for task in self.spawn_queue.iter_mut() {
+            task.target_node_idx = shift(task.target_node_idx);
+        }
+
+        log(&format!("[Rust/Wasm] Node removed: id={}", id));
+        true
+    }
+
+    /// すべてのノードをクリア（リンクも合わせてクリア）
     pub fn clear_nodes(&mut self) {
         self.nodes.clear();
+        self.links.clear();
+        self.autoscaling_groups.clear();
         log("[Rust/Wasm] All nodes cleared");
     }
 
+    /// LBノードの振り分け戦略を設定（strategy: LbStrategy as u32）
+    pub fn set_lb_strategy(&mut self, id: u32, strategy: u32) -> bool {
+        let Some(strategy) = LbStrategy::from_u32(strategy) else {
+            log(&format!("[Rust/Wasm] Warning: Unknown LB strategy {}", strategy));
+            return false;
+        };
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == id) {
+            node.lb_strategy = strategy;
+            node.lb_state = LbState::default();
+            log(&format!(
+                "[Rust/Wasm] LB strategy set: id={}, strategy={:?}",
+                id, strategy
+            ));
+            true
+        } else {
+            log(&format!(
+                "[Rust/Wasm] Warning: Node with id={} not found for LB strategy",
+                id
+            ));
+            false
+        }
+    }
+
+    /// ノードを停止させる（障害訓練用）
+    /// 処理中・待機中のパケットはノードのCrashPolicyに従ってドロップ or 送り直し
+    pub fn crash_node(&mut self, id: u32) -> bool {
+        let Some(idx) = self.get_node_index(id) else {
+            log(&format!("[Rust/Wasm] Warning: Node with id={} not found for crash", id));
+            return false;
+        };
+        if !self.nodes[idx].is_available() {
+            return false;
+        }
+        self.crash_node_at(idx);
+        true
+    }
+
+    /// ノードを劣化させる（停止中・再起動中のノードには無効）
+    pub fn degrade_node(&mut self, id: u32) -> bool {
+        let Some(idx) = self.get_node_index(id) else {
+            log(&format!("[Rust/Wasm] Warning: Node with id={} not found for degrade", id));
+            return false;
+        };
+        if !self.nodes[idx].is_available() {
+            return false;
+        }
+        self.degrade_node_at(idx);
+        true
+    }
+
+    /// ノードの障害・復旧設定を変更（crash_policy: CrashPolicy as u32）
+    pub fn set_node_health_config(
+        &mut self,
+        id: u32,
+        degraded_time_ms: f64,
+        down_time_ms: f64,
+        restart_time_ms: f64,
+        crash_policy: u32,
+    ) -> bool {
+        let Some(crash_policy) = CrashPolicy::from_u32(crash_policy) else {
+            log(&format!("[Rust/Wasm] Warning: Unknown crash policy {}", crash_policy));
+            return false;
+        };
+        self.apply_health_config(
+            id,
+            HealthConfig {
+                degraded_time_ms,
+                down_time_ms,
+                restart_time_ms,
+                crash_policy,
+            },
+        )
+    }
+
+    /// Firewallノードの検知設定を変更
+    pub fn set_firewall_config(
+        &mut self,
+        id: u32,
+        detection_rate: f32,
+        false_positive_rate: f32,
+        throughput_per_sec: f64,
+    ) -> bool {
+        self.apply_firewall_config(
+            id,
+            FirewallConfig {
+                detection_rate,
+                false_positive_rate,
+                throughput_per_sec,
+            },
+        )
+    }
+
+    /// Cacheノードの設定を変更
+    pub fn set_cache_config(&mut self, id: u32, hit_ratio: f32, ttl_ms: f64) -> bool {
+        self.apply_cache_config(id, CacheConfig { hit_ratio, ttl_ms })
+    }
+
+    /// RateLimiterノードの設定を変更（reject_policy: 0 = Drop, 1 = TooManyRequests）
+    pub fn set_rate_limit_config(
+        &mut self,
+        id: u32,
+        rate_per_sec: f64,
+        burst: f64,
+        reject_policy: u32,
+        per_client: bool,
+    ) -> bool {
+        let Some(reject) = RejectPolicy::from_u32(reject_policy) else {
+            log(&format!("[Rust/Wasm] Warning: Unknown reject policy {}", reject_policy));
+            return false;
+        };
+        self.apply_rate_limit_config(
+            id,
+            RateLimitConfig {
+                rate_per_sec,
+                burst,
+                reject,
+                per_client,
+            },
+        )
+    }
+
+    /// ノードのキュー規律をJSONで変更（例: {"discipline":"codel","target_ms":100}）
+    pub fn set_queue_policy_json(&mut self, id: u32, json: &str) -> bool {
+        match serde_json::from_str::<QueuePolicy>(json) {
+            Ok(policy) => self.apply_queue_policy(id, policy),
+            Err(e) => {
+                log(&format!("[Rust/Wasm] Warning: Invalid queue policy: {}", e));
+                false
+            }
+        }
+    }
+
+    /// キューでのドロップ数（原因ごと）をJSONで取得
+    pub fn get_queue_drops_json(&self) -> String {
+        serde_json::to_string(&self.queue_drops()).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Cacheノードの中身を捨てる（ヒット率はウォームアップからやり直し）
+    pub fn flush_cache(&mut self, id: u32) -> bool {
+        match self.nodes.iter_mut().find(|n| n.id == id) {
+            Some(node) if node.is_cache() => {
+                node.cache_entries.clear();
+                log(&format!("[Rust/Wasm] Cache flushed: id={}", id));
+                true
+            }
+            _ => {
+                log(&format!("[Rust/Wasm] Warning: Cache node with id={} not found", id));
+                false
+            }
+        }
+    }
+
+    /// クライアントキーの種類数を設定（生成されるパケットに順番に割り当てる）
+    pub fn set_client_count(&mut self, count: u32) {
+        self.client_count = count.max(1);
+    }
+
+    /// クライアントのタイムアウト・リトライ方針を設定（timeout_ms = 0 で無効）
+    pub fn set_retry_policy(
+        &mut self,
+        timeout_ms: f64,
+        max_retries: u32,
+        backoff_base_ms: f64,
+        backoff_multiplier: f64,
+        backoff_max_ms: f64,
+        jitter: f64,
+    ) {
+        self.apply_retry_policy(RetryPolicy {
+            timeout_ms,
+            max_retries,
+            backoff_base_ms,
+            backoff_multiplier,
+            backoff_max_ms,
+            jitter,
+        });
+    }
+
+    /// Waveのパケットをすべて同じクライアントから送らせる（1クライアントからのスパイク用）
+    /// 生成が終わったWave・存在しないWaveの場合はfalse
+    pub fn set_wave_client(&mut self, wave_id: u32, client_id: u32) -> bool {
+        match self.spawn_queue.iter_mut().find(|t| t.wave_id == wave_id) {
+            Some(task) => {
+                task.client_id = Some(client_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// ノード間リンクを追加（from_id -> to_id、伝送特性なし）
+    /// どちらかのノードが存在しない、または既に同じリンクがある場合はfalse
+    pub fn add_link(&mut self, from_id: u32, to_id: u32) -> bool {
+        self.add_link_with_profile(from_id, to_id, LinkProfile::default())
+    }
+
+    /// リンクの伝送特性を設定（latency_ms: 伝搬遅延、bandwidth: パケットサイズ/秒、loss_rate: 損失率）
+    /// すべて0にすると従来どおりパケットの移動速度で移動する。リンク上を移動中のパケットはそのまま
+    pub fn set_link_profile(
+        &mut self,
+        from_id: u32,
+        to_id: u32,
+        latency_ms: f64,
+        bandwidth: f64,
+        loss_rate: f32,
+    ) -> bool {
+        match self
+            .links
+            .iter_mut()
+            .find(|l| l.from_id == from_id && l.to_id == to_id)
+        {
+            Some(link) => {
+                link.profile = LinkProfile {
+                    latency_ms: latency_ms.max(0.0),
+                    bandwidth: bandwidth.max(0.0),
+                    loss_rate: loss_rate.clamp(0.0, 1.0),
+                };
+                log(&format!(
+                    "[Rust/Wasm] Link profile set: {} -> {}, latency={}ms, bandwidth={}/s, loss={}",
+                    from_id, to_id, latency_ms, bandwidth, loss_rate
+                ));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// ノード間リンクを削除（存在しない場合はfalse）
+    pub fn remove_link(&mut self, from_id: u32, to_id: u32) -> bool {
+        let Some(link_idx) = self
+            .links
+            .iter()
+            .position(|l| l.from_id == from_id && l.to_id == to_id)
+        else {
+            return false;
+        };
+        self.drop_link_packets(link_idx);
+        self.links.remove(link_idx);
+        log(&format!("[Rust/Wasm] Link removed: {} -> {}", from_id, to_id));
+        true
+    }
+
+    /// すべてのリンクをクリア（従来のタイプ順ルーティングに戻る）
+    pub fn clear_links(&mut self) {
+        for link_idx in 0..self.links.len() {
+            self.drop_link_packets(link_idx);
+        }
+        self.links.clear();
+        log("[Rust/Wasm] All links cleared");
+    }
+
+    /// リンク数を取得
+    pub fn get_link_count(&self) -> usize {
+        self.links.len()
+    }
+
     /// ノード数を取得
     pub fn get_node_count(&self) -> usize {
         self.nodes.len()
@@ -333,6 +1082,9 @@ impl SimulationState {
 
     /// パケット生成予約を追加（座標指定モード）
     /// Goから送られてくる生成情報を受け取り、spawn_queueに追加する
+    /// base_speed / speed_variance の単位はピクセル/ミリ秒
+    /// 戻り値: レイテンシ集計用のWave ID
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_wave(
         &mut self,
         x: f32,
@@ -345,7 +1097,8 @@ impl SimulationState {
         speed_variance: f32,
         packet_type: u32,
         complexity: u8,
-    ) {
+    ) -> u32 {
+        let wave_id = self.latency.register_wave();
         let task = SpawnTask {
             x,
             y,
@@ -360,6 +1113,8 @@ impl SimulationState {
             packet_type,
             complexity,
             start_time: self.current_time,
+            wave_id,
+            client_id: None,
         };
 
         log(&format!(
@@ -367,11 +1122,21 @@ impl SimulationState {
             count, x, y, target_x, target_y, duration_ms, base_speed, speed_variance
         ));
 
+        self.events.push(
+            self.current_time,
+            SimEventKind::WaveStarted {
+                wave_id,
+                count: count as u32,
+            },
+        );
         self.spawn_queue.push(task);
+        wave_id
     }
 
     /// パケット生成予約を追加（ノード指定モード）
     /// パケットは指定されたノードに向かって移動する
+    /// 戻り値: レイテンシ集計用のWave ID
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_wave_to_node(
         &mut self,
         x: f32,
@@ -383,7 +1148,8 @@ impl SimulationState {
         speed_variance: f32,
         packet_type: u32,
         complexity: u8,
-    ) {
+    ) -> u32 {
+        let wave_id = self.latency.register_wave();
         let task = SpawnTask {
             x,
             y,
@@ -398,6 +1164,8 @@ impl SimulationState {
             packet_type,
             complexity,
             start_time: self.current_time,
+            wave_id,
+            client_id: None,
         };
 
         log(&format!(
@@ -405,29 +1173,37 @@ impl SimulationState {
             count, x, y, target_node_idx, duration_ms, base_speed, speed_variance
         ));
 
+        self.events.push(
+            self.current_time,
+            SimEventKind::WaveStarted {
+                wave_id,
+                count: count as u32,
+            },
+        );
         self.spawn_queue.push(task);
+        wave_id
     }
 
     /// テスト用の簡易スポーン関数
     /// 指定位置からランダムな方向にパケットを生成
     pub fn debug_spawn(&mut self, x: f32, y: f32, count: usize) {
         let mut spawned = 0;
-        for packet in self.packets.iter_mut() {
-            if packet.active == 0 {
-                packet.active = 1;
-                packet.x = x;
-                packet.y = y;
-                // ランダムな方向に散らばらせる
-                packet.velocity_x = (js_random() - 0.5) * 4.0;
-                packet.velocity_y = (js_random() - 0.5) * 4.0;
-                packet.packet_type = PacketType::Normal as u32;
-                packet.complexity = 10;
-
-                spawned += 1;
-                if spawned >= count {
-                    break;
-                }
-            }
+        while spawned < count {
+            let Some(packet_idx) = self.packets.alloc() else {
+                break;
+            };
+            let mut packet = self.packets.get(packet_idx);
+            packet.x = x;
+            packet.y = y;
+            // ランダムな方向に散らばらせる
+            packet.velocity_x = (self.rng.next_f32() - 0.5) * 0.24;
+            packet.velocity_y = (self.rng.next_f32() - 0.5) * 0.24;
+            packet.packet_type = PacketType::Normal as u32;
+            packet.complexity = 10;
+            packet.spawn_time_ms = self.current_time;
+            self.packets.set(packet_idx, &packet);
+
+            spawned += 1;
         }
         log(&format!(
             "[Rust/Wasm] debug_spawn: spawned {} packets at ({}, {})",
@@ -437,27 +1213,37 @@ impl SimulationState {
 
     /// 毎フレーム呼び出す更新関数
     /// delta_ms: 前フレームからの経過時間（ミリ秒）
+    /// 経過時間を貯めて FIXED_STEP_MS ごとに進め、端数は次のフレームへ持ち越す
+    /// 1回に進めるのは MAX_STEPS_PER_TICK ステップまで（有限でない経過時間は無視する）
+    /// 到達・処理完了・乱数はフレームの長さによらず同じ時刻に起きるので、60Hzでも144Hzでもヘッドレス実行と同じ結果になる
     pub fn tick(&mut self, delta_ms: f64) {
-        self.current_time += delta_ms;
+        self.tick_with(delta_ms, |_| {});
+    }
 
-        // 1. spawn_queueを処理: 予約に基づいてパケットを生成
-        self.process_spawn_queue();
+    /// アクティブなパケット数を返す
+    pub fn get_active_count(&self) -> usize {
+        self.packets.active_count()
+    }
 
-        // 2. ノードでの処理時間を進める
-        self.process_nodes(delta_ms);
+    /// 生成予約・アクティブなパケット・応答待ちのリクエストが残っていないか（全処理の完了判定）
+    pub fn is_idle(&self) -> bool {
+        self.spawn_queue.is_empty() && self.get_active_count() == 0 && self.client.is_idle()
+    }
 
-        // 3. アクティブなパケットを更新
-        self.update_packets(delta_ms);
+    /// WebGPU描画用にパケット位置列のポインタを返す（[x, y] × get_packet_slot_count()）
+    pub fn get_packet_positions_ptr(&self) -> *const f32 {
+        self.packets.position.as_ptr() as *const f32
     }
 
-    /// アクティブなパケット数を返す
-    pub fn get_active_count(&self) -> usize {
-        self.packets.iter().filter(|p| p.active == 1).count()
+    /// WebGPU描画用にパケットの見た目列のポインタを返す（[r, g, b, size] × get_packet_slot_count()）
+    /// 非アクティブなスロットはサイズ0なので、先頭からまとめて転送してそのまま描画できる
+    pub fn get_packet_appearance_ptr(&self) -> *const f32 {
+        self.packets.appearance.as_ptr() as *const f32
     }
 
-    /// WebGPU描画用にパケットメモリのポインタを返す
-    pub fn get_packets_ptr(&self) -> *const Packet {
-        self.packets.as_ptr()
+    /// 描画で転送すべきスロット数（これより後ろのスロットは常に非アクティブ）
+    pub fn get_packet_slot_count(&self) -> usize {
+        self.packets.slot_count()
     }
 
     /// 最大パケット数を返す
@@ -485,31 +1271,314 @@ impl SimulationState {
         self.stats.packets_dropped
     }
 
+    /// 統計: 生成された攻撃パケット数（SynFlood / Killer）
+    pub fn get_stats_attacks_spawned(&self) -> u32 {
+        self.stats.attacks_spawned
+    }
+
+    /// 統計: 攻撃として作用し終えたパケット数（SYN Floodの占有終了・Killerの着弾）
+    pub fn get_stats_attacks_absorbed(&self) -> u32 {
+        self.stats.attacks_absorbed
+    }
+
+    /// オートスケーリングの累計コスト（全グループのインスタンス秒 × 単価）
+    pub fn get_autoscaling_cost(&self) -> f64 {
+        self.autoscaling_groups.iter().map(|g| g.cost()).sum()
+    }
+
+    /// オートスケーリンググループの状態をJSONで取得
+    pub fn get_autoscaling_status_json(&self) -> String {
+        serde_json::to_string(&self.autoscaling_status()).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// リンクの状態（移動中の数・利用率・スループット・損失）をJSONで取得
+    pub fn get_link_status_json(&self) -> String {
+        serde_json::to_string(&self.link_status()).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// 統計: Cacheでヒットしたリクエスト数
+    pub fn get_stats_cache_hits(&self) -> u32 {
+        self.stats.cache_hits
+    }
+
+    /// 統計: Cacheでミスした（DBへ進んだ）リクエスト数
+    pub fn get_stats_cache_misses(&self) -> u32 {
+        self.stats.cache_misses
+    }
+
+    /// 統計: クライアントがタイムアウトした回数（送り直しごとに数える）
+    pub fn get_stats_timed_out(&self) -> u32 {
+        self.stats.packets_timed_out
+    }
+
+    /// 統計: クライアントが送り直したリクエスト数（生成数には含まない）
+    pub fn get_stats_retried(&self) -> u32 {
+        self.stats.packets_retried
+    }
+
+    /// 統計: RateLimiterで拒否されたリクエスト数（ドロップ数には含まない）
+    pub fn get_stats_rate_limited(&self) -> u32 {
+        self.stats.packets_rate_limited
+    }
+
+    /// 統計: Firewallで遮断されたパケット数（誤検知を含む、ドロップ数には含まない）
+    pub fn get_stats_filtered(&self) -> u32 {
+        self.stats.packets_filtered
+    }
+
+    /// 統計: Firewallで遮断された攻撃パケット数
+    pub fn get_stats_attacks_filtered(&self) -> u32 {
+        self.stats.attacks_filtered
+    }
+
+    /// レイテンシ: 全体のパーセンタイル（ミリ秒、percentile: 0〜100）
+    pub fn get_latency_percentile(&self, percentile: f64) -> f64 {
+        self.latency.overall.percentile_ms(percentile)
+    }
+
+    /// レイテンシ: 全体のp50（ミリ秒）
+    pub fn get_latency_p50(&self) -> f64 {
+        self.latency.overall.percentile_ms(50.0)
+    }
+
+    /// レイテンシ: 全体のp90（ミリ秒）
+    pub fn get_latency_p90(&self) -> f64 {
+        self.latency.overall.percentile_ms(90.0)
+    }
+
+    /// レイテンシ: 全体のp99（ミリ秒）
+    pub fn get_latency_p99(&self) -> f64 {
+        self.latency.overall.percentile_ms(99.0)
+    }
+
+    /// レイテンシ: 全体の最大値（ミリ秒）
+    pub fn get_latency_max(&self) -> f64 {
+        self.latency.overall.max_ms()
+    }
+
+    /// レイテンシ: 記録済みのレスポンス数
+    pub fn get_latency_count(&self) -> u32 {
+        self.latency.overall.count() as u32
+    }
+
+    /// レイテンシ: 指定Waveのパーセンタイル（ミリ秒、Waveがなければ0）
+    pub fn get_wave_latency_percentile(&self, wave_id: u32, percentile: f64) -> f64 {
+        self.latency
+            .wave(wave_id)
+            .map_or(0.0, |h| h.percentile_ms(percentile))
+    }
+
+    /// レイテンシ: 指定Waveの最大値（ミリ秒、Waveがなければ0）
+    pub fn get_wave_latency_max(&self, wave_id: u32) -> f64 {
+        self.latency.wave(wave_id).map_or(0.0, |h| h.max_ms())
+    }
+
+    /// 登録済みのWave数（wave_idは 0..この値）
+    pub fn get_wave_count(&self) -> u32 {
+        self.latency.per_wave.len() as u32
+    }
+
+    /// レイテンシレポート（全体・Waveごと）をJSON文字列で取得
+    pub fn get_latency_report_json(&self) -> String {
+        serde_json::to_string(&self.latency.summary()).unwrap_or_default()
+    }
+
     /// 統計をリセット
     pub fn reset_stats(&mut self) {
         self.stats = SimulationStats::default();
+        self.latency.clear_records();
         log("[Rust/Wasm] Stats reset");
     }
 
-    /// シミュレーション全体をリセット（パケット、統計、時間）
+    /// シミュレーション全体をリセット（パケット、ノードの処理状態、統計、時間、乱数）
     pub fn reset(&mut self) {
-        // すべてのパケットを非アクティブに
-        for packet in self.packets.iter_mut() {
-            packet.active = 0;
+        // すべてのパケットを非アクティブにし、空きスロットを作り直す（GPUから届いたイベントも捨てる）
+        self.packets.clear();
+        self.motion_events.clear();
+        // ノードの処理中・キュー・振り分け状態をクリア
+        for node in self.nodes.iter_mut() {
+            node.processing_packets.clear();
+            node.queue.clear();
+            node.queue.drops = QueueDrops::default();
+            node.syn_backlog.clear();
+            node.health = NodeHealth::Healthy;
+            node.health_until_ms = 0.0;
+            node.firewall_bucket.reset(node.firewall.throughput_per_sec);
+            node.total_filtered = 0;
+            node.cache_entries.clear();
+            node.cache_hits = 0;
+            node.cache_misses = 0;
+            node.rate_bucket.reset(node.rate_limit.burst);
+            node.client_buckets.clear();
+            node.total_rate_limited = 0;
+            node.total_processed = 0;
+            node.total_dropped = 0;
+            node.lb_state = LbState::default();
+            node.metrics.clear();
+            node.saturated = false;
+        }
+        // リンク上のパケットと集計をクリア
+        for link in self.links.iter_mut() {
+            link.reset();
         }
         // スポーンキューをクリア
         self.spawn_queue.clear();
         // 時間をリセット
         self.current_time = 0.0;
-        // 統計をリセット
+        self.step_remainder_ms = 0.0;
+        self.metrics_window_start_ms = 0.0;
+        self.waves_draining.clear();
+        self.events.clear();
+        // オートスケーリンググループを最小台数に戻す
+        self.reset_autoscaling();
+        // 応答待ち・送り直し予約を捨てる
+        self.client.reset();
+        // 統計をリセット（Waveも発火し直すため、レイテンシはWaveごと破棄）
         self.stats = SimulationStats::default();
+        self.latency = LatencyMetrics::default();
+        // 乱数とクライアントキーを初期状態に戻す（同じシードで同じ結果を再現）
+        self.rng = SimRng::new(self.seed);
+        self.next_client_seq = 0;
         log("[Rust/Wasm] Simulation reset");
     }
 
 }
 
+// SimulationStateの内部実装（#[wasm_bindgen]なし）- シード指定の生成
+impl SimulationState {
+    /// シードを指定してSimulationStateを作成
+    /// 同じシード・同じ入力なら同じ結果になる
+    pub fn with_seed(max_packets: usize, seed: u64) -> SimulationState {
+        let packets = PacketStore::new(max_packets);
+        log(&format!(
+            "[Rust/Wasm] SimulationState created with {} packet slots (seed={})",
+            max_packets, seed
+        ));
+        SimulationState {
+            packets,
+            nodes: Vec::new(), // ノードリスト初期化
+            links: Vec::new(),
+            max_packets,
+            spawn_queue: Vec::new(),
+            current_time: 0.0,
+            step_remainder_ms: 0.0,
+            stats: SimulationStats::default(),
+            latency: LatencyMetrics::default(),
+            client_count: DEFAULT_CLIENT_COUNT,
+            next_client_seq: 0,
+            seed,
+            rng: SimRng::new(seed),
+            autoscaling_groups: Vec::new(),
+            client: ClientState::default(),
+            metrics_config: NodeMetricsConfig::default(),
+            metrics_window_start_ms: 0.0,
+            waves_draining: Vec::new(),
+            events: EventLog::default(),
+            gpu_motion: false,
+            motion_events: Vec::new(),
+        }
+    }
+
+    /// 乱数シードを取得
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// レイテンシレポート（全体・Waveごと）
+    pub fn latency_summary(&self) -> LatencySummary {
+        self.latency.summary()
+    }
+
+    /// レイテンシの集計（ヒストグラム）を参照
+    pub fn latency(&self) -> &LatencyMetrics {
+        &self.latency
+    }
+
+    /// 移動をGPUに任せるかを切り替える（描画側で GpuMotion を用意してから有効にする）
+    /// 有効にすると使用中のスロットをすべてGPUへ送り直す
+    /// 無効に戻すと、移動中のパケットは最後にGPUへ送った位置（直前のノード）から移動をやり直す
+    pub fn set_gpu_motion(&mut self, enabled: bool) {
+        self.gpu_motion = enabled;
+        self.motion_events.clear();
+        self.packets.set_motion_tracking(enabled);
+        log(&format!("[Rust/Wasm] GPU motion {}", if enabled { "enabled" } else { "disabled" }));
+    }
+
+    pub fn gpu_motion(&self) -> bool {
+        self.gpu_motion
+    }
+
+    /// GPUから読み戻した到達・消滅を渡す（次のtickのパケット更新で反映）
+    pub fn push_motion_events(&mut self, events: &[MotionEvent]) {
+        self.motion_events.extend_from_slice(events);
+    }
+
+    /// GPUへ送っていない移動状態の変更を取り出す（スロット順）
+    pub fn take_motion_updates(&mut self, out: &mut Vec<MotionUpdate>) {
+        self.packets.take_motion_updates(out);
+    }
+
+    /// ノード位置の一覧（GPUで移動先を引くため、ノードインデックス順）
+    pub fn node_positions_into(&self, out: &mut Vec<[f32; 2]>) {
+        out.clear();
+        out.extend(self.nodes.iter().map(|node| [node.x, node.y]));
+    }
+}
+
 // SimulationStateの内部実装（#[wasm_bindgen]なし）- ノード位置取得
 impl SimulationState {
+    /// 伝送特性を指定してノード間リンクを追加（from_id -> to_id）
+    /// どちらかのノードが存在しない、または既に同じリンクがある場合はfalse
+    pub fn add_link_with_profile(&mut self, from_id: u32, to_id: u32, profile: LinkProfile) -> bool {
+        let exists = |id: u32| self.nodes.iter().any(|n| n.id == id);
+        if !exists(from_id) || !exists(to_id) {
+            log(&format!(
+                "[Rust/Wasm] Warning: Link {} -> {} refers to an unknown node",
+                from_id, to_id
+            ));
+            return false;
+        }
+        if self.links.iter().any(|l| l.from_id == from_id && l.to_id == to_id) {
+            return false;
+        }
+        self.links.push(Link::new(from_id, to_id, profile));
+        log(&format!("[Rust/Wasm] Link added: {} -> {}", from_id, to_id));
+        true
+    }
+
+    /// リンクの一覧
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// リンクの状態一覧
+    pub fn link_status(&self) -> Vec<LinkStatus> {
+        self.links.iter().map(|l| l.status()).collect()
+    }
+
+    /// ノードの時系列メトリクスの記録間隔・サンプル数を変更（記録済みのサンプルは消える）
+    pub fn set_metrics_config(&mut self, interval_ms: f64, capacity: usize) {
+        self.metrics_config = NodeMetricsConfig {
+            interval_ms: interval_ms.max(1.0),
+            capacity,
+        };
+        self.metrics_window_start_ms = self.current_time;
+        for node in self.nodes.iter_mut() {
+            node.metrics.clear();
+            node.metrics.start_window(node.total_processed, node.total_dropped);
+        }
+    }
+
+    /// ノードの時系列メトリクスの記録間隔・サンプル数
+    pub fn metrics_config(&self) -> NodeMetricsConfig {
+        self.metrics_config
+    }
+
+    /// インデックスでノードの時系列メトリクスを取得
+    pub fn node_metrics(&self, index: usize) -> Option<&NodeMetrics> {
+        self.nodes.get(index).map(|n| &n.metrics)
+    }
+
     /// 指定IDのノード位置を取得（見つからない場合はNone）
     pub fn get_node_position(&self, id: u32) -> Option<(f32, f32)> {
         self.nodes.iter().find(|n| n.id == id).map(|n| (n.x, n.y))
@@ -524,10 +1593,749 @@ impl SimulationState {
     pub fn get_node_type_by_index(&self, index: usize) -> Option<u32> {
         self.nodes.get(index).map(|n| n.node_type)
     }
+
+    /// ノードIDからインデックスを取得
+    pub fn get_node_index(&self, id: u32) -> Option<usize> {
+        self.nodes.iter().position(|n| n.id == id)
+    }
+
+    /// 指定IDのノードの稼働状態を取得
+    pub fn get_node_health(&self, id: u32) -> Option<NodeHealth> {
+        self.nodes.iter().find(|n| n.id == id).map(|n| n.health)
+    }
+
+    /// ノードのキュー規律を変更（待機中のパケットはそのまま）
+    pub fn apply_queue_policy(&mut self, id: u32, policy: QueuePolicy) -> bool {
+        match self.nodes.iter_mut().find(|n| n.id == id) {
+            Some(node) => {
+                node.queue.set_policy(policy);
+                log(&format!("[Rust/Wasm] Queue policy set: id={}, policy={:?}", id, policy));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// グループのキュー規律を変更（既存と今後のインスタンスに反映）
+    pub fn apply_group_queue_policy(&mut self, group_id: u32, policy: QueuePolicy) -> bool {
+        let Some(group) = self.autoscaling_groups.get_mut(group_id as usize) else {
+            return false;
+        };
+        group.queue_policy = policy;
+        let members: Vec<u32> = group.instances.iter().chain(group.draining.iter()).copied().collect();
+        for member in members {
+            self.apply_queue_policy(member, policy);
+        }
+        true
+    }
+
+    /// ノードのキューでのドロップ数（原因ごと）
+    pub fn get_node_queue_drops(&self, id: u32) -> Option<QueueDrops> {
+        self.nodes.iter().find(|n| n.id == id).map(|n| n.queue.drops)
+    }
+
+    /// 全ノードのキューでのドロップ数（原因ごと、削除されたノードの分を含む）
+    pub fn queue_drops(&self) -> QueueDrops {
+        QueueDrops {
+            tail: self.stats.queue_tail_drops,
+            priority: self.stats.queue_priority_drops,
+            red: self.stats.queue_red_drops,
+            codel: self.stats.queue_codel_drops,
+        }
+    }
+
+    /// ノードの障害・復旧設定を変更
+    pub fn apply_health_config(&mut self, id: u32, config: HealthConfig) -> bool {
+        match self.nodes.iter_mut().find(|n| n.id == id) {
+            Some(node) => {
+                node.health_config = config;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Firewallノードの検知設定を変更（Firewall以外・見つからない場合はfalse）
+    pub fn apply_firewall_config(&mut self, id: u32, config: FirewallConfig) -> bool {
+        match self.nodes.iter_mut().find(|n| n.id == id) {
+            Some(node) if node.is_firewall() => {
+                node.firewall = config;
+                node.firewall_bucket.reset(config.throughput_per_sec);
+                log(&format!(
+                    "[Rust/Wasm] Firewall config set: id={}, detection={}, false_positive={}, throughput={}/s",
+                    id, config.detection_rate, config.false_positive_rate, config.throughput_per_sec
+                ));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// クライアントのタイムアウト・リトライ方針を設定（送信済みのリクエストには影響しない）
+    pub fn apply_retry_policy(&mut self, policy: RetryPolicy) {
+        self.client.policy = policy;
+        log(&format!(
+            "[Rust/Wasm] Retry policy set: timeout={}ms, max_retries={}, backoff={}ms x{} (max {}ms), jitter={}",
+            policy.timeout_ms,
+            policy.max_retries,
+            policy.backoff_base_ms,
+            policy.backoff_multiplier,
+            policy.backoff_max_ms,
+            policy.jitter
+        ));
+    }
+
+    /// クライアントのタイムアウト・リトライ方針
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.client.policy
+    }
+
+    /// RateLimiterノードの設定を変更（RateLimiter以外・見つからない場合はfalse）
+    pub fn apply_rate_limit_config(&mut self, id: u32, config: RateLimitConfig) -> bool {
+        match self.nodes.iter_mut().find(|n| n.id == id) {
+            Some(node) if node.is_rate_limiter() => {
+                node.rate_limit = config;
+                node.rate_bucket.reset(config.burst);
+                node.client_buckets.clear();
+                log(&format!(
+                    "[Rust/Wasm] Rate limit config set: id={}, rate={}/s, burst={}, reject={:?}, per_client={}",
+                    id, config.rate_per_sec, config.burst, config.reject, config.per_client
+                ));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Cacheノードの設定を変更（Cache以外・見つからない場合はfalse）
+    pub fn apply_cache_config(&mut self, id: u32, config: CacheConfig) -> bool {
+        match self.nodes.iter_mut().find(|n| n.id == id) {
+            Some(node) if node.is_cache() => {
+                node.cache = config;
+                log(&format!(
+                    "[Rust/Wasm] Cache config set: id={}, hit_ratio={}, ttl={}ms",
+                    id, config.hit_ratio, config.ttl_ms
+                ));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 未使用のノードID（既存の最大ID + 1）
+    pub fn next_free_node_id(&self) -> u32 {
+        self.nodes.iter().map(|n| n.id + 1).max().unwrap_or(0)
+    }
+
+    /// オートスケーリンググループを追加し、グループIDを返す
+    /// 最小台数のインスタンスは起動待ちなしで配置する
+    pub fn add_autoscaling_group(
+        &mut self,
+        node_type: u32,
+        spec: NodeSpec,
+        health_config: HealthConfig,
+        policy: ScalingPolicy,
+        x: f32,
+        y: f32,
+    ) -> u32 {
+        let id = self.autoscaling_groups.len() as u32;
+        let mut group = AutoscalingGroup::new(id, node_type, spec, health_config, policy, x, y);
+        for _ in 0..policy.min_instances {
+            self.spawn_instance(&mut group, false);
+        }
+        log(&format!(
+            "[Rust/Wasm] Autoscaling group added: id={}, type={}, instances={}..{}, cost={}/s",
+            id, node_type, policy.min_instances, policy.max_instances, policy.cost_per_sec
+        ));
+        self.autoscaling_groups.push(group);
+        id
+    }
+
+    /// グループとノードをリンク（upstream: true なら node -> グループ、false なら グループ -> node）
+    /// 既存のインスタンスにもリンクを張り、以後追加されるインスタンスにも同じリンクを張る
+    pub fn add_group_link(
+        &mut self,
+        group_id: u32,
+        node_id: u32,
+        upstream: bool,
+        profile: LinkProfile,
+    ) -> bool {
+        let Some(group) = self.autoscaling_groups.get_mut(group_id as usize) else {
+            return false;
+        };
+        if upstream {
+            group.upstream.push((node_id, profile));
+        } else {
+            group.downstream.push((node_id, profile));
+        }
+        let members: Vec<u32> = group.instances.iter().chain(group.draining.iter()).copied().collect();
+        for member in members {
+            if upstream {
+                self.add_link_with_profile(node_id, member, profile);
+            } else {
+                self.add_link_with_profile(member, node_id, profile);
+            }
+        }
+        true
+    }
+
+    /// オートスケーリンググループの一覧
+    pub fn autoscaling_groups(&self) -> &[AutoscalingGroup] {
+        &self.autoscaling_groups
+    }
+
+    /// オートスケーリンググループの状態一覧
+    pub fn autoscaling_status(&self) -> Vec<AutoscalingStatus> {
+        self.autoscaling_groups.iter().map(|g| g.status()).collect()
+    }
+
+    /// インデックスでノードスペックを取得
+    pub fn get_node_spec_by_index(&self, index: usize) -> Option<NodeSpec> {
+        self.nodes.get(index).map(|n| n.spec)
+    }
 }
 
 // SimulationStateの内部実装（#[wasm_bindgen]なし）
 impl SimulationState {
+    /// 固定ステップごとに before_step を呼んでから進める（ステージのWave発火をステップ単位で行う用）
+    pub fn tick_with(&mut self, delta_ms: f64, mut before_step: impl FnMut(&mut SimulationState)) {
+        if delta_ms.is_finite() {
+            self.step_remainder_ms += delta_ms.max(0.0);
+        }
+        self.step_remainder_ms = self
+            .step_remainder_ms
+            .min(MAX_STEPS_PER_TICK as f64 * FIXED_STEP_MS);
+        while self.step_remainder_ms >= FIXED_STEP_MS {
+            self.step_remainder_ms -= FIXED_STEP_MS;
+            before_step(self);
+            self.step(FIXED_STEP_MS);
+        }
+    }
+
+    /// 復元した状態の参照が壊れていないか確かめる（スナップショットの読み込み用）
+    /// ノード・リンクが持つパケットは使用中のスロット、パケットが指すノードは存在するものでなければならない
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let node_count = self.nodes.len();
+        let node_ref_ok = |idx: i32| idx == -1 || (idx >= 0 && (idx as usize) < node_count);
+
+        for idx in (0..self.packets.slot_count()).filter(|&idx| self.packets.is_active(idx)) {
+            if !node_ref_ok(self.packets.target_node_idx[idx])
+                || !node_ref_ok(self.packets.current_node_idx[idx])
+                || !node_ref_ok(self.packets.origin_server_idx[idx])
+                || self.packets.route_of(idx).iter().any(|&n| n as usize >= node_count)
+            {
+                return Err(format!("packet {} refers to a missing node", idx));
+            }
+        }
+
+        for node in &self.nodes {
+            let held = node
+                .processing_packets
+                .iter()
+                .map(|proc| proc.packet_idx)
+                .chain(node.queue.iter().map(|queued| queued.packet_idx))
+                .chain(node.syn_backlog.iter().map(|held| held.packet_idx));
+            for idx in held {
+                if !self.packets.is_active(idx) {
+                    return Err(format!("node {} holds unused packet slot {}", node.id, idx));
+                }
+            }
+        }
+
+        for link in &self.links {
+            if let Some(transit) = link
+                .in_flight
+                .iter()
+                .find(|transit| !self.packets.is_active(transit.packet_idx))
+            {
+                return Err(format!(
+                    "link {} -> {} carries unused packet slot {}",
+                    link.from_id, link.to_id, transit.packet_idx
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 1ステップ進める
+    fn step(&mut self, delta_ms: f64) {
+        self.current_time += delta_ms;
+
+        // 1. spawn_queueを処理: 予約に基づいてパケットを生成
+        self.process_spawn_queue();
+
+        // 2. クライアントのタイムアウト判定と送り直し
+        self.update_clients();
+
+        // 3. ノードの稼働状態を進める（劣化・停止からの復旧、インスタンスの起動完了）
+        self.update_node_health();
+
+        // 4. オートスケーリング（課金・負荷の計測・インスタンスの増減）
+        self.update_autoscaling(delta_ms);
+
+        // 5. ノードでの処理時間を進める
+        self.process_nodes(delta_ms);
+
+        // 6. リンク上のパケットを進める（帯域・伝搬遅延・損失）
+        self.update_links(delta_ms);
+
+        // 7. アクティブなパケットを更新
+        self.update_packets(delta_ms);
+
+        // 8. ノードの時系列メトリクスを集計・記録
+        self.sample_node_metrics(delta_ms);
+
+        // 9. ノードの飽和・回復とWaveの終了をイベントにする
+        self.update_saturation();
+        self.update_finished_waves();
+    }
+
+    /// パケットをドロップ（ノードで処理中・待機中、リンク上ならそこからも取り除く）
+    /// node_idx: ドロップしたノード（ノードに関係しない原因ならNone）
+    fn drop_packet(&mut self, packet_idx: usize, node_idx: Option<usize>, reason: DropReason) {
+        if !self.packets.is_active(packet_idx) {
+            return;
+        }
+        let current_node_idx = self.packets.current_node_idx[packet_idx];
+        if current_node_idx >= 0 {
+            if let Some(node) = self.nodes.get_mut(current_node_idx as usize) {
+                node.processing_packets.retain(|proc| proc.packet_idx != packet_idx);
+                node.queue.retain(|q| q.packet_idx != packet_idx);
+                node.syn_backlog.retain(|held| held.packet_idx != packet_idx);
+            }
+        }
+        if self.packets.state[packet_idx] == PacketState::OnLink {
+            for link in self.links.iter_mut() {
+                link.remove_packet(packet_idx);
+            }
+        }
+        self.discard_packet(packet_idx, node_idx, reason);
+    }
+
+    /// ノード・リンクがもう持っていないパケットをドロップとして数え、スロットを空ける
+    /// イベント、ノードのドロップ数（node_idxがあれば）、全体のドロップ数はここでまとめて更新する
+    fn discard_packet(&mut self, packet_idx: usize, node_idx: Option<usize>, reason: DropReason) {
+        if !self.packets.is_active(packet_idx) {
+            return;
+        }
+        self.record_drop(packet_idx, node_idx, reason);
+        self.packets.release(packet_idx);
+        if let Some(node) = node_idx.and_then(|idx| self.nodes.get_mut(idx)) {
+            node.total_dropped += 1;
+        }
+        self.stats.packets_dropped += 1;
+    }
+
+    /// ドロップをイベントとして記録
+    fn record_drop(&mut self, packet_idx: usize, node_idx: Option<usize>, reason: DropReason) {
+        let node_id = node_idx.and_then(|idx| self.nodes.get(idx)).map(|node| node.id);
+        self.events.push(
+            self.current_time,
+            SimEventKind::PacketDropped {
+                node_id,
+                reason,
+                packet_type: self.packets.packet_type[packet_idx],
+            },
+        );
+    }
+
+    /// 記録したイベントを古い順にすべて取り出す
+    pub fn drain_events(&mut self) -> Vec<SimEvent> {
+        self.events.drain()
+    }
+
+    /// イベントを記録（ステージ側の出来事用）
+    pub fn push_event(&mut self, kind: SimEventKind) {
+        self.events.push(self.current_time, kind);
+    }
+
+    /// 経過時間によるノードの状態遷移（Degraded -> Healthy、Down -> Restarting -> Healthy）
+    fn update_node_health(&mut self) {
+        let now = self.current_time;
+        for node in self.nodes.iter_mut() {
+            if node.advance_health(now) {
+                log(&format!(
+                    "[Rust/Wasm] Node {} is now {:?}",
+                    node.id, node.health
+                ));
+            }
+        }
+    }
+
+    /// ノードを劣化させる
+    fn degrade_node_at(&mut self, node_idx: usize) {
+        let now = self.current_time;
+        let node = &mut self.nodes[node_idx];
+        node.set_health(NodeHealth::Degraded, now);
+        log(&format!(
+            "[Rust/Wasm] Node {} degraded until t={}ms",
+            node.id, node.health_until_ms
+        ));
+    }
+
+    /// ノードを停止させ、処理中・待機中のパケットをポリシーに従って退避させる
+    fn crash_node_at(&mut self, node_idx: usize) {
+        let now = self.current_time;
+        let node = &mut self.nodes[node_idx];
+        node.set_health(NodeHealth::Down, now);
+        node.cache_entries.clear(); // 停止でキャッシュの中身は失われる
+        log(&format!(
+            "[Rust/Wasm] Node {} crashed, restarting at t={}ms (policy={:?})",
+            node.id, node.health_until_ms, node.health_config.crash_policy
+        ));
+
+        let held: Vec<usize> = node.syn_backlog.drain(..).map(|h| h.packet_idx).collect();
+        let evacuated: Vec<usize> = node
+            .processing_packets
+            .drain(..)
+            .map(|proc| proc.packet_idx)
+            .chain(node.queue.drain().map(|q| q.packet_idx))
+            .collect();
+        node.queue.clear();
+
+        // SYN Floodは接続ごと消える
+        for packet_idx in held {
+            self.discard_packet(packet_idx, Some(node_idx), DropReason::NodeDown);
+        }
+        for packet_idx in evacuated {
+            self.evacuate_packet(packet_idx, node_idx);
+        }
+    }
+
+    /// 停止中のノードにいる（向かっていた）パケットを退避させる
+    /// CrashPolicy::Requeueならリクエストを直前のノードから別の候補へ送り直し、それ以外はドロップ
+    fn evacuate_packet(&mut self, packet_idx: usize, node_idx: usize) {
+        let requeue = self.nodes[node_idx].health_config.crash_policy == CrashPolicy::Requeue;
+        self.packets.set_current_node(packet_idx, -1);
+        if requeue && !self.packets.is_response[packet_idx] {
+            if self.packets.last_route(packet_idx) == Some(node_idx) {
+                self.packets.pop_route(packet_idx);
+            }
+            if let Some(prev_idx) = self.packets.last_route(packet_idx) {
+                if prev_idx < self.nodes.len() && self.nodes[prev_idx].is_available() {
+                    self.route_packet_to_next(packet_idx, prev_idx, true);
+                    return;
+                }
+            }
+        }
+        self.discard_packet(packet_idx, Some(node_idx), DropReason::NodeDown);
+    }
+
+    /// Firewallでリクエストを検査（通過させるならtrue）
+    /// 検査スループットを超えた分は容量ドロップ、検知・誤検知したものは遮断として別に数える
+    fn inspect_at_firewall(&mut self, packet_idx: usize, node_idx: usize) -> bool {
+        let now = self.current_time;
+        let node = &mut self.nodes[node_idx];
+        let config = node.firewall;
+        if !node
+            .firewall_bucket
+            .try_take(now, config.throughput_per_sec, config.throughput_per_sec)
+        {
+            self.discard_packet(packet_idx, Some(node_idx), DropReason::FirewallOverload);
+            return false;
+        }
+
+        let is_attack = PacketType::is_attack(self.packets.packet_type[packet_idx]);
+        let block_rate = if is_attack {
+            config.detection_rate
+        } else {
+            config.false_positive_rate
+        };
+        if self.rng.next_f32() >= block_rate {
+            return true;
+        }
+
+        // droppedには数えないが、遮断したことはイベントに残す（packet_typeで攻撃か誤検知かわかる）
+        self.record_drop(packet_idx, Some(node_idx), DropReason::Filtered);
+        self.packets.release(packet_idx);
+        self.nodes[node_idx].total_filtered += 1;
+        self.stats.packets_filtered += 1;
+        if is_attack {
+            self.stats.attacks_filtered += 1;
+        }
+        false
+    }
+
+    /// 期限が切れたリクエストをタイムアウトとし、送り直しの予約と実行を行う
+    /// タイムアウトしたパケットは系内に残り（ノードは客が諦めたことを知らない）、処理能力を使い続ける
+    fn update_clients(&mut self) {
+        if self.client.is_idle() {
+            return;
+        }
+        let now = self.current_time;
+
+        for (request_id, req) in self.client.take_expired(now) {
+            self.stats.packets_timed_out += 1;
+            let idx = req.packet_idx;
+            if self.packets.is_active(idx) && self.packets.request_id[idx] == request_id {
+                self.packets.abandoned[idx] = true;
+            }
+            let random = self.rng.next_f32() as f64;
+            self.client.schedule_retry(now, req.retry + 1, req.origin, random);
+        }
+
+        for retry in self.client.take_due_retries(now) {
+            self.spawn_retry(retry);
+        }
+    }
+
+    /// 送り直しのリクエストを1つ生成（空きスロットがなければ送れずにドロップ）
+    fn spawn_retry(&mut self, retry: PendingRetry) {
+        let now = self.current_time;
+        let origin = retry.origin;
+        let Some(packet_idx) = self.packets.alloc() else {
+            self.stats.packets_dropped += 1;
+            self.events.push(
+                now,
+                SimEventKind::PacketDropped {
+                    node_id: None,
+                    reason: DropReason::NoPacketSlot,
+                    packet_type: origin.packet_type,
+                },
+            );
+            return;
+        };
+        let (request_id, deadline_ms) = self.client.register(packet_idx, now, retry.retry, origin);
+
+        let mut packet = self.packets.get(packet_idx);
+        packet.x = origin.x;
+        packet.y = origin.y;
+        packet.speed = origin.speed;
+        packet.target_node_idx = origin.target_node_idx;
+        packet.packet_type = origin.packet_type;
+        packet.complexity = origin.complexity;
+        packet.wave_id = origin.wave_id;
+        packet.client_id = origin.client_id;
+        packet.spawn_time_ms = origin.spawn_time_ms; // レイテンシは最初の送信から計る
+        packet.request_id = request_id;
+        packet.deadline_ms = deadline_ms;
+        packet.retry = retry.retry;
+        self.packets.set(packet_idx, &packet);
+        self.stats.packets_retried += 1;
+    }
+
+    /// レスポンスが入口に戻った（クライアントに届いた）ときの処理
+    /// 諦めたリクエストは数えず、429応答は送り直せるなら予約する
+    fn complete_request(&mut self, packet_idx: usize) {
+        let now = self.current_time;
+        let p = self.packets.get(packet_idx);
+        self.packets.release(packet_idx);
+        if p.abandoned {
+            return;
+        }
+        let outstanding = self.client.complete(p.request_id);
+        if p.rejected {
+            // 429応答は拒否として数え済み
+            if let Some(req) = outstanding {
+                let random = self.rng.next_f32() as f64;
+                self.client.schedule_retry(now, req.retry + 1, req.origin, random);
+            }
+            return;
+        }
+        self.stats.packets_processed += 1;
+        self.latency.record(p.wave_id, now - p.spawn_time_ms);
+    }
+
+    /// RateLimiterでリクエストのトークンを取る（通過させるならtrue）
+    /// 取れなかったものはDropならその場で消え、TooManyRequestsなら429応答として直前のノードへ返す
+    fn admit_at_rate_limiter(&mut self, packet_idx: usize, node_idx: usize) -> bool {
+        let now = self.current_time;
+        let client_id = self.packets.client_id[packet_idx];
+        let node = &mut self.nodes[node_idx];
+        let config = node.rate_limit;
+        let bucket = if config.per_client {
+            node.client_buckets
+                .entry(client_id)
+                .or_insert_with(|| TokenBucket::full(config.burst))
+        } else {
+            &mut node.rate_bucket
+        };
+        if bucket.try_take(now, config.rate_per_sec, config.burst) {
+            return true;
+        }
+
+        node.total_rate_limited += 1;
+        self.stats.packets_rate_limited += 1;
+        match config.reject {
+            RejectPolicy::Drop => {
+                // droppedには数えないが、原因付きのイベントは残す
+                self.record_drop(packet_idx, Some(node_idx), DropReason::RateLimited);
+                self.packets.release(packet_idx);
+            }
+            RejectPolicy::TooManyRequests => {
+                // 自ノードは通過履歴に積まれていないので、そのまま直前のノードへ戻る
+                self.packets.make_response(packet_idx, REJECT_RESPONSE_SIZE);
+                self.packets.rejected[packet_idx] = true;
+                self.route_packet_to_next(packet_idx, node_idx, false);
+            }
+        }
+        false
+    }
+
+    /// ノードを参照しているパケットがあるか（処理中・待機中・移動先・戻り経路）
+    fn node_in_use(&self, node_idx: usize) -> bool {
+        let node = &self.nodes[node_idx];
+        !node.processing_packets.is_empty()
+            || !node.queue.is_empty()
+            || !node.syn_backlog.is_empty()
+            || self.packets.node_refs(node_idx) > 0
+    }
+
+    /// グループにインスタンスを1台追加（booting: 起動待ちにするか）
+    fn spawn_instance(&mut self, group: &mut AutoscalingGroup, booting: bool) -> u32 {
+        let id = self.next_free_node_id();
+
+        // 他のメンバーと重ならない位置に置く
+        let members: Vec<(f32, f32)> = group
+            .instances
+            .iter()
+            .chain(group.draining.iter())
+            .filter_map(|&member| self.get_node_position(member))
+            .collect();
+        let (x, y) = (0..)
+            .map(|slot| group.slot_position(slot))
+            .find(|pos| !members.contains(pos))
+            .unwrap_or((group.x, group.y));
+
+        let mut node = Node::new(id, x, y, group.node_type);
+        node.spec = group.spec;
+        node.health_config = group.health_config;
+        node.queue.set_policy(group.queue_policy);
+        if booting && group.policy.boot_delay_ms > 0.0 {
+            // 起動が終わるまでは再起動中と同じく振り分け対象外
+            node.health = NodeHealth::Restarting;
+            node.health_until_ms = self.current_time + group.policy.boot_delay_ms;
+        }
+        self.nodes.push(node);
+        for &(from_id, profile) in &group.upstream {
+            self.add_link_with_profile(from_id, id, profile);
+        }
+        for &(to_id, profile) in &group.downstream {
+            self.add_link_with_profile(id, to_id, profile);
+        }
+        group.instances.push(id);
+        id
+    }
+
+    /// オートスケーリング: 課金、削除待ちインスタンスの片付け、負荷の計測と増減
+    fn update_autoscaling(&mut self, delta_ms: f64) {
+        if self.autoscaling_groups.is_empty() {
+            return;
+        }
+        let now = self.current_time;
+        let mut groups = std::mem::take(&mut self.autoscaling_groups);
+        for group in groups.iter_mut() {
+            group.accrue(delta_ms);
+
+            // 外部から削除されたインスタンスは忘れる
+            group.instances.retain(|&id| self.get_node_index(id).is_some());
+            group.draining.retain(|&id| self.get_node_index(id).is_some());
+
+            // 処理の終わった削除待ちインスタンスを消す
+            let drained: Vec<u32> = group
+                .draining
+                .iter()
+                .copied()
+                .filter(|&id| self.get_node_index(id).is_some_and(|idx| !self.node_in_use(idx)))
+                .collect();
+            for id in drained {
+                self.remove_node(id);
+                group.draining.retain(|&member| member != id);
+            }
+
+            // 起動済みのインスタンスの平均負荷率を記録
+            let loads: Vec<f32> = group
+                .instances
+                .iter()
+                .filter_map(|&id| self.get_node_index(id))
+                .map(|idx| &self.nodes[idx])
+                .filter(|node| node.is_available())
+                .map(|node| node.load_rate())
+                .collect();
+            if !loads.is_empty() {
+                group.record_load(now, loads.iter().sum::<f32>() / loads.len() as f32);
+            }
+
+            match group.decide(now) {
+                ScaleAction::Out => {
+                    let id = self.spawn_instance(group, true);
+                    group.mark_scaled(now);
+                    log(&format!(
+                        "[Rust/Wasm] Autoscaling group {} scaled out: node {} booting ({} instances)",
+                        group.id,
+                        id,
+                        group.instances.len()
+                    ));
+                }
+                ScaleAction::In => {
+                    // 最後に追加したインスタンスから削除（処理中のパケットは終わるまで待つ）
+                    if let Some(id) = group.instances.pop() {
+                        if let Some(idx) = self.get_node_index(id) {
+                            self.nodes[idx].draining = true;
+                        }
+                        group.draining.push(id);
+                        log(&format!(
+                            "[Rust/Wasm] Autoscaling group {} scaled in: node {} draining ({} instances)",
+                            group.id,
+                            id,
+                            group.instances.len()
+                        ));
+                    }
+                    group.mark_scaled(now);
+                }
+                ScaleAction::None => {}
+            }
+        }
+        self.autoscaling_groups = groups;
+    }
+
+    /// オートスケーリンググループを最小台数・課金なしの状態に戻す
+    fn reset_autoscaling(&mut self) {
+        let mut groups = std::mem::take(&mut self.autoscaling_groups);
+        for group in groups.iter_mut() {
+            for id in std::mem::take(&mut group.draining) {
+                self.remove_node(id);
+            }
+            while group.instances.len() > group.policy.min_instances as usize {
+                if let Some(id) = group.instances.pop() {
+                    self.remove_node(id);
+                }
+            }
+            while group.instances.len() < group.policy.min_instances as usize {
+                self.spawn_instance(group, false);
+            }
+            group.reset();
+        }
+        self.autoscaling_groups = groups;
+    }
+
+    /// Cacheでリクエストを引く（ヒットならtrue）
+    /// キャッシュ可能な割合（hit_ratio）のうち、クライアントキーのエントリが期限内ならヒット
+    /// 空のキャッシュはミスのレスポンスが通過するたびに埋まっていく（ウォームアップ）
+    fn lookup_cache(&mut self, packet_idx: usize, node_idx: usize) -> bool {
+        let now = self.current_time;
+        let key = self.packets.client_id[packet_idx];
+        let cacheable = self.rng.next_f32() < self.nodes[node_idx].cache.hit_ratio;
+        let node = &mut self.nodes[node_idx];
+        let fresh = match node.cache_entries.get(&key) {
+            Some(&expires_at) if now < expires_at => true,
+            Some(_) => {
+                node.cache_entries.remove(&key);
+                false
+            }
+            None => false,
+        };
+        let hit = cacheable && fresh;
+        if hit {
+            node.cache_hits += 1;
+            self.stats.cache_hits += 1;
+        } else {
+            node.cache_misses += 1;
+            self.stats.cache_misses += 1;
+        }
+        hit
+    }
+
     /// spawn_queueを処理し、適切な数のパケットを生成
     fn process_spawn_queue(&mut self) {
         let current_time = self.current_time;
@@ -552,49 +2360,83 @@ impl SimulationState {
 
             if to_spawn > 0 {
                 let mut actually_spawned = 0;
-                for packet in self.packets.iter_mut() {
-                    if packet.active == 0 && actually_spawned < to_spawn {
-                        // パケットを生成
-                        packet.active = 1;
-                        packet.x = task.x;
-                        packet.y = task.y;
-
-                        // 速度にばらつきを加える
-                        let speed =
-                            task.base_speed + (js_random() - 0.5) * 2.0 * task.speed_variance;
-                        packet.speed = speed;
-
-                        // ノード指定モードかチェック
-                        if task.target_node_idx >= 0 {
-                            // ノードターゲットモード: パケットにターゲットノードを設定
-                            packet.target_node_idx = task.target_node_idx;
-                            // velocity は使わない（update_packetsでベクトル計算）
-                            packet.velocity_x = 0.0;
-                            packet.velocity_y = 0.0;
+                while actually_spawned < to_spawn {
+                    // 空きスロットから取り出して生成（前回の使用状態を引き継がないよう初期化）
+                    let Some(packet_idx) = self.packets.alloc() else {
+                        break;
+                    };
+                    let mut packet = self.packets.get(packet_idx);
+                    packet.x = task.x;
+                    packet.y = task.y;
+
+                    // 速度にばらつきを加える
+                    let speed =
+                        task.base_speed + (self.rng.next_f32() - 0.5) * 2.0 * task.speed_variance;
+                    packet.speed = speed;
+
+                    // ノード指定モードかチェック
+                    if task.target_node_idx >= 0 {
+                        // ノードターゲットモード: パケットにターゲットノードを設定
+                        packet.target_node_idx = task.target_node_idx;
+                        // velocity は使わない（update_packetsでベクトル計算）
+                        packet.velocity_x = 0.0;
+                        packet.velocity_y = 0.0;
+                    } else {
+                        // 座標指定モード（従来の動作）
+                        packet.target_node_idx = -1;
+                        let dx = task.target_x - task.x;
+                        let dy = task.target_y - task.y;
+                        let dist = (dx * dx + dy * dy).sqrt();
+                        let (dir_x, dir_y) = if dist > 0.0 {
+                            (dx / dist, dy / dist)
                         } else {
-                            // 座標指定モード（従来の動作）
-                            packet.target_node_idx = -1;
-                            let dx = task.target_x - task.x;
-                            let dy = task.target_y - task.y;
-                            let dist = (dx * dx + dy * dy).sqrt();
-                            let (dir_x, dir_y) = if dist > 0.0 {
-                                (dx / dist, dy / dist)
-                            } else {
-                                (1.0, 0.0)
-                            };
-                            packet.velocity_x = dir_x * speed;
-                            packet.velocity_y = dir_y * speed;
-                        }
+                            (1.0, 0.0)
+                        };
+                        packet.velocity_x = dir_x * speed;
+                        packet.velocity_y = dir_y * speed;
+                    }
 
-                        packet.packet_type = task.packet_type;
-                        packet.complexity = task.complexity;
+                    packet.packet_type = task.packet_type;
+                    packet.complexity = task.complexity;
+                    packet.spawn_time_ms = current_time;
+                    packet.wave_id = task.wave_id;
+                    packet.client_id = match task.client_id {
+                        Some(client_id) => client_id,
+                        None => {
+                            let seq = self.next_client_seq;
+                            self.next_client_seq = seq.wrapping_add(1);
+                            seq % self.client_count
+                        }
+                    };
 
-                        actually_spawned += 1;
+                    // 応答を待つリクエストはクライアントが期限を管理する（攻撃・座標指定モードは除く）
+                    if task.target_node_idx >= 0 && !PacketType::is_attack(task.packet_type) {
+                        let origin = RequestOrigin {
+                            x: task.x,
+                            y: task.y,
+                            target_node_idx: task.target_node_idx,
+                            speed,
+                            packet_type: task.packet_type,
+                            complexity: task.complexity,
+                            wave_id: task.wave_id,
+                            client_id: packet.client_id,
+                            spawn_time_ms: current_time,
+                        };
+                        let (request_id, deadline_ms) =
+                            self.client.register(packet_idx, current_time, 0, origin);
+                        packet.request_id = request_id;
+                        packet.deadline_ms = deadline_ms;
                     }
+                    self.packets.set(packet_idx, &packet);
+
+                    actually_spawned += 1;
                 }
 
                 task.spawned_count += actually_spawned;
                 self.stats.packets_spawned += actually_spawned as u32;
+                if PacketType::is_attack(task.packet_type) {
+                    self.stats.attacks_spawned += actually_spawned as u32;
+                }
             }
 
             // タスク完了チェック
@@ -605,339 +2447,701 @@ impl SimulationState {
 
         // 完了したタスクを削除（逆順で削除してインデックスがずれないように）
         for idx in completed_indices.into_iter().rev() {
-            self.spawn_queue.remove(idx);
+            let task = self.spawn_queue.remove(idx);
+            self.waves_draining.push(task.wave_id);
         }
     }
 
     /// アクティブなパケットの位置を更新（移動中のパケットのみ）
-    fn update_packets(&mut self, _delta_ms: f64) {
+    /// 速度はピクセル/ミリ秒なので、フレームレートによらず同じ時間で同じ距離を進む
+    fn update_packets(&mut self, delta_ms: f64) {
+        if self.gpu_motion {
+            self.apply_motion_events();
+            return;
+        }
+
         // 到達したパケットのインデックスを収集
         let mut arrived_packets: Vec<usize> = Vec::new();
 
-        // まずパケットの移動処理（不変借用でノードを参照）
-        for (idx, packet) in self.packets.iter_mut().enumerate() {
-            if packet.active == 1 && packet.state == PacketState::Moving {
+        // 画面外に出た・ターゲットを失ったパケットのインデックスを収集
+        let mut vanished_packets: Vec<usize> = Vec::new();
+
+        // まずパケットの移動処理（位置・速度・ターゲットの列だけを舐める）
+        let dt = delta_ms as f32;
+        let packets = &mut self.packets;
+        for idx in 0..packets.slot_count() {
+            if packets.active[idx] == 1 && packets.state[idx] == PacketState::Moving {
                 // 移動中のパケットのみ処理
-                if packet.target_node_idx >= 0
-                    && (packet.target_node_idx as usize) < self.nodes.len()
-                {
-                    let target = &self.nodes[packet.target_node_idx as usize];
+                let target_node_idx = packets.target_node_idx[idx];
+                let [x, y] = packets.position[idx];
+                if target_node_idx >= 0 && (target_node_idx as usize) < self.nodes.len() {
+                    let target = &self.nodes[target_node_idx as usize];
 
                     // ベクトル計算（目的地 - 現在地）
-                    let dx = target.x - packet.x;
-                    let dy = target.y - packet.y;
+                    let dx = target.x - x;
+                    let dy = target.y - y;
 
                     // 距離計算
                     let dist_sq = dx * dx + dy * dy;
                     let dist = dist_sq.sqrt();
 
-                    // 到達判定（半径5.0以内なら到着）
-                    if dist < 5.0 {
+                    // このtickで進める距離
+                    let step = packets.speed[idx] * dt;
+
+                    // 到達判定（半径ARRIVAL_RADIUS以内に入るなら到着）
+                    // 進める距離が残りより長くても通り過ぎないよう、ノード位置で止める
+                    if dist < ARRIVAL_RADIUS || step >= dist - ARRIVAL_RADIUS {
                         // 到達！→ 後で処理
+                        packets.position[idx] = [target.x, target.y];
                         arrived_packets.push(idx);
                     } else {
-                        // 正規化して速度を掛けて移動
-                        if dist > 0.0 {
-                            packet.x += (dx / dist) * packet.speed;
-                            packet.y += (dy / dist) * packet.speed;
-                        }
+                        // 正規化して進む距離を掛けて移動
+                        packets.position[idx] = [x + (dx / dist) * step, y + (dy / dist) * step];
                     }
-                } else if packet.target_node_idx == -1 {
+                } else if target_node_idx == -1 {
                     // 座標指定モード（従来のvelocity使用）
-                    packet.x += packet.velocity_x;
-                    packet.y += packet.velocity_y;
+                    let [velocity_x, velocity_y] = packets.velocity[idx];
+                    let (x, y) = (x + velocity_x * dt, y + velocity_y * dt);
+                    packets.position[idx] = [x, y];
+
+                    // 画面外に出たら非アクティブに
+                    if !(-OFFSCREEN_MARGIN..=WIDTH + OFFSCREEN_MARGIN).contains(&x)
+                        || !(-OFFSCREEN_MARGIN..=HEIGHT + OFFSCREEN_MARGIN).contains(&y)
+                    {
+                        vanished_packets.push(idx);
+                    }
+                } else {
+                    // ターゲットがないか無効ならその場で消滅
+                    vanished_packets.push(idx);
+                }
+            }
+        }
+        for packet_idx in vanished_packets {
+            self.vanish_packet(packet_idx);
+        }
+
+        // 到達したパケットの処理（ルーティング）
+        for packet_idx in arrived_packets {
+            self.handle_packet_arrival(packet_idx);
+        }
+    }
 
-                    // 画面外に出たら非アクティブに
-                    if packet.x < -50.0
-                        || packet.x > WIDTH + 50.0
-                        || packet.y < -50.0
-                        || packet.y > HEIGHT + 50.0
-                    {
-                        packet.active = 0;
-                    }
-                } else {
-                    // ターゲットがないか無効ならその場で消滅
-                    packet.active = 0;
-                }
+    /// GPUから届いた到達・消滅を反映（GPU移動モードでのupdate_packets）
+    /// 古い移動状態に対するもの・すでに移動中でないものは捨て、CPUでの移動と同じくスロット順に処理する
+    fn apply_motion_events(&mut self) {
+        let mut events = std::mem::take(&mut self.motion_events);
+        events.sort_unstable_by_key(|event| event.slot);
+
+        let mut arrived_packets: Vec<usize> = Vec::new();
+        let mut vanished_packets: Vec<usize> = Vec::new();
+        for event in events.drain(..) {
+            let idx = event.slot;
+            if !self.packets.is_active(idx)
+                || self.packets.state[idx] != PacketState::Moving
+                || !self.packets.is_current_motion(idx, event.seq)
+            {
+                continue;
+            }
+            if event.vanished {
+                vanished_packets.push(idx);
+            } else {
+                arrived_packets.push(idx);
             }
         }
+        self.motion_events = events; // 確保済みの領域を使い回す
 
-        // 到達したパケットの処理（ルーティング）
+        for packet_idx in vanished_packets {
+            self.vanish_packet(packet_idx);
+        }
         for packet_idx in arrived_packets {
             self.handle_packet_arrival(packet_idx);
         }
     }
 
+    /// 移動中に消えたパケットを解放（座標指定モードで画面外に出たものは正常、ノードを見失ったものはドロップ）
+    fn vanish_packet(&mut self, packet_idx: usize) {
+        if self.packets.target_node_idx[packet_idx] == -1 {
+            self.packets.release(packet_idx);
+        } else {
+            self.drop_packet(packet_idx, None, DropReason::InvalidTarget);
+        }
+    }
+
     /// パケットがターゲットノードに到達したときの処理（負荷モデル対応）
     fn handle_packet_arrival(&mut self, packet_idx: usize) {
-        let target_node_idx = self.packets[packet_idx].target_node_idx;
+        let target_node_idx = self.packets.target_node_idx[packet_idx];
 
         // ターゲットが存在しないなら終了
         if target_node_idx < 0 || (target_node_idx as usize) >= self.nodes.len() {
-            self.packets[packet_idx].active = 0;
+            self.vanish_packet(packet_idx);
             return;
         }
 
         let node_idx = target_node_idx as usize;
+
+        // 停止中・再起動中のノードには入れない
+        if !self.nodes[node_idx].is_available() {
+            self.evacuate_packet(packet_idx, node_idx);
+            return;
+        }
+
+        // Firewall: リクエストを検査し、遮断したものはここで消える
+        if self.nodes[node_idx].is_firewall()
+            && !self.packets.is_response[packet_idx]
+            && !self.inspect_at_firewall(packet_idx, node_idx)
+        {
+            return;
+        }
+
+        // RateLimiter: トークンが取れなかったリクエストはここで拒否
+        if self.nodes[node_idx].is_rate_limiter()
+            && !self.packets.is_response[packet_idx]
+            && !self.admit_at_rate_limiter(packet_idx, node_idx)
+        {
+            return;
+        }
         
         // パケットサイズを取得
-        let packet_size = self.packets[packet_idx].size;
+        let packet_size = self.packets.size[packet_idx];
         
         // ノードの情報を取得
         let node_type = self.nodes[node_idx].node_type;
         let base_process_time = self.nodes[node_idx].spec.process_time_ms;
-        let bandwidth_factor = self.nodes[node_idx].spec.bandwidth_factor;
         let max_concurrent = self.nodes[node_idx].spec.max_concurrent;
         let queue_capacity = self.nodes[node_idx].spec.queue_capacity;
         let current_processing = self.nodes[node_idx].processing_packets.len() as u32;
-        let current_queue = self.nodes[node_idx].queue.len() as u32;
+        let current_queue = self.nodes[node_idx].queue_size();
         let node_pos = (self.nodes[node_idx].x, self.nodes[node_idx].y);
 
-        // パケットサイズに応じた処理時間を計算
-        // レスポンス（大きいパケット）は帯域を消費して処理が遅くなる
-        let size_multiplier = 1.0 + (packet_size as f64 - 1.0) * bandwidth_factor;
-        let adjusted_process_time = base_process_time * size_multiplier;
+        // 処理時間を計算
+        // レスポンス（大きいパケット）は帯域を消費し、HeavyTaskは複雑度に応じて遅くなる
+        let adjusted_process_time =
+            self.nodes[node_idx].process_time_for(&self.packets.get(packet_idx));
 
         // パケット位置をノード位置に更新
-        self.packets[packet_idx].x = node_pos.0;
-        self.packets[packet_idx].y = node_pos.1;
-        self.packets[packet_idx].current_node_idx = node_idx as i32;
+        self.packets.position[packet_idx][0] = node_pos.0;
+        self.packets.position[packet_idx][1] = node_pos.1;
+        self.packets.set_current_node(packet_idx, node_idx as i32);
+
+        // リクエストは通過履歴にノードを積む（レスポンスの戻り経路になる）
+        if !self.packets.is_response[packet_idx]
+            && !self.packets.push_route(packet_idx, node_idx)
+        {
+            // 経路が長すぎる（ループ等）場合はドロップ
+            self.discard_packet(packet_idx, Some(node_idx), DropReason::RouteTooLong);
+            return;
+        }
 
         // 処理時間が0のノード（Gateway等）は即座に次へ転送
         if base_process_time <= 0.0 {
-            self.route_packet_to_next(packet_idx, node_type, node_pos);
+            self.route_packet_to_next(packet_idx, node_idx, false);
             return;
         }
 
         // Serverノードの場合、リクエスト時に通過サーバーを記録
-        if node_type == 2 && !self.packets[packet_idx].is_response {
-            self.packets[packet_idx].origin_server_idx = node_idx as i32;
+        if node_type == 2 && !self.packets.is_response[packet_idx] {
+            self.packets.origin_server_idx[packet_idx] = node_idx as i32;
+        }
+
+        // SYN Flood: ワーカーノードでは接続待ちとしてキュー枠を占有し、応答しない
+        if self.packets.packet_type[packet_idx] == PacketType::SynFlood as u32
+            && !self.packets.is_response[packet_idx]
+            && self.nodes[node_idx].is_worker()
+        {
+            if current_queue < queue_capacity {
+                self.packets.state[packet_idx] = PacketState::Queued;
+                self.nodes[node_idx].syn_backlog.push(HeldPacket {
+                    packet_idx,
+                    remaining_time_ms: SYN_HOLD_TIME_MS,
+                });
+            } else {
+                self.discard_packet(packet_idx, Some(node_idx), DropReason::QueueFull);
+            }
+            return;
         }
 
         // 負荷チェック: 処理可能か？
         if current_processing < max_concurrent {
             // 処理開始（サイズに応じた処理時間）
-            self.packets[packet_idx].state = PacketState::Processing;
+            self.packets.state[packet_idx] = PacketState::Processing;
             self.nodes[node_idx].processing_packets.push(ProcessingPacket {
                 packet_idx,
                 remaining_time_ms: adjusted_process_time,
+                process_time_ms: adjusted_process_time,
                 packet_size,
             });
-        } else if current_queue < queue_capacity {
-            // キューに追加
-            self.packets[packet_idx].state = PacketState::Queued;
-            self.nodes[node_idx].queue.push(QueuedPacket { packet_idx });
         } else {
-            // ドロップ！
-            self.packets[packet_idx].active = 0;
-            self.nodes[node_idx].total_dropped += 1;
-            self.stats.packets_dropped += 1;
+            // キューに追加（満杯・早期廃棄・押し出しはキュー規律に従う）
+            // SYN Floodが占有している枠は使えない
+            let node = &mut self.nodes[node_idx];
+            let capacity = (queue_capacity as usize).saturating_sub(node.syn_backlog.len());
+            let item = QueuedPacket {
+                packet_idx,
+                enqueued_ms: self.current_time,
+                class: node.queue.class_of(&self.packets.get(packet_idx)),
+            };
+            let rng = &mut self.rng;
+            match node.queue.offer(item, capacity, &mut || rng.next_f32()) {
+                Enqueue::Queued => {
+                    self.packets.state[packet_idx] = PacketState::Queued;
+                }
+                Enqueue::Evicted(victim_idx) => {
+                    self.packets.state[packet_idx] = PacketState::Queued;
+                    self.count_queue_drop(victim_idx, node_idx, QueueDrop::Priority);
+                }
+                Enqueue::Dropped(cause) => {
+                    // ドロップ！
+                    self.count_queue_drop(packet_idx, node_idx, cause);
+                }
+            }
+        }
+    }
+
+    /// キューが捨てたパケットをドロップし、原因ごとの数も統計に数える
+    fn count_queue_drop(&mut self, packet_idx: usize, node_idx: usize, cause: QueueDrop) {
+        self.discard_packet(packet_idx, Some(node_idx), cause.into());
+        match cause {
+            QueueDrop::Tail => self.stats.queue_tail_drops += 1,
+            QueueDrop::Priority => self.stats.queue_priority_drops += 1,
+            QueueDrop::Red => self.stats.queue_red_drops += 1,
+            QueueDrop::Codel => self.stats.queue_codel_drops += 1,
         }
     }
 
     /// パケットを次のノードへルーティング
-    /// リクエスト: Gateway -> LB -> Server -> DB
-    /// レスポンス: DB -> Server -> LB -> Gateway（逆方向、リクエスト時と同じサーバーを経由）
-    fn route_packet_to_next(&mut self, packet_idx: usize, current_node_type: u32, current_pos: (f32, f32)) {
-        let is_response = self.packets[packet_idx].is_response;
-        let origin_server_idx = self.packets[packet_idx].origin_server_idx;
-        
-        let next_node = if is_response {
-            // レスポンス: 逆方向にルーティング（リクエスト時と同じサーバーを経由）
-            match current_node_type {
-                3 => {
-                    // DB -> Server: リクエスト時に通ったサーバーに戻る
-                    if origin_server_idx >= 0 && (origin_server_idx as usize) < self.nodes.len() {
-                        Some(origin_server_idx as usize)
-                    } else {
-                        // フォールバック: 最初のServerを返す
-                        self.find_next_node_by_type(2)
-                    }
-                }
-                2 => self.find_next_node_by_type(1),           // Server -> LB
-                1 => self.find_next_node_by_type(0),           // LB -> Gateway
-                0 => {
-                    // Gateway到達 = レスポンス完了
-                    self.packets[packet_idx].active = 0;
-                    self.stats.packets_processed += 1;
+    /// リクエスト: リンク（未定義なら Gateway -> LB -> Server -> DB の順）に沿って進む
+    ///   次のノードがないServer/DBが終端となり、レスポンスに変換される
+    /// レスポンス: リクエスト時の通過履歴を逆順に辿り、最初のノード（Gateway）で完了
+    /// requeued: 停止したノードから送り直すリクエスト（そのノードは通過済みなので、Cacheは引き直さない）
+    fn route_packet_to_next(&mut self, packet_idx: usize, node_idx: usize, requeued: bool) {
+        let current_node_type = self.nodes[node_idx].node_type;
+
+        let next_node = if self.packets.is_response[packet_idx] {
+            // レスポンス: 自ノードを履歴から外し、直前のノードへ戻る
+            // Cache: 戻り経路で通過したレスポンスの内容をキャッシュに入れる（429応答は除く）
+            if self.nodes[node_idx].is_cache() && !self.packets.rejected[packet_idx] {
+                let key = self.packets.client_id[packet_idx];
+                let cache = &mut self.nodes[node_idx];
+                let expires_at = self.current_time + cache.cache.ttl_ms;
+                cache.cache_entries.insert(key, expires_at);
+            }
+            if self.packets.last_route(packet_idx) == Some(node_idx) {
+                self.packets.pop_route(packet_idx);
+            }
+            match self.packets.last_route(packet_idx) {
+                Some(prev_idx) if prev_idx < self.nodes.len() => Some(prev_idx),
+                Some(_) => None,
+                None => {
+                    // 入口ノードに到達 = レスポンス完了
+                    self.complete_request(packet_idx);
                     return;
                 }
-                _ => None,
             }
         } else {
-            // リクエスト: 順方向にルーティング
-            match current_node_type {
-                0 => self.find_next_node_by_type(1), // Gateway -> LB
-                1 => self.find_next_server_target(), // LB -> Server (負荷分散)
-                2 => self.find_next_node_by_type(3), // Server -> DB
-                3 => {
-                    // DB到達 = リクエスト処理完了、レスポンスに変換
-                    let origin_server = self.packets[packet_idx].origin_server_idx;
-                    let p = &mut self.packets[packet_idx];
-                    p.is_response = true;
-                    p.size = 10.0;  // レスポンスはリクエストの10倍のサイズ
-                    p.target_node_idx = -1;
-                    p.current_node_idx = -1;
-                    p.state = PacketState::Moving;
-                    p.x = current_pos.0;
-                    p.y = current_pos.1;
-                    
-                    // DBから次のノード（元のServer）へ向かう
-                    let next_server = if origin_server >= 0 && (origin_server as usize) < self.nodes.len() {
-                        Some(origin_server as usize)
-                    } else {
-                        self.find_next_node_by_type(2)
-                    };
-                    
-                    if let Some(next_idx) = next_server {
-                        self.packets[packet_idx].target_node_idx = next_idx as i32;
-                    } else {
-                        // 次がない場合は完了扱い
-                        self.packets[packet_idx].active = 0;
-                        self.stats.packets_processed += 1;
+            let candidates = self.next_hop_candidates(node_idx);
+            let cache_hit = !requeued
+                && self.nodes[node_idx].is_cache()
+                && self.lookup_cache(packet_idx, node_idx);
+            let is_terminal =
+                candidates.is_empty() && (current_node_type == 2 || current_node_type == 3);
+            if cache_hit || is_terminal {
+                // 終端ノード到達・キャッシュヒット = リクエスト処理完了、レスポンスに変換
+                self.packets.make_response(packet_idx, 10.0); // レスポンスはリクエストの10倍のサイズ
+                self.packets.pop_route(packet_idx);
+                match self.packets.last_route(packet_idx) {
+                    Some(prev_idx) if prev_idx < self.nodes.len() => Some(prev_idx),
+                    _ => {
+                        // 戻り先がない場合は完了扱い
+                        self.complete_request(packet_idx);
+                        return;
                     }
-                    return;
                 }
-                _ => None,
+            } else {
+                // 停止中・再起動中・削除待ちのノードには振り分けない
+                let candidates: Vec<usize> = candidates
+                    .into_iter()
+                    .filter(|&i| self.nodes[i].accepts_requests())
+                    .collect();
+                if current_node_type == 1 {
+                    // LB: ノードごとの振り分け戦略で選択
+                    self.select_by_strategy(node_idx, &candidates, packet_idx)
+                } else {
+                    self.select_least_loaded(&candidates)
+                }
             }
         };
 
         if let Some(next_idx) = next_node {
-            let p = &mut self.packets[packet_idx];
-            p.target_node_idx = next_idx as i32;
-            p.current_node_idx = -1; // 移動中
-            p.state = PacketState::Moving;
-            p.x = current_pos.0;
-            p.y = current_pos.1;
+            self.send_to(packet_idx, node_idx, next_idx);
         } else {
             // 次のノードがない = ドロップ
-            self.packets[packet_idx].active = 0;
-            self.stats.packets_dropped += 1;
+            self.discard_packet(packet_idx, Some(node_idx), DropReason::NoRoute);
+        }
+    }
+
+    /// ノードから次のノードへパケットを送り出す
+    /// 2つのノードを結ぶリンクが伝送特性を持てばリンクに乗せ、なければ移動速度で移動させる
+    fn send_to(&mut self, packet_idx: usize, node_idx: usize, next_idx: usize) {
+        let (from_id, to_id) = (self.nodes[node_idx].id, self.nodes[next_idx].id);
+        let origin = [self.nodes[node_idx].x, self.nodes[node_idx].y];
+        let size = self.packets.size[packet_idx];
+        let Some(link) = self.links.iter_mut().find(|l| l.connects(from_id, to_id)) else {
+            self.packets.start_moving(packet_idx, next_idx, origin);
+            return;
+        };
+        link.record_sent(size);
+        if !link.profile.is_modeled() {
+            self.packets.start_moving(packet_idx, next_idx, origin);
+            return;
         }
+
+        // 伝搬遅延が未指定なら、移動速度で到達半径まで進むのにかかる時間を使う
+        let target = &self.nodes[next_idx];
+        let dist = ((target.x - origin[0]).powi(2) + (target.y - origin[1]).powi(2)).sqrt();
+        let speed = self.packets.speed[packet_idx];
+        let propagation_ms = if speed > 0.0 {
+            ((dist - ARRIVAL_RADIUS).max(0.0) / speed) as f64
+        } else {
+            0.0
+        };
+        link.enter(packet_idx, origin, size, propagation_ms);
+        self.packets.enter_link(packet_idx, next_idx, origin);
+    }
+
+    /// リンク上を移動中のパケットをドロップ（リンクを削除する前に呼ぶ）
+    fn drop_link_packets(&mut self, link_idx: usize) {
+        let in_flight = std::mem::take(&mut self.links[link_idx].in_flight);
+        for transit in in_flight {
+            self.drop_packet(transit.packet_idx, None, DropReason::LinkRemoved);
+        }
+    }
+
+    /// リンク上のパケットを進め、届いたものをノードに到達させる（損失したものはドロップ）
+    fn update_links(&mut self, delta_ms: f64) {
+        let mut delivered: Vec<usize> = Vec::new();
+        let mut arrived_packets: Vec<usize> = Vec::new();
+        for link_idx in 0..self.links.len() {
+            delivered.clear();
+            self.links[link_idx].advance(delta_ms, &mut delivered);
+
+            // 移動中のパケットはリンク上の進み具合に合わせて描く
+            let link = &self.links[link_idx];
+            for transit in &link.in_flight {
+                let idx = transit.packet_idx;
+                let target_node_idx = self.packets.target_node_idx[idx];
+                if target_node_idx < 0 || target_node_idx as usize >= self.nodes.len() {
+                    continue;
+                }
+                let target = &self.nodes[target_node_idx as usize];
+                let t = transit.progress;
+                self.packets.position[idx] = [
+                    transit.origin[0] + (target.x - transit.origin[0]) * t,
+                    transit.origin[1] + (target.y - transit.origin[1]) * t,
+                ];
+                self.packets.mark_motion_dirty(idx);
+            }
+
+            for &packet_idx in &delivered {
+                if !self.packets.is_active(packet_idx)
+                    || self.packets.state[packet_idx] != PacketState::OnLink
+                {
+                    continue;
+                }
+                let size = self.packets.size[packet_idx];
+                let lost = self.links[link_idx].profile.loss_rate > 0.0
+                    && self.rng.next_f32() < self.links[link_idx].profile.loss_rate;
+                self.links[link_idx].record_delivery(size, lost);
+                if lost {
+                    let target_node_idx = self.packets.target_node_idx[packet_idx];
+                    let node_idx = (target_node_idx >= 0).then_some(target_node_idx as usize);
+                    self.drop_packet(packet_idx, node_idx, DropReason::LinkLoss);
+                } else {
+                    arrived_packets.push(packet_idx);
+                }
+            }
+        }
+
+        // 到達処理はリンクをすべて進めてから（到達先で次のリンクに乗ることがあるため）
+        for packet_idx in arrived_packets {
+            let target_node_idx = self.packets.target_node_idx[packet_idx];
+            if target_node_idx >= 0 && (target_node_idx as usize) < self.nodes.len() {
+                let target = &self.nodes[target_node_idx as usize];
+                self.packets.position[packet_idx] = [target.x, target.y];
+            }
+            self.packets.state[packet_idx] = PacketState::Moving;
+            self.handle_packet_arrival(packet_idx);
+        }
+    }
+
+    /// ノードの処理枠の使用状況を集計し、記録間隔が経ったら全ノード分を1サンプルずつ記録する
+    fn sample_node_metrics(&mut self, delta_ms: f64) {
+        for node in self.nodes.iter_mut() {
+            node.metrics.accumulate_busy(node.processing_packets.len(), delta_ms);
+        }
+
+        let window_ms = self.current_time - self.metrics_window_start_ms;
+        if window_ms < self.metrics_config.interval_ms {
+            return;
+        }
+        for node in self.nodes.iter_mut() {
+            node.metrics.push_sample(
+                self.metrics_config.capacity,
+                self.current_time,
+                window_ms,
+                node.total_processed,
+                node.total_dropped,
+                node.queue.len(),
+                node.spec.max_concurrent,
+            );
+        }
+        self.metrics_window_start_ms = self.current_time;
+    }
+
+    /// ノードの飽和・回復をイベントにする
+    /// 処理枠もキューも埋まったら飽和、処理枠が空くかキューが半分まで空いたら回復とする（境目で繰り返さないように）
+    fn update_saturation(&mut self) {
+        let now = self.current_time;
+        for node in self.nodes.iter_mut() {
+            let busy = node.processing_packets.len() >= node.spec.max_concurrent as usize;
+            let queued = node.queue_size();
+            if !node.saturated && busy && queued >= node.spec.queue_capacity {
+                node.saturated = true;
+                self.events
+                    .push(now, SimEventKind::NodeSaturated { node_id: node.id });
+            } else if node.saturated && (!busy || queued * 2 <= node.spec.queue_capacity) {
+                node.saturated = false;
+                self.events
+                    .push(now, SimEventKind::NodeRecovered { node_id: node.id });
+            }
+        }
+    }
+
+    /// 生成し終わったWaveのうち、パケット・応答待ち・送り直し予約が残っていないものを終了とする
+    fn update_finished_waves(&mut self) {
+        if self.waves_draining.is_empty() {
+            return;
+        }
+        let now = self.current_time;
+        let packets = &self.packets;
+        let client = &self.client;
+        let events = &mut self.events;
+        self.waves_draining.retain(|&wave_id| {
+            if packets.wave_packets(wave_id) > 0 || client.wave_requests(wave_id) > 0 {
+                return true;
+            }
+            events.push(now, SimEventKind::WaveFinished { wave_id });
+            false
+        });
     }
 
     /// ノードでの処理時間を進め、完了したパケットを次へ送る
     fn process_nodes(&mut self, delta_ms: f64) {
         // 処理完了したパケットを収集
         let mut completed: Vec<(usize, usize)> = Vec::new(); // (node_idx, packet_idx)
+        // 占有時間が切れたSYN Flood
+        let mut expired: Vec<usize> = Vec::new();
+        // キューから取り出すときに捨てられたパケット（CoDel）
+        let mut codel_dropped: Vec<usize> = Vec::new();
+        let mut codel_nodes: Vec<usize> = Vec::new(); // codel_dropped と同じ並びの、捨てたノード
+        // このtick内で処理が終わってから余った時間（次に取り出すパケットの処理に回す）
+        let mut leftover_ms: Vec<f64> = Vec::new();
+        let now = self.current_time;
 
         // 各ノードの処理時間を減算
         for (node_idx, node) in self.nodes.iter_mut().enumerate() {
-            let mut completed_indices = Vec::new();
+            // SYN Floodの占有時間を進め、切れたものは枠を解放
+            node.syn_backlog.retain_mut(|held| {
+                held.remaining_time_ms -= delta_ms;
+                if held.remaining_time_ms <= 0.0 {
+                    expired.push(held.packet_idx);
+                    false
+                } else {
+                    true
+                }
+            });
             
-            for (i, proc) in node.processing_packets.iter_mut().enumerate() {
+            // 処理完了したものを取り除く（1回の走査で詰め直す）
+            // 余った時間を取っておき、tickの長さ（フレームレート）で処理能力が変わらないようにする
+            let before = node.processing_packets.len();
+            leftover_ms.clear();
+            node.processing_packets.retain_mut(|proc| {
                 proc.remaining_time_ms -= delta_ms;
                 if proc.remaining_time_ms <= 0.0 {
-                    completed_indices.push(i);
                     completed.push((node_idx, proc.packet_idx));
+                    leftover_ms.push(-proc.remaining_time_ms);
+                    node.metrics.record_service(proc.process_time_ms);
+                    false
+                } else {
+                    true
                 }
-            }
-
-            // 処理完了したものを削除（逆順）
-            for i in completed_indices.into_iter().rev() {
-                node.processing_packets.remove(i);
-                node.total_processed += 1;
-            }
+            });
+            node.total_processed += (before - node.processing_packets.len()) as u32;
 
             // キューから次のパケットを処理開始
-            while node.processing_packets.len() < node.spec.max_concurrent as usize
-                && !node.queue.is_empty()
-            {
-                let queued = node.queue.remove(0);
+            while node.processing_packets.len() < node.spec.max_concurrent as usize {
+                let next = node.queue.pop(now, &mut codel_dropped);
+                codel_nodes.resize(codel_dropped.len(), node_idx);
+                let Some(queued) = next else {
+                    break;
+                };
                 
-                // パケットサイズに応じた処理時間を計算
-                let packet_size = if queued.packet_idx < self.packets.len() {
-                    self.packets[queued.packet_idx].size
+                // パケットサイズ・複雑度に応じた処理時間を計算
+                let (packet_size, adjusted_process_time) = if queued.packet_idx < self.packets.len() {
+                    let packet = self.packets.get(queued.packet_idx);
+                    (packet.size, node.process_time_for(&packet))
                 } else {
-                    1.0
+                    (1.0, node.spec.process_time_ms)
                 };
-                let size_multiplier = 1.0 + (packet_size as f64 - 1.0) * node.spec.bandwidth_factor;
-                let adjusted_process_time = node.spec.process_time_ms * size_multiplier;
                 
+                // 空いた枠で余った時間はこのパケットの処理に使ったものとする
+                let carried_ms = leftover_ms.pop().unwrap_or(0.0);
                 node.processing_packets.push(ProcessingPacket {
                     packet_idx: queued.packet_idx,
-                    remaining_time_ms: adjusted_process_time,
+                    remaining_time_ms: adjusted_process_time - carried_ms,
+                    process_time_ms: adjusted_process_time,
                     packet_size,
                 });
                 // パケットの状態を更新
                 if queued.packet_idx < self.packets.len() {
-                    self.packets[queued.packet_idx].state = PacketState::Processing;
+                    self.packets.state[queued.packet_idx] = PacketState::Processing;
                 }
             }
         }
 
+        for (packet_idx, node_idx) in codel_dropped.into_iter().zip(codel_nodes) {
+            self.count_queue_drop(packet_idx, node_idx, QueueDrop::Codel);
+        }
+
+        // SYN Floodは応答せずに消える
+        for packet_idx in expired {
+            if self.packets.release(packet_idx) {
+                self.stats.attacks_absorbed += 1;
+            }
+        }
+
         // 処理完了したパケットを次のノードへルーティング
+        // Killerの影響は全員を送り出してから与える（同じtickに処理を終えたパケットは停止前に出ていく）
+        let mut killer_hits: Vec<usize> = Vec::new();
         for (node_idx, packet_idx) in completed {
-            if packet_idx < self.packets.len() && self.packets[packet_idx].active == 1 {
-                let node_type = self.nodes[node_idx].node_type;
-                let node_pos = (self.nodes[node_idx].x, self.nodes[node_idx].y);
-                self.route_packet_to_next(packet_idx, node_type, node_pos);
+            if packet_idx < self.packets.len() && self.packets.active[packet_idx] == 1 {
+                if self.packets.packet_type[packet_idx] == PacketType::Killer as u32
+                    && !self.packets.is_response[packet_idx]
+                    && self.nodes[node_idx].is_worker()
+                {
+                    // Killer: パケット自体は消える
+                    self.packets.release(packet_idx);
+                    self.stats.attacks_absorbed += 1;
+                    killer_hits.push(node_idx);
+                    continue;
+                }
+                self.route_packet_to_next(packet_idx, node_idx, false);
+            }
+        }
+
+        // Killer: 処理したノードを劣化させ（劣化中なら停止させ）る
+        for node_idx in killer_hits {
+            if self.nodes[node_idx].is_degraded() {
+                self.crash_node_at(node_idx);
+            } else if self.nodes[node_idx].is_available() {
+                self.degrade_node_at(node_idx);
             }
         }
     }
 
-    /// 指定タイプのノードを検索して返す
-    fn find_next_node_by_type(&self, node_type: u32) -> Option<usize> {
-        for (i, node) in self.nodes.iter().enumerate() {
-            if node.node_type == node_type {
-                return Some(i);
+    /// 指定ノードから次に進めるノードの候補を返す
+    /// リンクが定義されていればリンク先のみ、未定義なら従来のタイプ順で次のタイプの全ノード
+    fn next_hop_candidates(&self, node_idx: usize) -> Vec<usize> {
+        let node = &self.nodes[node_idx];
+
+        if !self.links.is_empty() {
+            return self
+                .links
+                .iter()
+                .filter(|l| l.from_id == node.id)
+                .filter_map(|l| self.nodes.iter().position(|n| n.id == l.to_id))
+                .collect();
+        }
+
+        // 従来モード: チェーン上で自分より後ろにある、ノードが存在する最初のタイプへ進む
+        let Some(pos) = LEGACY_CHAIN.iter().position(|&t| t == node.node_type) else {
+            return Vec::new();
+        };
+        for &next_type in &LEGACY_CHAIN[pos + 1..] {
+            let candidates: Vec<usize> = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| n.node_type == next_type)
+                .map(|(i, _)| i)
+                .collect();
+            if !candidates.is_empty() {
+                return candidates;
             }
         }
-        None
+        Vec::new()
     }
 
-    /// ロードバランシング: 最も負荷の低いServerを選択
-    fn find_next_server_target(&self) -> Option<usize> {
-        // node_type == 2 (Server) のノードを収集
-        let servers: Vec<(usize, f32)> = self
-            .nodes
+    /// 振り分け候補の負荷情報を収集
+    fn lb_candidates(&self, candidates: &[usize]) -> Vec<Candidate> {
+        candidates
             .iter()
-            .enumerate()
-            .filter(|(_, node)| node.node_type == 2)
-            .map(|(i, node)| {
-                // 負荷率 = (処理中 + キュー) / max_concurrent
-                let load = (node.processing_packets.len() + node.queue.len()) as f32
-                    / node.spec.max_concurrent.max(1) as f32;
-                (i, load)
+            .map(|&i| {
+                let node = &self.nodes[i];
+                let connections = (node.processing_packets.len() + node.queue.len()) as u32;
+                Candidate {
+                    node_idx: i,
+                    node_id: node.id,
+                    // 負荷率 = (処理中 + キュー) / max_concurrent
+                    load_rate: connections as f32 / node.spec.max_concurrent.max(1) as f32,
+                    connections,
+                    weight: node.spec.max_concurrent,
+                }
             })
-            .collect();
+            .collect()
+    }
 
-        if servers.is_empty() {
-            None
-        } else {
-            // 最も負荷の低いサーバーを選択
-            servers
-                .iter()
-                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
-                .map(|(idx, _)| *idx)
-        }
+    /// ロードバランシング: 候補の中から最も負荷の低いノードを選択
+    fn select_least_loaded(&self, candidates: &[usize]) -> Option<usize> {
+        let candidates = self.lb_candidates(candidates);
+        // LeastLoadは乱数を使わない
+        LbState::default().select(LbStrategy::LeastLoad, &candidates, 0, &mut || 0.0)
+    }
+
+    /// ロードバランシング: LBノードに設定された戦略で候補から選択
+    fn select_by_strategy(&mut self, lb_idx: usize, candidates: &[usize], packet_idx: usize) -> Option<usize> {
+        let candidates = self.lb_candidates(candidates);
+        let client_key = self.packets.client_id[packet_idx];
+        let rng = &mut self.rng;
+        let lb = &mut self.nodes[lb_idx];
+        lb.lb_state
+            .select(lb.lb_strategy, &candidates, client_key, &mut || rng.next_f32())
     }
 
     /// アクティブなパケットの座標をf32配列として抽出（描画用）
     pub fn get_active_coords(&self) -> Vec<f32> {
         let mut coords = Vec::new();
-        for packet in &self.packets {
-            if packet.active == 1 {
-                coords.push(packet.x);
-                coords.push(packet.y);
+        for idx in 0..self.packets.slot_count() {
+            if self.packets.active[idx] == 1 {
+                coords.extend_from_slice(&self.packets.position[idx]);
             }
         }
         coords
     }
-    
-    /// アクティブなパケットの詳細情報を取得（描画用）
-    /// 戻り値: [x, y, is_response(0.0/1.0), size] の配列
-    pub fn get_active_packet_details(&self) -> Vec<f32> {
-        let mut details = Vec::new();
-        for packet in &self.packets {
-            if packet.active == 1 {
-                details.push(packet.x);
-                details.push(packet.y);
-                details.push(if packet.is_response { 1.0 } else { 0.0 });
-                details.push(packet.size);
-            }
-        }
-        details
+
+    /// 描画用のパケット列（位置 [x, y] と見た目 [r, g, b, size]、先頭からslot_count個）
+    /// 非アクティブなスロットはサイズ0なので、詰め直さずにそのままGPUへ転送できる
+    pub fn packet_render_columns(&self) -> (&[[f32; 2]], &[[f32; 4]]) {
+        let count = self.packets.slot_count();
+        (
+            &self.packets.position[..count],
+            &self.packets.appearance[..count],
+        )
+    }
+
+    /// 各ノードの稼働状態を取得（NodeHealth as u32）
+    /// 戻り値: [node0_health, node1_health, ...]
+    pub fn get_node_health_states(&self) -> Vec<u32> {
+        self.nodes.iter().map(|node| node.health as u32).collect()
     }
 
     /// 各ノードの負荷率を取得（0.0 - 1.0+）
@@ -957,3 +3161,777 @@ impl SimulationState {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Gateway -> LB -> Server x2 の最小構成（リンクなし = タイプ順ルーティング）
+    fn build(seed: u64, server_queue: u32) -> SimulationState {
+        let mut sim = SimulationState::with_seed(1000, seed);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 540.0, NodeType::LB as u32);
+        sim.add_node_with_spec(
+            2,
+            800.0,
+            300.0,
+            NodeType::Server as u32,
+            2,
+            80.0,
+            server_queue,
+            50,
+        );
+        sim.add_node_with_spec(
+            3,
+            800.0,
+            780.0,
+            NodeType::Server as u32,
+            2,
+            80.0,
+            server_queue,
+            50,
+        );
+        sim
+    }
+
+    // Gatewayから count 個を duration_ms かけて流し、処理が終わるまで進める
+    fn run(sim: &mut SimulationState, count: usize, duration_ms: f64) {
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            count,
+            duration_ms,
+            0.4,
+            0.1,
+            PacketType::Normal as u32,
+            BASE_COMPLEXITY,
+        );
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                return;
+            }
+        }
+        panic!("simulation did not become idle");
+    }
+
+    #[test]
+    fn wave_is_fully_processed_within_capacity() {
+        let mut sim = build(1, 100);
+        run(&mut sim, 40, 4000.0);
+
+        assert_eq!(sim.get_stats_spawned(), 40);
+        assert_eq!(sim.get_stats_processed(), 40);
+        assert_eq!(sim.get_stats_dropped(), 0);
+        assert_eq!(sim.get_latency_count(), 40);
+        assert_eq!(sim.get_active_count(), 0);
+    }
+
+    #[test]
+    fn overload_drops_at_full_queues() {
+        let mut sim = build(1, 2);
+        run(&mut sim, 200, 500.0);
+
+        assert_eq!(sim.get_stats_spawned(), 200);
+        assert!(sim.get_stats_dropped() > 0);
+        assert_eq!(sim.get_stats_processed() + sim.get_stats_dropped(), 200);
+        assert_eq!(sim.queue_drops().tail, sim.get_stats_dropped());
+        assert_eq!(
+            dropped_events(&mut sim, DropReason::QueueFull),
+            sim.get_stats_dropped()
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_identical_stats() {
+        let mut a = build(42, 2);
+        let mut b = build(42, 2);
+        run(&mut a, 200, 500.0);
+        run(&mut b, 200, 500.0);
+
+        assert_eq!(format!("{:?}", a.stats), format!("{:?}", b.stats));
+        assert_eq!(a.latency_summary().overall, b.latency_summary().overall);
+        assert_eq!(a.get_current_time(), b.get_current_time());
+    }
+
+    #[test]
+    fn reset_replays_the_same_run() {
+        let mut sim = build(7, 2);
+        run(&mut sim, 200, 500.0);
+        let first = format!("{:?}", sim.stats);
+
+        sim.reset();
+        run(&mut sim, 200, 500.0);
+        assert_eq!(format!("{:?}", sim.stats), first);
+    }
+
+    // Gateway -> LB(1) -> Server(2, 3)、Gateway -> LB(4) -> Server(5)、DBにはServer(2)だけが繋がる
+    fn build_two_pools() -> SimulationState {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 300.0, NodeType::LB as u32);
+        sim.add_node(2, 800.0, 200.0, NodeType::Server as u32);
+        sim.add_node(3, 800.0, 400.0, NodeType::Server as u32);
+        sim.add_node(4, 400.0, 780.0, NodeType::LB as u32);
+        sim.add_node(5, 800.0, 780.0, NodeType::Server as u32);
+        sim.add_node(6, 1200.0, 200.0, NodeType::DB as u32);
+        for (from_id, to_id) in [(0, 1), (1, 2), (1, 3), (0, 4), (4, 5), (2, 6)] {
+            assert!(sim.add_link(from_id, to_id));
+        }
+        sim
+    }
+
+    #[test]
+    fn packets_follow_only_the_links() {
+        let mut sim = build_two_pools();
+        run(&mut sim, 60, 1000.0);
+        assert_eq!(sim.get_stats_processed(), 60);
+        assert_eq!(sim.get_stats_dropped(), 0);
+
+        // 途中のノードはリクエストとレスポンスで2回、終端のノードは1回だけ処理する
+        // 各LBは自分のプールにだけ振り分ける
+        let processed: Vec<u32> = sim.nodes.iter().map(|n| n.total_processed).collect();
+        assert!(processed[1] > 0 && processed[4] > 0);
+        assert_eq!(processed[1] + processed[4], 120);
+        assert_eq!(processed[1], processed[2] + processed[3] * 2);
+        assert_eq!(processed[4], processed[5] * 2);
+        // DBに届くのはServer(2)経由だけ
+        assert_eq!(processed[6] * 2, processed[2]);
+    }
+
+    #[test]
+    fn links_can_be_added_and_removed() {
+        let mut sim = build_two_pools();
+        // 存在しないノード・重複するリンクは追加しない
+        assert!(!sim.add_link(0, 99));
+        assert!(!sim.add_link(0, 1));
+        assert!(!sim.remove_link(3, 6));
+        assert_eq!(sim.get_link_count(), 6);
+        assert_eq!(sim.next_hop_candidates(1), vec![2, 3]);
+
+        // LB(4)の先を外すと、そちらには行き先がない
+        assert!(sim.remove_link(4, 5));
+        assert!(sim.next_hop_candidates(4).is_empty());
+
+        // リンクをすべて消すとタイプ順ルーティングに戻る
+        sim.clear_links();
+        assert_eq!(sim.get_link_count(), 0);
+        assert_eq!(sim.next_hop_candidates(0), vec![1, 4]);
+        assert_eq!(sim.next_hop_candidates(4), vec![2, 3, 5]);
+    }
+
+    // Gateway -> Server の最小構成に packet_type のパケットを count 個まとめて流し、処理が終わるまで進める
+    fn run_attack(sim: &mut SimulationState, packet_type: PacketType, count: usize) {
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            count,
+            0.0,
+            0.4,
+            0.0,
+            packet_type as u32,
+            BASE_COMPLEXITY,
+        );
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                return;
+            }
+        }
+        panic!("simulation did not become idle");
+    }
+
+    #[test]
+    fn syn_flood_holds_queue_slots_without_responding() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node_with_spec(1, 800.0, 540.0, NodeType::Server as u32, 1, 80.0, 3, 50);
+        run_attack(&mut sim, PacketType::SynFlood, 5);
+
+        // キュー枠3つを占有し、あふれた2つはドロップ。どれも応答しない
+        assert_eq!(sim.get_stats_attacks_spawned(), 5);
+        assert_eq!(sim.get_stats_attacks_absorbed(), 3);
+        assert_eq!(sim.get_stats_dropped(), 2);
+        assert_eq!(sim.get_stats_processed(), 0);
+        assert!(sim.get_current_time() >= SYN_HOLD_TIME_MS);
+    }
+
+    #[test]
+    fn killer_degrades_the_worker_that_processes_it() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 800.0, 540.0, NodeType::Server as u32);
+        run_attack(&mut sim, PacketType::Killer, 1);
+
+        assert_eq!(sim.get_stats_attacks_absorbed(), 1);
+        assert_eq!(sim.get_stats_processed(), 0);
+        assert_eq!(sim.get_node_health(1), Some(NodeHealth::Degraded));
+        // Gatewayは処理をしないので劣化しない
+        assert_eq!(sim.get_node_health(0), Some(NodeHealth::Healthy));
+
+        for _ in 0..400 {
+            sim.tick(16.67);
+        }
+        assert_eq!(sim.get_node_health(1), Some(NodeHealth::Healthy));
+    }
+
+    // 指定時刻を過ぎるまで10msずつ進める
+    fn advance_to(sim: &mut SimulationState, time_ms: f64) {
+        while sim.get_current_time() < time_ms {
+            sim.tick(FIXED_STEP_MS);
+        }
+    }
+
+    #[test]
+    fn crashed_node_restarts_then_recovers() {
+        let mut sim = build(1, 100);
+        assert!(sim.set_node_health_config(2, 1000.0, 500.0, 300.0, CrashPolicy::Drop as u32));
+        assert!(sim.crash_node(2));
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Down));
+        // 停止中のノードはさらに停止・劣化させられない
+        assert!(!sim.crash_node(2));
+        assert!(!sim.degrade_node(2));
+
+        // Down（500ms） -> Restarting（300ms） -> Healthy
+        advance_to(&mut sim, 490.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Down));
+        advance_to(&mut sim, 510.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Restarting));
+        assert!(!sim.nodes[2].is_available());
+        advance_to(&mut sim, 790.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Restarting));
+        advance_to(&mut sim, 810.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Healthy));
+
+        // 劣化は degraded_time_ms で戻り、その間も受け付ける
+        assert!(sim.degrade_node(2));
+        assert!(sim.nodes[2].is_available());
+        advance_to(&mut sim, 1800.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Degraded));
+        advance_to(&mut sim, 1820.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Healthy));
+    }
+
+    #[test]
+    fn huge_or_non_finite_ticks_run_a_bounded_number_of_steps() {
+        let max_ms = MAX_STEPS_PER_TICK as f64 * FIXED_STEP_MS;
+        let mut sim = build(1, 100);
+        sim.tick(f64::INFINITY);
+        assert_eq!(sim.get_current_time(), 0.0);
+        sim.tick(f64::NAN);
+        assert_eq!(sim.get_current_time(), 0.0);
+
+        // 溜まった経過時間は1回あたり MAX_STEPS_PER_TICK ステップ分までしか進めず、残りは持ち越さない
+        sim.tick(1e9);
+        let after_first = sim.get_current_time();
+        assert!(after_first <= max_ms + 1e-6);
+        assert!(after_first >= max_ms - FIXED_STEP_MS);
+        sim.tick(0.0);
+        assert_eq!(sim.get_current_time(), after_first);
+    }
+
+    #[test]
+    fn long_tick_passes_through_every_recovery_state() {
+        let mut sim = build(1, 100);
+        assert!(sim.set_node_health_config(2, 1000.0, 500.0, 300.0, CrashPolicy::Drop as u32));
+        assert!(sim.crash_node(2));
+        sim.tick(1000.0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Healthy));
+    }
+
+    // Server(id=2) のキューにリクエストが溜まった時点で停止させ、最後まで流す
+    fn crash_with_waiting_requests(policy: CrashPolicy) -> SimulationState {
+        let mut sim = build(1, 100);
+        assert!(sim.set_node_health_config(2, 1000.0, 500.0, 300.0, policy as u32));
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            20,
+            0.0,
+            0.4,
+            0.0,
+            PacketType::Normal as u32,
+            BASE_COMPLEXITY,
+        );
+        while sim.nodes[2].queue.len() < 3 {
+            sim.tick(16.67);
+        }
+        assert!(sim.crash_node(2));
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                return sim;
+            }
+        }
+        panic!("simulation did not become idle");
+    }
+
+    #[test]
+    fn crash_policy_decides_what_happens_to_waiting_requests() {
+        // Drop: 処理中・待機中のリクエストは失われる
+        let mut dropped = crash_with_waiting_requests(CrashPolicy::Drop);
+        let lost = dropped.get_stats_dropped();
+        assert!(lost >= 3);
+        assert_eq!(dropped.nodes[2].total_dropped, lost);
+        assert_eq!(dropped_events(&mut dropped, DropReason::NodeDown), lost);
+        assert_eq!(dropped.get_stats_processed() + lost, 20);
+
+        // Requeue: 直前のLBから稼働中の別のServerへ送り直す
+        let requeued = crash_with_waiting_requests(CrashPolicy::Requeue);
+        assert_eq!(requeued.get_stats_dropped(), 0);
+        assert_eq!(requeued.get_stats_processed(), 20);
+        assert!(requeued.nodes[3].total_processed > requeued.nodes[2].total_processed);
+    }
+
+    #[test]
+    fn killer_crash_lets_same_tick_completions_leave_first() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node_with_spec(1, 800.0, 540.0, NodeType::Server as u32, 5, 80.0, 100, 50);
+        assert!(sim.degrade_node(1));
+        // Killerと通常パケットを同時に流し、Killerと4個を同じtickで処理し終えさせる（残り3個は待機）
+        for (packet_type, count) in [(PacketType::Killer, 1), (PacketType::Normal, 7)] {
+            sim.spawn_wave_to_node(
+                100.0,
+                540.0,
+                0,
+                count,
+                0.0,
+                0.4,
+                0.0,
+                packet_type as u32,
+                BASE_COMPLEXITY,
+            );
+        }
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                break;
+            }
+        }
+
+        assert!(!sim.nodes[1].is_available());
+        assert_eq!(sim.stats.attacks_absorbed, 1);
+        // 同じtickに処理を終えたパケットは停止前に出ていき、待機中のものだけが停止で失われる
+        assert_eq!(sim.get_stats_processed(), 4);
+        assert_eq!(sim.get_stats_dropped(), 3);
+        assert_eq!(sim.nodes[1].total_dropped, 3);
+        assert_eq!(dropped_events(&mut sim, DropReason::NodeDown), 3);
+    }
+
+    #[test]
+    fn killers_in_the_same_tick_degrade_then_crash() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 800.0, 540.0, NodeType::Server as u32);
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            2,
+            0.0,
+            0.4,
+            0.0,
+            PacketType::Killer as u32,
+            BASE_COMPLEXITY,
+        );
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                break;
+            }
+        }
+
+        assert_eq!(sim.stats.attacks_absorbed, 2);
+        assert_eq!(sim.nodes[1].health, NodeHealth::Down);
+    }
+
+    #[test]
+    fn firewall_blocks_attacks_without_counting_them_as_drops() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 540.0, NodeType::Firewall as u32);
+        sim.add_node(2, 800.0, 540.0, NodeType::Server as u32);
+        sim.add_link(0, 1);
+        sim.add_link(1, 2);
+        sim.set_firewall_config(1, 1.0, 0.0, 10_000.0);
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            30,
+            300.0,
+            0.4,
+            0.0,
+            PacketType::Killer as u32,
+            BASE_COMPLEXITY,
+        );
+        run(&mut sim, 20, 300.0);
+
+        // 攻撃はすべて遮断され、通常のリクエストは誤検知なしで通る
+        assert_eq!(sim.get_stats_filtered(), 30);
+        assert_eq!(sim.get_stats_attacks_filtered(), 30);
+        assert_eq!(sim.nodes[1].total_filtered, 30);
+        assert_eq!(sim.get_stats_processed(), 20);
+        assert_eq!(sim.get_stats_dropped(), 0);
+        assert_eq!(sim.get_node_health(2), Some(NodeHealth::Healthy));
+        // droppedには数えないが、遮断したことはイベントに残る
+        assert_eq!(dropped_events(&mut sim, DropReason::Filtered), 30);
+    }
+
+    // Gateway -> Cache -> Server（クライアントキーは1種類、キャッシュ可能な割合100%）
+    fn build_cache(ttl_ms: f64) -> SimulationState {
+        let mut sim = SimulationState::with_seed(100, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 540.0, NodeType::Cache as u32);
+        sim.add_node(2, 800.0, 540.0, NodeType::Server as u32);
+        sim.add_link(0, 1);
+        sim.add_link(1, 2);
+        sim.set_cache_config(1, 1.0, ttl_ms);
+        sim.set_client_count(1);
+        sim
+    }
+
+    fn cache_counts(sim: &SimulationState) -> (u32, u32, u32) {
+        (
+            sim.get_stats_cache_hits(),
+            sim.get_stats_cache_misses(),
+            sim.nodes[2].total_processed,
+        )
+    }
+
+    #[test]
+    fn cache_warms_up_from_passing_responses() {
+        let mut sim = build_cache(10_000.0);
+        // 空のキャッシュはミスし、Serverからのレスポンスがエントリを埋める
+        run(&mut sim, 1, 0.0);
+        assert_eq!(cache_counts(&sim), (0, 1, 1));
+
+        // 温まった後はServerまで行かずにCacheが応答する
+        run(&mut sim, 3, 300.0);
+        assert_eq!(cache_counts(&sim), (3, 1, 1));
+        assert_eq!(sim.get_stats_processed(), 4);
+    }
+
+    #[test]
+    fn requeue_behind_a_cache_does_not_look_up_the_cache_again() {
+        // Gateway -> Cache -> DB x2（キーがばらけるのでほとんどミスする）
+        let mut sim = SimulationState::with_seed(100, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 540.0, NodeType::Cache as u32);
+        sim.add_node_with_spec(2, 800.0, 300.0, NodeType::DB as u32, 1, 200.0, 50, 0);
+        sim.add_node_with_spec(3, 800.0, 780.0, NodeType::DB as u32, 1, 200.0, 50, 0);
+        sim.add_link(0, 1);
+        sim.add_link(1, 2);
+        sim.add_link(1, 3);
+        sim.set_cache_config(1, 1.0, 10_000.0);
+        sim.set_client_count(1000);
+        assert!(sim.set_node_health_config(2, 1000.0, 500.0, 300.0, CrashPolicy::Requeue as u32));
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            20,
+            0.0,
+            0.4,
+            0.0,
+            PacketType::Normal as u32,
+            BASE_COMPLEXITY,
+        );
+        while sim.nodes[2].queue.len() < 3 {
+            sim.tick(16.67);
+        }
+        assert!(sim.crash_node(2));
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                break;
+            }
+        }
+
+        // 送り直したリクエストもCacheを引いたのは最初の1回だけ
+        assert!(sim.is_idle());
+        assert_eq!(sim.get_stats_processed(), 20);
+        assert_eq!(sim.get_stats_cache_hits() + sim.get_stats_cache_misses(), 20);
+        assert_eq!(sim.nodes[1].cache_hits + sim.nodes[1].cache_misses, 20);
+    }
+
+    #[test]
+    fn cache_entries_expire_after_ttl() {
+        let mut sim = build_cache(5000.0);
+        run(&mut sim, 1, 0.0);
+        assert_eq!(cache_counts(&sim), (0, 1, 1));
+
+        // TTLを過ぎたエントリは捨てられ、次のレスポンスで入れ直す
+        for _ in 0..360 {
+            sim.tick(16.67);
+        }
+        run(&mut sim, 1, 0.0);
+        assert_eq!(cache_counts(&sim), (0, 2, 2));
+        run(&mut sim, 1, 0.0);
+        assert_eq!(cache_counts(&sim), (1, 2, 2));
+    }
+
+    #[test]
+    fn flush_empties_the_cache() {
+        let mut sim = build_cache(10_000.0);
+        run(&mut sim, 1, 0.0);
+        run(&mut sim, 1, 0.0);
+        assert_eq!(cache_counts(&sim), (1, 1, 1));
+
+        assert!(sim.flush_cache(1));
+        assert!(!sim.flush_cache(2)); // Cacheでないノード
+        run(&mut sim, 1, 0.0);
+        assert_eq!(cache_counts(&sim), (1, 2, 2));
+    }
+
+    #[test]
+    fn scaled_out_instances_boot_before_taking_requests() {
+        let mut sim = SimulationState::with_seed(100, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        let policy = ScalingPolicy {
+            min_instances: 1,
+            max_instances: 2,
+            scale_out_load: -1.0, // 負荷によらず増やし、減らさない
+            scale_in_load: -1.0,
+            window_ms: 100.0,
+            cooldown_ms: 0.0,
+            boot_delay_ms: 1000.0,
+            cost_per_sec: 1.0,
+        };
+        let group_id = sim.add_autoscaling_group(
+            NodeType::Server as u32,
+            NodeSpec::default_for(NodeType::Server as u32),
+            HealthConfig::default(),
+            policy,
+            800.0,
+            540.0,
+        );
+        sim.add_group_link(group_id, 0, true, LinkProfile::default());
+        let first = sim.autoscaling_groups()[0].instances[0];
+        assert_eq!(sim.get_node_health(first), Some(NodeHealth::Healthy));
+
+        // ウィンドウ分の計測が溜まったところで1台増える
+        while sim.autoscaling_groups()[0].instances.len() < 2 {
+            sim.tick(FIXED_STEP_MS);
+        }
+        let scaled_at = sim.get_current_time();
+        assert!((100.0..100.0 + 2.0 * FIXED_STEP_MS).contains(&scaled_at));
+        let second = sim.autoscaling_groups()[0].instances[1];
+        // 起動中は再起動中と同じく振り分け対象外（リンクは先に張る）
+        assert_eq!(sim.get_node_health(second), Some(NodeHealth::Restarting));
+        assert_eq!(sim.links.iter().filter(|l| l.to_id == second).count(), 1);
+
+        while sim.get_current_time() + FIXED_STEP_MS < scaled_at + 1000.0 {
+            sim.tick(FIXED_STEP_MS);
+        }
+        assert_eq!(sim.get_node_health(second), Some(NodeHealth::Restarting));
+        sim.tick(FIXED_STEP_MS);
+        assert_eq!(sim.get_node_health(second), Some(NodeHealth::Healthy));
+
+        // 課金は起動中も含めた台数 × 時間
+        let expected = (scaled_at + 2.0 * (sim.get_current_time() - scaled_at)) / 1000.0;
+        assert!((sim.get_autoscaling_cost() - expected).abs() < 1e-6);
+    }
+
+    // Gateway -> RateLimiter -> Server（ほぼ補充しない容量2のバケット）
+    fn build_rate_limited(reject: RejectPolicy, per_client: bool) -> SimulationState {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 540.0, NodeType::RateLimiter as u32);
+        sim.add_node(2, 800.0, 540.0, NodeType::Server as u32);
+        sim.add_link(0, 1);
+        sim.add_link(1, 2);
+        sim.set_rate_limit_config(1, 0.001, 2.0, reject as u32, per_client);
+        sim.set_client_count(3);
+        sim
+    }
+
+    #[test]
+    fn per_client_buckets_limit_each_client_separately() {
+        let mut shared = build_rate_limited(RejectPolicy::Drop, false);
+        run(&mut shared, 30, 100.0);
+        assert_eq!(shared.get_stats_processed(), 2);
+        assert_eq!(shared.get_stats_rate_limited(), 28);
+
+        // 3つのクライアントキーがそれぞれ容量2のバケットを持つ
+        let mut per_client = build_rate_limited(RejectPolicy::Drop, true);
+        run(&mut per_client, 30, 100.0);
+        assert_eq!(per_client.get_stats_processed(), 6);
+        assert_eq!(per_client.get_stats_rate_limited(), 24);
+        assert_eq!(per_client.nodes[1].client_buckets.len(), 3);
+    }
+
+    #[test]
+    fn too_many_requests_returns_a_response_without_processing() {
+        let mut sim = build_rate_limited(RejectPolicy::TooManyRequests, false);
+        run(&mut sim, 10, 100.0);
+
+        // 429応答はGatewayまで戻って消え、処理完了にもドロップにも数えない
+        assert_eq!(sim.get_stats_processed(), 2);
+        assert_eq!(sim.get_stats_rate_limited(), 8);
+        assert_eq!(sim.get_stats_dropped(), 0);
+        assert_eq!(sim.nodes[2].total_processed, 2);
+        assert_eq!(sim.get_active_count(), 0);
+        assert_eq!(dropped_events(&mut sim, DropReason::RateLimited), 0);
+        assert_eq!(sim.get_latency_count(), 2);
+    }
+
+    #[test]
+    fn too_many_requests_triggers_a_client_retry() {
+        let mut sim = build_rate_limited(RejectPolicy::TooManyRequests, false);
+        sim.set_retry_policy(60_000.0, 1, 100.0, 2.0, 1000.0, 0.0);
+        run(&mut sim, 10, 100.0);
+
+        // 429を受け取ったクライアントはタイムアウトを待たずに1回ずつ送り直し、それもまた拒否される
+        assert_eq!(sim.get_stats_retried(), 8);
+        assert_eq!(sim.get_stats_rate_limited(), 16);
+        assert_eq!(sim.get_stats_timed_out(), 0);
+        assert_eq!(sim.get_stats_processed(), 2);
+    }
+
+    #[test]
+    fn removing_a_node_drops_its_packets_and_shifts_the_rest() {
+        let mut sim = build(1, 100);
+        let a = sim.packets.alloc().unwrap();
+        let b = sim.packets.alloc().unwrap();
+        sim.packets.start_moving(a, 2, [0.0, 0.0]);
+        sim.packets.start_moving(b, 3, [0.0, 0.0]);
+        sim.packets.push_route(b, 1);
+        assert!(sim.node_in_use(2));
+
+        // ノード2に向かっていたパケットはドロップされ、後ろのノードの参照が1つ前に詰まる
+        assert!(sim.remove_node(2));
+        assert_eq!(sim.get_active_count(), 1);
+        assert_eq!(sim.packets.target_node_idx[b], 2);
+        assert_eq!(sim.packets.node_refs(2), 1);
+        assert_eq!(sim.packets.node_refs(1), 1);
+
+        // 走り終えたら（ドロップを含めて）参照は残らない
+        let mut sim = build(1, 2);
+        run(&mut sim, 200, 500.0);
+        assert!(sim.get_stats_dropped() > 0);
+        assert!((0..4).all(|idx| sim.packets.node_refs(idx) == 0));
+    }
+
+    #[test]
+    fn removing_a_busy_node_drops_everything_it_holds() {
+        let mut sim = build(1, 100);
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            40,
+            100.0,
+            0.4,
+            0.1,
+            PacketType::Normal as u32,
+            BASE_COMPLEXITY,
+        );
+        while sim.nodes[2].queue.is_empty() {
+            sim.tick(16.67);
+        }
+        sim.drain_events();
+        let held = sim.nodes[2].processing_packets.len() + sim.nodes[2].queue.len();
+        let refs = sim.packets.node_refs(2);
+
+        // 処理中・待機中のパケットもまとめてドロップされ、ノード・リンクに使われていないスロットは残らない
+        assert!(sim.remove_node(2));
+        let dropped = sim.get_stats_dropped();
+        assert!(dropped as usize >= held);
+        assert!(dropped <= refs);
+        assert_eq!(dropped_events(&mut sim, DropReason::NodeRemoved), dropped);
+        assert_eq!(sim.validate(), Ok(()));
+
+        for _ in 0..10_000 {
+            sim.tick(16.67);
+            if sim.is_idle() {
+                break;
+            }
+        }
+        assert!(sim.is_idle());
+        assert!((0..3).all(|idx| sim.packets.node_refs(idx) == 0));
+    }
+
+    #[test]
+    fn link_loss_drops_packets_at_the_target_node() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 800.0, 540.0, NodeType::Server as u32);
+        sim.add_link(0, 1);
+        assert!(sim.set_link_profile(0, 1, 20.0, 0.0, 1.0));
+        run(&mut sim, 10, 200.0);
+
+        assert_eq!(sim.get_stats_processed(), 0);
+        assert_eq!(sim.get_stats_dropped(), 10);
+        assert_eq!(dropped_events(&mut sim, DropReason::LinkLoss), 10);
+        assert_eq!(sim.nodes[1].total_dropped, 10);
+        let status = &sim.link_status()[0];
+        assert_eq!((status.sent, status.delivered, status.lost), (10, 0, 10));
+
+        // 損失率0なら全部届き、レスポンスも同じリンクを逆向きに通る
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 800.0, 540.0, NodeType::Server as u32);
+        sim.add_link(0, 1);
+        assert!(sim.set_link_profile(0, 1, 20.0, 0.0, 0.0));
+        run(&mut sim, 10, 200.0);
+
+        assert_eq!(sim.get_stats_processed(), 10);
+        assert_eq!(sim.get_stats_dropped(), 0);
+        let status = &sim.link_status()[0];
+        assert_eq!((status.sent, status.delivered, status.lost), (20, 20, 0));
+    }
+
+    // 原因ごとのドロップイベント数
+    fn dropped_events(sim: &mut SimulationState, reason: DropReason) -> u32 {
+        sim.drain_events()
+            .iter()
+            .filter(
+                |e| matches!(e.kind, SimEventKind::PacketDropped { reason: r, .. } if r == reason),
+            )
+            .count() as u32
+    }
+
+    #[test]
+    fn rate_limited_drops_are_events_but_not_dropped() {
+        let mut sim = SimulationState::with_seed(1000, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node(1, 400.0, 540.0, NodeType::RateLimiter as u32);
+        sim.add_node(2, 800.0, 540.0, NodeType::Server as u32);
+        sim.add_link(0, 1);
+        sim.add_link(1, 2);
+        sim.set_rate_limit_config(1, 5.0, 5.0, RejectPolicy::Drop as u32, false);
+        run(&mut sim, 50, 500.0);
+
+        let rate_limited = sim.get_stats_rate_limited();
+        assert!(rate_limited > 0);
+        assert_eq!(sim.get_stats_dropped(), 0);
+        assert_eq!(sim.get_stats_processed() + rate_limited, 50);
+        assert_eq!(
+            dropped_events(&mut sim, DropReason::RateLimited),
+            rate_limited
+        );
+    }
+
+    #[test]
+    fn node_drop_counts_match_total_drops() {
+        // LBから先のリンクがないので、LBに着いたリクエストは行き先がない
+        let mut sim = build(1, 100);
+        sim.add_link(0, 1);
+        run(&mut sim, 10, 500.0);
+        assert_eq!(dropped_events(&mut sim, DropReason::NoRoute), 10);
+        assert_eq!(sim.nodes[1].total_dropped, 10);
+        assert_eq!(sim.get_stats_dropped(), 10);
+
+        // 原因によらず、ノードに帰属するドロップはノードのドロップ数にも入る
+        let mut sim = build(1, 2);
+        run(&mut sim, 200, 500.0);
+        let node_drops: u32 = sim.nodes.iter().map(|node| node.total_dropped).sum();
+        assert!(node_drops > 0);
+        assert_eq!(node_drops, sim.get_stats_dropped());
+    }
+}
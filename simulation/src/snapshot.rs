@@ -0,0 +1,453 @@
+// =============================================================================
+// SNAPSHOT - シミュレーション状態の保存・復元担当
+// SimulationState とロード済みステージをまるごと書き出し、同じシードなら保存した時点から同じ結果で再開できる
+// 保存形式はヘッダ付きのバイナリ（MessagePack）と、中身を確認するためのJSONの2種類
+// =============================================================================
+
+use crate::simulation::SimulationState;
+use crate::stage::LoadedStage;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// バイナリ形式の先頭に置く識別子
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"PSIM";
+
+// 形式のバージョン（保存する構造を変えたら上げる。違うバージョンは読み込まない）
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+// バイナリ形式のヘッダ長（識別子 + リトルエンディアンのバージョン）
+const HEADER_LEN: usize = SNAPSHOT_MAGIC.len() + 2;
+
+/// 復元したシミュレーション状態
+#[derive(Deserialize)]
+pub struct Snapshot {
+    pub state: SimulationState,
+    pub stage: Option<LoadedStage>, // ステージ未ロードで保存した場合はNone
+}
+
+/// 保存用（状態を借用してそのまま書き出す）
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    state: &'a SimulationState,
+    stage: Option<&'a LoadedStage>,
+}
+
+/// JSON形式（バージョンを中に持つ）
+#[derive(Serialize)]
+struct JsonSnapshotRef<'a> {
+    version: u16,
+    #[serde(flatten)]
+    snapshot: SnapshotRef<'a>,
+}
+
+/// JSON形式のバージョンだけを先に読む
+#[derive(Deserialize)]
+struct JsonHeader {
+    version: u16,
+}
+
+/// 保存・復元のエラー
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    /// 先頭の識別子が違う（スナップショットではない）
+    BadMagic,
+    /// 対応していないバージョン
+    UnsupportedVersion { version: u16 },
+    /// 書き出しに失敗した
+    Encode(String),
+    /// 中身が壊れている・構造が合わない
+    Decode(String),
+    /// 読めたが、パケット・ノードの参照が食い違っている
+    Inconsistent(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a simulation snapshot"),
+            SnapshotError::UnsupportedVersion { version } => write!(
+                f,
+                "unsupported snapshot version {} (expected {})",
+                version, SNAPSHOT_VERSION
+            ),
+            SnapshotError::Encode(msg) => write!(f, "failed to encode snapshot: {}", msg),
+            SnapshotError::Decode(msg) => write!(f, "failed to decode snapshot: {}", msg),
+            SnapshotError::Inconsistent(msg) => write!(f, "inconsistent snapshot: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Snapshot {
+    /// バイナリ形式で書き出す
+    pub fn encode(
+        state: &SimulationState,
+        stage: Option<&LoadedStage>,
+    ) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        bytes.extend_from_slice(&SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        rmp_serde::encode::write(&mut bytes, &SnapshotRef { state, stage })
+            .map_err(|e| SnapshotError::Encode(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// バイナリ形式から復元
+    pub fn decode(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        if bytes.len() < HEADER_LEN || bytes[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion { version });
+        }
+        let snapshot: Snapshot = rmp_serde::from_slice(&bytes[HEADER_LEN..])
+            .map_err(|e| SnapshotError::Decode(e.to_string()))?;
+        snapshot.validate()
+    }
+
+    /// JSON形式で書き出す（デバッグ用。無限大は "inf" / "-inf" の文字列になる）
+    pub fn to_json(
+        state: &SimulationState,
+        stage: Option<&LoadedStage>,
+    ) -> Result<String, SnapshotError> {
+        let json = JsonSnapshotRef {
+            version: SNAPSHOT_VERSION,
+            snapshot: SnapshotRef { state, stage },
+        };
+        serde_json::to_string_pretty(&json).map_err(|e| SnapshotError::Encode(e.to_string()))
+    }
+
+    /// JSON形式から復元
+    pub fn from_json(json: &str) -> Result<Snapshot, SnapshotError> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| SnapshotError::Decode(e.to_string()))?;
+        let header = JsonHeader::deserialize(&value).map_err(|e| SnapshotError::Decode(e.to_string()))?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion { version: header.version });
+        }
+        let snapshot =
+            Snapshot::deserialize(&value).map_err(|e| SnapshotError::Decode(e.to_string()))?;
+        snapshot.validate()
+    }
+
+    /// 読み込んだ状態を次のtickで使っても落ちないか確かめる
+    fn validate(self) -> Result<Snapshot, SnapshotError> {
+        self.state.validate().map_err(SnapshotError::Inconsistent)?;
+        Ok(self)
+    }
+}
+
+/// 無限大になりうる f64 の保存形式（#[serde(with = "crate::snapshot::non_finite")]）
+/// JSONは無限大を表せないため文字列にする。バイナリ形式ではそのまま書く
+pub(crate) mod non_finite {
+    use serde::de::{self, Visitor};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() || value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else if value.is_nan() {
+            serializer.serialize_str("nan")
+        } else if *value > 0.0 {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(NonFiniteVisitor)
+        } else {
+            f64::deserialize(deserializer)
+        }
+    }
+
+    struct NonFiniteVisitor;
+
+    impl Visitor<'_> for NonFiniteVisitor {
+        type Value = f64;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a number or \"inf\" / \"-inf\" / \"nan\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<f64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<f64, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<f64, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<f64, E> {
+            match value {
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                "nan" => Ok(f64::NAN),
+                _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::LatencyReport;
+    use crate::simulation::{NodeType, PacketType};
+    use crate::stage::StageConfig;
+
+    // Gateway -> LB -> Server x2 に混雑するWaveを流し、途中まで進める
+    fn mid_run() -> (SimulationState, LoadedStage) {
+        let config: StageConfig = serde_json::from_str(
+            r#"{
+                "meta": { "title": "Snap", "description": "", "budget": 0, "sla_target": 0.5 },
+                "map": { "fixed_nodes": [
+                    { "id": "gateway", "type": "gateway", "x": 100, "y": 540 },
+                    { "id": "lb", "type": "lb", "x": 400, "y": 540 },
+                    { "id": "s1", "type": "server", "x": 800, "y": 300 },
+                    { "id": "s2", "type": "server", "x": 800, "y": 780 }
+                ] },
+                "waves": [
+                    { "time_start_ms": 0, "source_id": "gateway", "count": 400, "duration_ms": 1000, "packet_type": "NORMAL", "speed": 0.4 },
+                    { "time_start_ms": 1500, "source_id": "gateway", "count": 100, "duration_ms": 500, "packet_type": "HEAVY_TASK", "speed": 0.4 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut sim = SimulationState::with_seed(2000, 5);
+        let mut stage = LoadedStage::new(config);
+        stage.place_fixed_nodes(&mut sim);
+        advance(&mut sim, &mut stage, 900.0);
+        (sim, stage)
+    }
+
+    fn advance(sim: &mut SimulationState, stage: &mut LoadedStage, until_ms: f64) {
+        while sim.get_current_time() < until_ms {
+            stage.tick(sim, 16.67);
+        }
+    }
+
+    // 結果の比較用（レイテンシ分布・処理数・ドロップ数・時刻）
+    fn outcome(sim: &SimulationState) -> (LatencyReport, u32, u32, f64) {
+        (
+            sim.latency_summary().overall,
+            sim.get_stats_processed(),
+            sim.get_stats_dropped(),
+            sim.get_current_time(),
+        )
+    }
+
+    #[test]
+    fn binary_and_json_resume_match_uninterrupted_run() {
+        let (mut sim, mut stage) = mid_run();
+        assert!(sim.get_active_count() > 0);
+        let bytes = Snapshot::encode(&sim, Some(&stage)).unwrap();
+        let json = Snapshot::to_json(&sim, Some(&stage)).unwrap();
+
+        advance(&mut sim, &mut stage, 20_000.0);
+        let expected = (outcome(&sim), stage.evaluate(&sim));
+
+        for restored in [Snapshot::decode(&bytes), Snapshot::from_json(&json)] {
+            let Snapshot { state, stage } = restored.unwrap();
+            let (mut state, mut stage) = (state, stage.unwrap());
+            advance(&mut state, &mut stage, 20_000.0);
+            assert_eq!((outcome(&state), stage.evaluate(&state)), expected);
+        }
+    }
+
+    #[test]
+    fn snapshot_without_stage() {
+        let mut sim = SimulationState::with_seed(100, 1);
+        sim.add_node(0, 100.0, 100.0, NodeType::Gateway as u32);
+        sim.spawn_wave_to_node(
+            100.0,
+            100.0,
+            0,
+            10,
+            0.0,
+            0.3,
+            0.0,
+            PacketType::Normal as u32,
+            10,
+        );
+        sim.tick(16.67);
+        let bytes = Snapshot::encode(&sim, None).unwrap();
+        let restored = Snapshot::decode(&bytes).unwrap();
+        assert!(restored.stage.is_none());
+        assert_eq!(restored.state.get_stats_spawned(), sim.get_stats_spawned());
+    }
+
+    #[test]
+    fn rejects_malformed_binary() {
+        let (sim, stage) = mid_run();
+        let bytes = Snapshot::encode(&sim, Some(&stage)).unwrap();
+
+        assert_eq!(
+            Snapshot::decode(b"PSI").err(),
+            Some(SnapshotError::BadMagic)
+        );
+        assert_eq!(
+            Snapshot::decode(b"NOPE\x03\x00").err(),
+            Some(SnapshotError::BadMagic)
+        );
+
+        let mut old = bytes.clone();
+        old[4..6].copy_from_slice(&(SNAPSHOT_VERSION - 1).to_le_bytes());
+        assert_eq!(
+            Snapshot::decode(&old).err(),
+            Some(SnapshotError::UnsupportedVersion {
+                version: SNAPSHOT_VERSION - 1
+            })
+        );
+
+        let truncated = &bytes[..bytes.len() / 2];
+        assert!(matches!(
+            Snapshot::decode(truncated),
+            Err(SnapshotError::Decode(_))
+        ));
+        assert!(matches!(
+            Snapshot::decode(&bytes[..HEADER_LEN]),
+            Err(SnapshotError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        let (mut sim, mut stage) = mid_run();
+        advance(&mut sim, &mut stage, 20_000.0); // 使ったスロットが解放されるまで進める
+        let json = Snapshot::to_json(&sim, Some(&stage)).unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert!(matches!(
+            Snapshot::from_json("{"),
+            Err(SnapshotError::Decode(_))
+        ));
+        assert!(matches!(
+            Snapshot::from_json("{}"),
+            Err(SnapshotError::Decode(_))
+        ));
+
+        value["version"] = serde_json::json!(SNAPSHOT_VERSION + 1);
+        assert_eq!(
+            Snapshot::from_json(&value.to_string()).err(),
+            Some(SnapshotError::UnsupportedVersion {
+                version: SNAPSHOT_VERSION + 1
+            })
+        );
+
+        // 空きスロットを重複させる（数は合わなくなる前に重複で弾く）
+        value["version"] = serde_json::json!(SNAPSHOT_VERSION);
+        let free_tail = &mut value["state"]["packets"]["free_tail"];
+        assert!(!free_tail.as_array().unwrap().is_empty());
+        let first = free_tail[0].clone();
+        free_tail.as_array_mut().unwrap().push(first);
+        let err = Snapshot::from_json(&value.to_string()).err().unwrap();
+        assert!(
+            err.to_string().contains("invalid free packet slot"),
+            "{}",
+            err
+        );
+
+        // 巨大なスロット数は確保する前に弾く
+        value["state"]["packets"]["capacity"] = serde_json::json!(u64::MAX);
+        let err = Snapshot::from_json(&value.to_string()).err().unwrap();
+        assert!(err.to_string().contains("too many packet slots"), "{}", err);
+    }
+
+    // 処理待ちのキューとリンク上のパケットがある状態（Gateway -> 遅いServer、リンクに伝搬遅延）
+    fn crowded() -> serde_json::Value {
+        let mut sim = SimulationState::with_seed(100, 1);
+        sim.add_node(0, 100.0, 540.0, NodeType::Gateway as u32);
+        sim.add_node_with_spec(1, 800.0, 540.0, NodeType::Server as u32, 1, 500.0, 50, 0);
+        sim.add_link(0, 1);
+        sim.set_link_profile(0, 1, 100.0, 0.0, 0.0);
+        sim.spawn_wave_to_node(
+            100.0,
+            540.0,
+            0,
+            10,
+            1000.0,
+            0.4,
+            0.0,
+            PacketType::Normal as u32,
+            10,
+        );
+        while sim.get_current_time() < 700.0 {
+            sim.tick(16.67);
+        }
+        let json = Snapshot::to_json(&sim, None).unwrap();
+        assert!(Snapshot::from_json(&json).is_ok());
+        serde_json::from_str(&json).unwrap()
+    }
+
+    // 書き換えたJSONが参照の食い違いとして弾かれるか
+    fn assert_inconsistent(value: &serde_json::Value, expected: &str) {
+        match Snapshot::from_json(&value.to_string()) {
+            Err(SnapshotError::Inconsistent(msg)) => assert!(msg.contains(expected), "{}", msg),
+            other => panic!("expected inconsistent snapshot, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn rejects_packets_held_in_unused_slots() {
+        let value = crowded();
+        let server = &value["state"]["nodes"][1];
+        assert!(!server["processing_packets"].as_array().unwrap().is_empty());
+        assert!(!server["queue"]["lanes"][0].as_array().unwrap().is_empty());
+        assert!(!value["state"]["links"][0]["in_flight"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        // 使ったことのないスロット・範囲外を指す
+        for slot in [serde_json::json!(99), serde_json::json!(usize::MAX)] {
+            let mut broken = value.clone();
+            broken["state"]["nodes"][1]["processing_packets"][0]["packet_idx"] = slot.clone();
+            assert_inconsistent(&broken, "node 1 holds unused packet slot");
+
+            let mut broken = value.clone();
+            broken["state"]["nodes"][1]["queue"]["lanes"][0][0]["packet_idx"] = slot.clone();
+            assert_inconsistent(&broken, "node 1 holds unused packet slot");
+
+            let mut broken = value.clone();
+            broken["state"]["links"][0]["in_flight"][0]["packet_idx"] = slot;
+            assert_inconsistent(&broken, "link 0 -> 1 carries unused packet slot");
+        }
+    }
+
+    #[test]
+    fn rejects_packets_pointing_to_missing_nodes() {
+        let value = crowded();
+        let active = value["state"]["packets"]["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .position(|row| row["active"] == 1 && row["route_len"].as_u64().unwrap() > 0)
+            .unwrap();
+
+        for key in ["target_node_idx", "current_node_idx", "origin_server_idx"] {
+            let mut broken = value.clone();
+            broken["state"]["packets"]["rows"][active][key] = serde_json::json!(2);
+            assert_inconsistent(&broken, "missing node");
+        }
+        let mut broken = value.clone();
+        broken["state"]["packets"]["rows"][active]["route"][0] = serde_json::json!(2);
+        assert_inconsistent(&broken, "missing node");
+
+        // 経路長が配列を超えるものは読み込み時に弾く
+        let mut broken = value.clone();
+        broken["state"]["packets"]["rows"][active]["route_len"] = serde_json::json!(9);
+        let err = Snapshot::from_json(&broken.to_string()).err().unwrap();
+        assert!(err.to_string().contains("route longer"), "{}", err);
+    }
+}
@@ -0,0 +1,1179 @@
+// =============================================================================
+// STAGE - ステージ設定の読み込み・ノード配置・Wave発火・結果判定担当
+// wasm依存なしでビルドできるため、ネイティブ実行からも使える
+// =============================================================================
+
+use crate::autoscale::ScalingPolicy;
+use crate::client::RetryPolicy;
+use crate::console::log;
+use crate::events::SimEventKind;
+use crate::link::LinkProfile;
+use crate::load_balancer::LbStrategy;
+use crate::queue::QueuePolicy;
+use crate::simulation::{
+    CacheConfig, FirewallConfig, HealthConfig, NodeSpec, PacketType, RateLimitConfig,
+    SimulationState, BASE_COMPLEXITY,
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+// =============================================================================
+// STAGE CONFIG STRUCTURES - ステージ設定用構造体（Go APIのJSONと対応）
+// =============================================================================
+
+/// ステージ全体の設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageConfig {
+    pub meta: StageMeta,
+    #[serde(default)]
+    pub grades: GradeDefinitions,
+    pub map: MapConfig,
+    pub waves: Vec<WaveConfig>,
+    #[serde(default)]
+    pub client: RetryPolicy, // クライアントのタイムアウト・リトライ（省略時はタイムアウトなし）
+}
+
+/// ステージのメタ情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageMeta {
+    pub title: String,
+    pub description: String,
+    pub budget: u32, // プレイヤーが買うノードとオートスケーリングの課金に使える額（固定ノードは無料で、ここから引かない）
+    pub sla_target: f64,
+}
+
+/// グレード定義
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GradeDefinitions {
+    #[serde(default)]
+    pub lb: HashMap<String, GradeSpec>,
+    #[serde(default)]
+    pub server: HashMap<String, GradeSpec>,
+    #[serde(default)]
+    pub db: HashMap<String, GradeSpec>,
+    #[serde(default)]
+    pub firewall: HashMap<String, GradeSpec>,
+    #[serde(default)]
+    pub cache: HashMap<String, GradeSpec>,
+    #[serde(default)]
+    pub rate_limiter: HashMap<String, GradeSpec>,
+}
+
+/// グレードのスペック
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradeSpec {
+    pub max_concurrent: u32,
+    pub process_time_ms: f64,
+    pub queue_capacity: u32,
+    pub cost: u32,
+    #[serde(default)]
+    pub health: Option<HealthConfig>, // 障害・復旧設定（省略時はデフォルト）
+    #[serde(default)]
+    pub firewall: Option<FirewallConfig>, // 検知設定（Firewallのみ、省略時はデフォルト）
+    #[serde(default)]
+    pub cache: Option<CacheConfig>, // ヒット率・TTL（Cacheのみ、省略時はデフォルト）
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>, // 補充レート・バースト・拒否時の扱い（RateLimiterのみ）
+    #[serde(default)]
+    pub queue: Option<QueuePolicy>, // キュー規律とそのパラメータ（省略時はFIFO）
+}
+
+/// マップ設定（固定ノードなど）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapConfig {
+    pub fixed_nodes: Vec<FixedNodeConfig>,
+    #[serde(default)]
+    pub links: Vec<LinkConfig>, // ノード間リンク（省略時は従来のタイプ順ルーティング）
+    #[serde(default)]
+    pub autoscaling_groups: Vec<AutoscalingGroupConfig>, // オートスケーリンググループ
+}
+
+/// 固定配置されるノード（Gateway等）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixedNodeConfig {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    #[serde(default)]
+    pub grade: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    #[serde(default)]
+    pub lb_strategy: Option<String>, // LBの振り分け戦略（"round_robin" 等、省略時は least_load）
+}
+
+/// オートスケーリンググループ（同じグレードのインスタンスを負荷に応じて増減）
+/// リンクの from / to にグループのidを書くと、全インスタンスにリンクが張られる
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoscalingGroupConfig {
+    pub id: String,
+    #[serde(rename = "type", default = "default_group_node_type")]
+    pub node_type: String, // インスタンスのノードタイプ（省略時は "server"）
+    pub grade: String,
+    pub x: i32, // インスタンスを並べる基準位置
+    pub y: i32,
+    #[serde(flatten)]
+    pub policy: ScalingPolicy,
+}
+
+fn default_group_node_type() -> String {
+    "server".to_string()
+}
+
+/// ノード間リンク（from -> to の向きにリクエストが流れる）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkConfig {
+    pub from: String, // 送信元ノード（またはオートスケーリンググループ）のid
+    pub to: String,   // 宛先ノード（またはオートスケーリンググループ）のid
+    #[serde(flatten)]
+    pub profile: LinkProfile, // 伝送特性（latency_ms / bandwidth / loss_rate、省略時は移動速度で移動）
+}
+
+/// パケット出現パターン（Wave）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveConfig {
+    pub time_start_ms: u32,
+    pub source_id: String,
+    pub count: u32,
+    pub duration_ms: u32,
+    pub packet_type: String,
+    pub speed: f64, // 移動速度（ピクセル/ミリ秒）
+    #[serde(default)]
+    pub complexity: Option<u8>, // 処理の重さ（10 = 通常、省略時はHEAVY_TASKのみ50）
+    #[serde(default)]
+    pub client_id: Option<u32>, // すべて同じクライアントから送る（省略時はクライアントを順番に割り当て）
+}
+
+/// プレイヤーが配置したノードとリンク（ヘッドレス実行用のレイアウトファイル）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutConfig {
+    #[serde(default)]
+    pub nodes: Vec<FixedNodeConfig>,
+    #[serde(default)]
+    pub links: Vec<LinkConfig>,
+}
+
+/// ロード済みステージの状態（Wave管理用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedStage {
+    pub config: StageConfig,
+    pub node_id_map: HashMap<String, u32>, // "gateway" -> ノードID
+    pub group_id_map: HashMap<String, u32>, // "web" -> オートスケーリンググループID
+    pub pending_waves: Vec<WaveConfig>,     // まだ発火していないWave
+    pub player_nodes: Vec<PlacedNode>,      // プレイヤーが配置したノード（固定ノードは含まない）
+    next_node_id: u32,                      // 次に配置するノードのID
+    finished: bool,                         // ステージ終了をイベントにしたか（Waveを再ロードするまで一度だけ）
+}
+
+/// プレイヤーが配置したノード
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacedNode {
+    pub id: u32,
+    pub node_type: String,     // "lb" / "server" / "db" / "firewall" / "cache" / "rate_limiter"
+    pub grade: Option<String>, // グレード名（レイアウトでグレード省略時はNone）
+    pub cost: u32,
+}
+
+/// ノード配置・削除・グレード変更のエラー
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlacementError {
+    /// ステージが未ロード（wasm API用）
+    StageNotLoaded,
+    /// 配置できないノードタイプ（Gateway・未知のタイプ）
+    UnknownNodeType { node_type: String },
+    /// グレード表にないグレード
+    UnknownGrade { node_type: String, grade: String },
+    /// 予算不足（cost: 追加で必要なコスト、remaining: 残り予算）
+    OverBudget { cost: u32, remaining: u32 },
+    /// 指定IDのノードがない
+    NodeNotFound { id: u32 },
+    /// 固定ノードは削除・グレード変更できない
+    FixedNode { id: u32 },
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::StageNotLoaded => write!(f, "stage is not loaded"),
+            PlacementError::UnknownNodeType { node_type } => {
+                write!(f, "node type '{}' cannot be placed", node_type)
+            }
+            PlacementError::UnknownGrade { node_type, grade } => {
+                write!(f, "unknown grade '{}' for {}", grade, node_type)
+            }
+            PlacementError::OverBudget { cost, remaining } => {
+                write!(f, "cost {} exceeds remaining budget {}", cost, remaining)
+            }
+            PlacementError::NodeNotFound { id } => write!(f, "node {} not found", id),
+            PlacementError::FixedNode { id } => write!(f, "node {} is a fixed node", id),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// ステージ結果（JSONでGoサーバーにも送れる）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageResult {
+    pub finished: bool,     // すべてのWaveが発火し、処理中のパケットがなくなったか
+    pub spawned: u32,         // 生成されたパケット数（攻撃を含む）
+    pub attacks_spawned: u32, // うち攻撃パケット数（SynFlood / Killer）
+    pub processed: u32,       // レスポンスがGatewayに戻ったリクエスト数
+    pub dropped: u32,         // ドロップしたパケット数（容量超過・停止など）
+    pub filtered: u32,        // Firewallで遮断したパケット数（誤検知を含む）
+    pub rate_limited: u32,    // RateLimiterで拒否したリクエスト数（429応答を含む）
+    pub timed_out: u32,       // クライアントがタイムアウトした回数（送り直しごと）
+    pub retried: u32,         // クライアントが送り直したリクエスト数（spawnedには含まない）
+    pub success_ratio: f64,   // processed / (spawned - attacks_spawned)
+    pub sla_target: f64,
+    pub autoscaling_cost: u32, // オートスケーリングの課金（インスタンス秒 × 単価）
+    pub total_cost: u32, // プレイヤー配置ノードの合計コスト + オートスケーリングの課金（予算と比べる額）
+    pub fixed_cost: u32, // 固定ノードのグレードの合計コスト（ステージが用意するので予算には含めない）
+    pub budget: u32,
+    pub passed: bool,
+    pub reasons: Vec<FailReason>, // 不合格の理由（合格なら空）
+}
+
+/// 不合格の理由
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailReason {
+    /// まだWaveが残っている、または処理中のパケットがある
+    NotFinished,
+    /// 成功率がSLA目標に届かない
+    SlaMissed { success_ratio: f64, sla_target: f64 },
+    /// プレイヤー配置ノードの合計コストとオートスケーリングの課金が予算を超えている（固定ノードは含まない）
+    OverBudget { total_cost: u32, budget: u32 },
+}
+
+
+// =============================================================================
+// STAGE LOADER - ノード配置とWave発火
+// =============================================================================
+
+/// ノードタイプ名の別名（別名, 正規の名前）
+const NODE_TYPE_ALIASES: [(&str, &str); 2] =
+    [("waf", "firewall"), ("ratelimiter", "rate_limiter")];
+
+/// ノードタイプ文字列を正規の名前（小文字、別名は置き換え）にそろえる
+/// NodeTypeへの変換とグレード表の選択は、どちらもこの名前で引く
+pub fn normalize_node_type(name: &str) -> String {
+    let name = name.to_lowercase();
+    NODE_TYPE_ALIASES
+        .iter()
+        .find(|&&(alias, _)| alias == name)
+        .map_or(name, |&(_, canonical)| canonical.to_string())
+}
+
+/// ノードタイプ文字列をNodeType as u32に変換（不明な場合はGateway）
+pub fn parse_node_type(name: &str) -> u32 {
+    match normalize_node_type(name).as_str() {
+        "gateway" => 0,
+        "lb" => 1,
+        "server" => 2,
+        "db" => 3,
+        "firewall" => 4,
+        "cache" => 5,
+        "rate_limiter" => 6,
+        _ => 0,
+    }
+}
+
+// HEAVY_TASKのWaveで complexity を省略した場合の値（通常の5倍の処理時間）
+const DEFAULT_HEAVY_COMPLEXITY: u8 = 50;
+
+// Waveのパケット速度のばらつき（ピクセル/ミリ秒。60fpsで1フレーム1ピクセル相当）
+const WAVE_SPEED_VARIANCE: f32 = 0.06;
+
+/// グレードに付随する設定（障害・復旧、キュー規律、Firewallの検知、Cacheのヒット率、RateLimiterの制限）をノードに反映
+fn apply_grade_settings(sim: &mut SimulationState, id: u32, spec: &GradeSpec) {
+    sim.apply_health_config(id, spec.health.unwrap_or_default());
+    sim.apply_queue_policy(id, spec.queue.unwrap_or_default());
+    sim.apply_firewall_config(id, spec.firewall.unwrap_or_default());
+    sim.apply_cache_config(id, spec.cache.unwrap_or_default());
+    sim.apply_rate_limit_config(id, spec.rate_limit.unwrap_or_default());
+}
+
+/// パケットタイプ文字列をPacketType as u32に変換（不明な場合はNormal）
+pub fn parse_packet_type(name: &str) -> u32 {
+    match name.to_uppercase().as_str() {
+        "NORMAL" => 0,
+        "SYN_FLOOD" | "SYNFLOOD" => 1,
+        "HEAVY_TASK" | "HEAVYTASK" => 2,
+        "KILLER" => 3,
+        _ => 0,
+    }
+}
+
+impl LoadedStage {
+    /// ステージ設定からLoadedStageを作成（ノードはまだ配置しない）
+    pub fn new(config: StageConfig) -> LoadedStage {
+        let pending_waves = config.waves.clone();
+        let mut stage = LoadedStage {
+            config,
+            node_id_map: HashMap::new(),
+            group_id_map: HashMap::new(),
+            pending_waves,
+            player_nodes: Vec::new(),
+            next_node_id: 0,
+            finished: false,
+        };
+        stage.reset_fixed_node_ids();
+        stage
+    }
+
+    /// 固定ノードのIDマップに戻す（固定ノードのIDは定義順の連番）
+    fn reset_fixed_node_ids(&mut self) {
+        self.node_id_map = self
+            .config
+            .map
+            .fixed_nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| (node.id.clone(), idx as u32))
+            .collect();
+        // グループIDも定義順の連番
+        self.group_id_map = self
+            .config
+            .map
+            .autoscaling_groups
+            .iter()
+            .enumerate()
+            .map(|(idx, group)| (group.id.clone(), idx as u32))
+            .collect();
+        self.player_nodes.clear();
+        self.next_node_id = self.config.map.fixed_nodes.len() as u32;
+    }
+
+    /// シミュレーションのノードをクリアし、固定ノードとリンクを配置
+    /// プレイヤーが配置したノードも取り除かれる
+    pub fn place_fixed_nodes(&mut self, sim: &mut SimulationState) {
+        sim.clear_nodes();
+        self.reset_fixed_node_ids();
+
+        for (idx, node) in self.config.map.fixed_nodes.iter().enumerate() {
+            self.add_configured_node(sim, idx as u32, node);
+            let grade_info = node.grade.as_deref().unwrap_or("default");
+            log(&format!(
+                "[Rust/Wasm] Fixed node added: id={}, type={}, grade={}, pos=({}, {})",
+                node.id, node.node_type, grade_info, node.x, node.y
+            ));
+        }
+
+        for group in &self.config.map.autoscaling_groups {
+            self.add_configured_group(sim, group);
+        }
+
+        self.add_configured_links(sim, &self.config.map.links);
+        sim.apply_retry_policy(self.config.client);
+
+        log(&format!(
+            "[Rust/Wasm] Stage loaded: {} fixed nodes, {} autoscaling groups, {} links, {} waves",
+            self.config.map.fixed_nodes.len(),
+            self.config.map.autoscaling_groups.len(),
+            self.config.map.links.len(),
+            self.pending_waves.len()
+        ));
+    }
+
+    /// レイアウト（プレイヤー配置分）のノードとリンクを追加
+    /// ノードIDは配置済みノードの後ろに連番で割り当てる
+    /// 予算は超えても配置し、結果判定（evaluate）で不合格にする
+    pub fn apply_layout(&mut self, sim: &mut SimulationState, layout: &LayoutConfig) {
+        for node in &layout.nodes {
+            let id = self.allocate_node_id(sim);
+            self.add_configured_node(sim, id, node);
+            self.node_id_map.insert(node.id.clone(), id);
+
+            let cost = sim
+                .get_node_index(id)
+                .and_then(|idx| sim.get_node_spec_by_index(idx))
+                .map_or(0, |spec| spec.cost);
+            self.player_nodes.push(PlacedNode {
+                id,
+                node_type: normalize_node_type(&node.node_type),
+                grade: node.grade.clone(),
+                cost,
+            });
+        }
+        self.add_configured_links(sim, &layout.links);
+    }
+
+    /// プレイヤーが配置したノードの合計コスト
+    pub fn player_cost(&self) -> u32 {
+        self.player_nodes.iter().map(|n| n.cost).sum()
+    }
+
+    /// 固定ノードの合計コスト（グレードのないノードは0）
+    pub fn fixed_cost(&self) -> u32 {
+        self.config
+            .map
+            .fixed_nodes
+            .iter()
+            .filter_map(|node| {
+                let grade = node.grade.as_ref()?;
+                self.grade_table(&normalize_node_type(&node.node_type))?
+                    .get(grade)
+            })
+            .map(|spec| spec.cost)
+            .sum()
+    }
+
+    /// オートスケーリングの課金（インスタンス秒、端数切り上げ）
+    pub fn autoscaling_cost(&self, sim: &SimulationState) -> u32 {
+        sim.get_autoscaling_cost().ceil() as u32
+    }
+
+    /// 次に配置するノードのID（オートスケールのインスタンスと重ならないようにする）
+    fn allocate_node_id(&mut self, sim: &SimulationState) -> u32 {
+        let id = self.next_node_id.max(sim.next_free_node_id());
+        self.next_node_id = id + 1;
+        id
+    }
+
+    /// 残り予算（配置したノードのコストとオートスケーリングの課金を引いた額）
+    pub fn remaining_budget(&self, sim: &SimulationState) -> u32 {
+        self.config
+            .meta
+            .budget
+            .saturating_sub(self.player_cost() + self.autoscaling_cost(sim))
+    }
+
+    /// ノードタイプのグレード表（node_type は normalize_node_type 済み、配置できないタイプはNone）
+    fn grade_table(&self, node_type: &str) -> Option<&HashMap<String, GradeSpec>> {
+        match node_type {
+            "lb" => Some(&self.config.grades.lb),
+            "server" => Some(&self.config.grades.server),
+            "db" => Some(&self.config.grades.db),
+            "firewall" => Some(&self.config.grades.firewall),
+            "cache" => Some(&self.config.grades.cache),
+            "rate_limiter" => Some(&self.config.grades.rate_limiter),
+            _ => None,
+        }
+    }
+
+    /// グレードのスペックを検索
+    fn find_grade(&self, node_type: &str, grade: &str) -> Result<&GradeSpec, PlacementError> {
+        let table = self
+            .grade_table(node_type)
+            .ok_or_else(|| PlacementError::UnknownNodeType {
+                node_type: node_type.to_string(),
+            })?;
+        table
+            .get(grade)
+            .ok_or_else(|| PlacementError::UnknownGrade {
+                node_type: node_type.to_string(),
+                grade: grade.to_string(),
+            })
+    }
+
+    /// プレイヤーがノードを購入して配置し、ノードIDを返す
+    /// node_type: "lb" / "server" / "db" / "firewall"（"waf"）/ "cache" / "rate_limiter"（"ratelimiter"）
+    /// （大文字小文字は問わない）、grade: グレード表のキー
+    pub fn place_node(
+        &mut self,
+        sim: &mut SimulationState,
+        node_type: &str,
+        grade: &str,
+        x: f32,
+        y: f32,
+    ) -> Result<u32, PlacementError> {
+        let node_type = normalize_node_type(node_type);
+        let spec = self.find_grade(&node_type, grade)?.clone();
+
+        let remaining = self.remaining_budget(sim);
+        if spec.cost > remaining {
+            return Err(PlacementError::OverBudget {
+                cost: spec.cost,
+                remaining,
+            });
+        }
+
+        let id = self.allocate_node_id(sim);
+        sim.add_node_with_spec(
+            id,
+            x,
+            y,
+            parse_node_type(&node_type),
+            spec.max_concurrent,
+            spec.process_time_ms,
+            spec.queue_capacity,
+            spec.cost,
+        );
+        apply_grade_settings(sim, id, &spec);
+        self.player_nodes.push(PlacedNode {
+            id,
+            node_type,
+            grade: Some(grade.to_string()),
+            cost: spec.cost,
+        });
+
+        log(&format!(
+            "[Rust/Wasm] Node placed: id={}, grade={}, cost={}, remaining budget={}",
+            id,
+            grade,
+            spec.cost,
+            self.remaining_budget(sim)
+        ));
+        Ok(id)
+    }
+
+    /// プレイヤーが配置したノードを削除し、払い戻したコストを返す
+    pub fn remove_node(
+        &mut self,
+        sim: &mut SimulationState,
+        id: u32,
+    ) -> Result<u32, PlacementError> {
+        let pos = self.player_node_position(sim, id)?;
+        let removed = self.player_nodes.remove(pos);
+        sim.remove_node(id);
+        self.node_id_map.retain(|_, node_id| *node_id != id);
+
+        log(&format!(
+            "[Rust/Wasm] Node sold: id={}, refund={}, remaining budget={}",
+            id,
+            removed.cost,
+            self.remaining_budget(sim)
+        ));
+        Ok(removed.cost)
+    }
+
+    /// プレイヤーが配置したノードのグレードを変更し、変更後のコストを返す
+    /// 差額を予算から支払う（ダウングレードなら差額が戻る）
+    pub fn upgrade_node(
+        &mut self,
+        sim: &mut SimulationState,
+        id: u32,
+        grade: &str,
+    ) -> Result<u32, PlacementError> {
+        let pos = self.player_node_position(sim, id)?;
+        let node_type = self.player_nodes[pos].node_type.clone();
+        let spec = self.find_grade(&node_type, grade)?.clone();
+
+        let old_cost = self.player_nodes[pos].cost;
+        let remaining = self.remaining_budget(sim) + old_cost;
+        if spec.cost > remaining {
+            return Err(PlacementError::OverBudget {
+                cost: spec.cost - old_cost,
+                remaining: self.remaining_budget(sim),
+            });
+        }
+
+        sim.set_node_spec(
+            id,
+            spec.max_concurrent,
+            spec.process_time_ms,
+            spec.queue_capacity,
+            spec.cost,
+        );
+        apply_grade_settings(sim, id, &spec);
+        let placed = &mut self.player_nodes[pos];
+        placed.grade = Some(grade.to_string());
+        placed.cost = spec.cost;
+
+        log(&format!(
+            "[Rust/Wasm] Node upgraded: id={}, grade={}, cost {} -> {}, remaining budget={}",
+            id,
+            grade,
+            old_cost,
+            spec.cost,
+            self.remaining_budget(sim)
+        ));
+        Ok(spec.cost)
+    }
+
+    /// プレイヤーが配置したノードの位置（player_nodes内）を取得
+    fn player_node_position(
+        &self,
+        sim: &SimulationState,
+        id: u32,
+    ) -> Result<usize, PlacementError> {
+        if let Some(pos) = self.player_nodes.iter().position(|n| n.id == id) {
+            return Ok(pos);
+        }
+        if sim.get_node_index(id).is_some() {
+            Err(PlacementError::FixedNode { id })
+        } else {
+            Err(PlacementError::NodeNotFound { id })
+        }
+    }
+
+    /// 設定に従ってノードを1つ追加（グレードがあればそのスペックを使う）
+    fn add_configured_node(&self, sim: &mut SimulationState, id: u32, node: &FixedNodeConfig) {
+        let node_type_str = normalize_node_type(&node.node_type);
+        let node_type = parse_node_type(&node_type_str);
+
+        // グレードからスペックを取得
+        let grade_spec = node
+            .grade
+            .as_ref()
+            .and_then(|grade_name| self.grade_table(&node_type_str)?.get(grade_name));
+
+        if let Some(spec) = grade_spec {
+            // グレードスペック付きでノードを追加
+            sim.add_node_with_spec(
+                id,
+                node.x as f32,
+                node.y as f32,
+                node_type,
+                spec.max_concurrent,
+                spec.process_time_ms,
+                spec.queue_capacity,
+                spec.cost,
+            );
+            apply_grade_settings(sim, id, spec);
+        } else {
+            // デフォルトスペックでノードを追加
+            sim.add_node(id, node.x as f32, node.y as f32, node_type);
+        }
+
+        // LBの振り分け戦略を設定
+        if let Some(name) = &node.lb_strategy {
+            match LbStrategy::from_name(name) {
+                Some(strategy) => {
+                    sim.set_lb_strategy(id, strategy as u32);
+                }
+                None => log(&format!(
+                    "[Rust/Wasm] Warning: unknown lb_strategy '{}' for node {}",
+                    name, node.id
+                )),
+            }
+        }
+    }
+
+    /// オートスケーリンググループを配置（インスタンスのスペックはグレードから取得）
+    fn add_configured_group(&self, sim: &mut SimulationState, group: &AutoscalingGroupConfig) {
+        let node_type_str = normalize_node_type(&group.node_type);
+        let Ok(grade) = self.find_grade(&node_type_str, &group.grade) else {
+            log(&format!(
+                "[Rust/Wasm] Warning: autoscaling group {} refers to an unknown grade {}/{}",
+                group.id, node_type_str, group.grade
+            ));
+            return;
+        };
+        let node_type = parse_node_type(&node_type_str);
+        let spec = NodeSpec {
+            max_concurrent: grade.max_concurrent,
+            process_time_ms: grade.process_time_ms,
+            queue_capacity: grade.queue_capacity,
+            cost: grade.cost,
+            bandwidth_factor: NodeSpec::default_for(node_type).bandwidth_factor,
+        };
+        let group_id = sim.add_autoscaling_group(
+            node_type,
+            spec,
+            grade.health.unwrap_or_default(),
+            group.policy,
+            group.x as f32,
+            group.y as f32,
+        );
+        sim.apply_group_queue_policy(group_id, grade.queue.unwrap_or_default());
+    }
+
+    /// リンクを配置（ノード・グループのidをIDに解決）
+    fn add_configured_links(&self, sim: &mut SimulationState, links: &[LinkConfig]) {
+        for link in links {
+            let from_group = self.group_id_map.get(&link.from);
+            let to_group = self.group_id_map.get(&link.to);
+            match (
+                self.node_id_map.get(&link.from),
+                self.node_id_map.get(&link.to),
+                from_group,
+                to_group,
+            ) {
+                (Some(&from_id), Some(&to_id), _, _) => {
+                    sim.add_link_with_profile(from_id, to_id, link.profile);
+                }
+                (Some(&from_id), None, _, Some(&group_id)) => {
+                    sim.add_group_link(group_id, from_id, true, link.profile);
+                }
+                (None, Some(&to_id), Some(&group_id), _) => {
+                    sim.add_group_link(group_id, to_id, false, link.profile);
+                }
+                _ => {
+                    log(&format!(
+                        "[Rust/Wasm] Warning: link {} -> {} refers to an unknown node id",
+                        link.from, link.to
+                    ));
+                }
+            }
+        }
+    }
+
+    /// 指定した時刻までのWaveを発火させる
+    /// ソースノード自身をターゲットにし、そこからトポロジーに沿って転送する
+    pub fn trigger_waves_until(&mut self, sim: &mut SimulationState, current_time_ms: u32) {
+        let (to_trigger, remaining): (Vec<WaveConfig>, Vec<WaveConfig>) = self
+            .pending_waves
+            .drain(..)
+            .partition(|wave| wave.time_start_ms <= current_time_ms);
+        self.pending_waves = remaining;
+
+        for wave in to_trigger {
+            // source_idからノードインデックスを解決
+            let Some(idx) = self
+                .node_id_map
+                .get(&wave.source_id)
+                .and_then(|&id| sim.get_node_index(id))
+            else {
+                log(&format!(
+                    "[Rust/Wasm] Warning: source_id '{}' not found in node_id_map",
+                    wave.source_id
+                ));
+                continue;
+            };
+
+            // ソースノードの位置を取得
+            let Some((x, y)) = sim.get_node_position_by_index(idx) else {
+                continue;
+            };
+
+            let packet_type = parse_packet_type(&wave.packet_type);
+            let complexity = wave.complexity.unwrap_or(
+                if packet_type == PacketType::HeavyTask as u32 {
+                    DEFAULT_HEAVY_COMPLEXITY
+                } else {
+                    BASE_COMPLEXITY
+                },
+            );
+
+            let wave_id = sim.spawn_wave_to_node(
+                x,
+                y,
+                idx as i32,
+                wave.count as usize,
+                wave.duration_ms as f64,
+                wave.speed as f32,
+                WAVE_SPEED_VARIANCE,
+                packet_type,
+                complexity,
+            );
+            if let Some(client_id) = wave.client_id {
+                sim.set_wave_client(wave_id, client_id);
+            }
+
+            log(&format!(
+                "[Rust/Wasm] Wave triggered: {} packets from {} at t={}ms",
+                wave.count, wave.source_id, wave.time_start_ms
+            ));
+        }
+
+        self.check_finished(sim);
+    }
+
+    /// すべてのWaveが発火し、処理中のものがなくなったか
+    /// 初めて終わったときにステージ終了のイベント（合否付き）を記録する
+    pub fn check_finished(&mut self, sim: &mut SimulationState) -> bool {
+        let finished = self.all_waves_triggered() && sim.is_idle();
+        if finished && !self.finished {
+            self.finished = true;
+            let passed = self.evaluate(sim).passed;
+            sim.push_event(SimEventKind::StageFinished { passed });
+            log(&format!(
+                "[Rust/Wasm] Stage finished at t={}ms (passed={})",
+                sim.get_current_time(),
+                passed
+            ));
+        }
+        finished
+    }
+
+    /// シミュレーションを進め、終わったか（check_finished）を返す
+    /// Waveは固定ステップごとに発火させるので、フレームの長さによらず同じ時刻に始まる
+    pub fn tick(&mut self, sim: &mut SimulationState, delta_ms: f64) -> bool {
+        sim.tick_with(delta_ms, |sim| {
+            let now_ms = sim.get_current_time() as u32;
+            self.trigger_waves_until(sim, now_ms);
+        });
+        self.check_finished(sim)
+    }
+
+    /// Waveを再ロード（すべて未発火に戻す）
+    pub fn reset_waves(&mut self) {
+        self.pending_waves = self.config.waves.clone();
+        self.finished = false;
+        log(&format!(
+            "[Rust/Wasm] Stage waves reset: {} waves pending",
+            self.pending_waves.len()
+        ));
+    }
+
+    /// すべてのWaveが発火済みか
+    pub fn all_waves_triggered(&self) -> bool {
+        self.pending_waves.is_empty()
+    }
+
+    /// 現在のシミュレーション状態からステージ結果を判定
+    /// 合格条件: 終了済み && 成功率 >= sla_target && 合計コスト <= budget
+    /// 攻撃パケットは応答しないのが正常なので、成功率の分母に含めない
+    pub fn evaluate(&self, sim: &SimulationState) -> StageResult {
+        let meta = &self.config.meta;
+        let spawned = sim.get_stats_spawned();
+        let attacks_spawned = sim.get_stats_attacks_spawned();
+        let processed = sim.get_stats_processed();
+        let legit_spawned = spawned.saturating_sub(attacks_spawned);
+        let success_ratio = if legit_spawned > 0 {
+            processed as f64 / legit_spawned as f64
+        } else if attacks_spawned > 0 {
+            1.0 // 攻撃だけのステージは守りきれば成功
+        } else {
+            0.0
+        };
+        let finished = self.all_waves_triggered() && sim.is_idle();
+
+        let mut reasons = Vec::new();
+        if !finished {
+            reasons.push(FailReason::NotFinished);
+        }
+        if success_ratio < meta.sla_target {
+            reasons.push(FailReason::SlaMissed {
+                success_ratio,
+                sla_target: meta.sla_target,
+            });
+        }
+        let autoscaling_cost = self.autoscaling_cost(sim);
+        let total_cost = self.player_cost() + autoscaling_cost;
+        if total_cost > meta.budget {
+            reasons.push(FailReason::OverBudget {
+                total_cost,
+                budget: meta.budget,
+            });
+        }
+
+        StageResult {
+            finished,
+            spawned,
+            attacks_spawned,
+            processed,
+            dropped: sim.get_stats_dropped(),
+            filtered: sim.get_stats_filtered(),
+            rate_limited: sim.get_stats_rate_limited(),
+            timed_out: sim.get_stats_timed_out(),
+            retried: sim.get_stats_retried(),
+            success_ratio,
+            sla_target: meta.sla_target,
+            autoscaling_cost,
+            total_cost,
+            fixed_cost: self.fixed_cost(),
+            budget: meta.budget,
+            passed: reasons.is_empty(),
+            reasons,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BlueBull相当（Gateway -> LB -> Server x3 -> DB、Wave 2つ）
+    const STAGE_JSON: &str = r#"{
+        "meta": { "title": "Test", "description": "", "budget": 500, "sla_target": 0.99 },
+        "grades": {
+            "lb": { "standard": { "max_concurrent": 100, "process_time_ms": 10, "queue_capacity": 500, "cost": 100 } },
+            "server": {
+                "small": { "max_concurrent": 5, "process_time_ms": 100, "queue_capacity": 10, "cost": 50 },
+                "medium": { "max_concurrent": 20, "process_time_ms": 50, "queue_capacity": 50, "cost": 150 },
+                "large": { "max_concurrent": 100, "process_time_ms": 20, "queue_capacity": 200, "cost": 400 }
+            },
+            "db": { "standard": { "max_concurrent": 10, "process_time_ms": 30, "queue_capacity": 100, "cost": 200 } }
+        },
+        "map": {
+            "fixed_nodes": [
+                { "id": "gateway", "type": "gateway", "x": 150, "y": 540 },
+                { "id": "lb", "type": "lb", "grade": "standard", "x": 500, "y": 540 },
+                { "id": "server1", "type": "server", "grade": "medium", "x": 900, "y": 300 },
+                { "id": "server2", "type": "server", "grade": "medium", "x": 900, "y": 540 },
+                { "id": "server3", "type": "server", "grade": "small", "x": 900, "y": 780 },
+                { "id": "db", "type": "db", "grade": "standard", "x": 1300, "y": 540 }
+            ]
+        },
+        "waves": [
+            { "time_start_ms": 100, "source_id": "gateway", "count": 50, "duration_ms": 2000, "packet_type": "NORMAL", "speed": 0.3 },
+            { "time_start_ms": 3000, "source_id": "gateway", "count": 200, "duration_ms": 1000, "packet_type": "NORMAL", "speed": 0.36 }
+        ]
+    }"#;
+
+    fn config() -> StageConfig {
+        serde_json::from_str(STAGE_JSON).unwrap()
+    }
+
+    // ヘッドレスランナーと同じく、終わるまでdt_msずつ進める
+    fn run(
+        config: StageConfig,
+        layout: Option<&LayoutConfig>,
+        seed: u64,
+    ) -> (SimulationState, LoadedStage) {
+        run_with_dt(config, layout, seed, 16.67)
+    }
+
+    fn run_with_dt(
+        config: StageConfig,
+        layout: Option<&LayoutConfig>,
+        seed: u64,
+        dt_ms: f64,
+    ) -> (SimulationState, LoadedStage) {
+        let mut sim = SimulationState::with_seed(10_000, seed);
+        let mut stage = LoadedStage::new(config);
+        stage.place_fixed_nodes(&mut sim);
+        if let Some(layout) = layout {
+            stage.apply_layout(&mut sim, layout);
+        }
+        while sim.get_current_time() < 600_000.0 {
+            if stage.tick(&mut sim, dt_ms) {
+                break;
+            }
+        }
+        (sim, stage)
+    }
+
+    #[test]
+    fn seeded_stage_passes_headless() {
+        let (mut sim, stage) = run(config(), None, 1);
+        let result = stage.evaluate(&sim);
+
+        assert!(result.finished);
+        assert_eq!(result.spawned, 250);
+        assert_eq!(result.processed, 250);
+        assert_eq!(result.dropped, 0);
+        assert_eq!(result.success_ratio, 1.0);
+        // 固定ノード（LB・Server・DBのグレードにはコストがある）は予算に数えず、別に報告する
+        assert_eq!(result.total_cost, 0);
+        assert_eq!(result.fixed_cost, 650);
+        assert!(result.passed);
+        assert!(result.reasons.is_empty());
+
+        let finished: Vec<_> = sim
+            .drain_events()
+            .into_iter()
+            .filter(|e| matches!(e.kind, SimEventKind::StageFinished { .. }))
+            .collect();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(
+            finished[0].kind,
+            SimEventKind::StageFinished { passed: true }
+        );
+    }
+
+    #[test]
+    fn overloaded_stage_misses_sla() {
+        let mut config = config();
+        config.waves[1].count = 1000;
+        let (sim, stage) = run(config, None, 1);
+        let result = stage.evaluate(&sim);
+
+        assert!(result.finished);
+        assert!(result.dropped > 0);
+        assert_eq!(result.processed + result.dropped, result.spawned);
+        assert!(!result.passed);
+        assert!(matches!(result.reasons[..], [FailReason::SlaMissed { .. }]));
+    }
+
+    #[test]
+    fn same_seed_gives_identical_result() {
+        let mut config = config();
+        config.waves[1].count = 1000;
+        let (sim_a, stage_a) = run(config.clone(), None, 9);
+        let (sim_b, stage_b) = run(config, None, 9);
+
+        assert_eq!(stage_a.evaluate(&sim_a), stage_b.evaluate(&sim_b));
+        assert_eq!(
+            sim_a.latency_summary().overall,
+            sim_b.latency_summary().overall
+        );
+        assert_eq!(sim_a.get_current_time(), sim_b.get_current_time());
+    }
+
+    #[test]
+    fn over_budget_layout_fails() {
+        let layout: LayoutConfig = serde_json::from_str(
+            r#"{ "nodes": [
+                { "id": "big1", "type": "server", "grade": "large", "x": 900, "y": 100 },
+                { "id": "big2", "type": "server", "grade": "large", "x": 900, "y": 980 }
+            ] }"#,
+        )
+        .unwrap();
+        let (sim, stage) = run(config(), Some(&layout), 1);
+        let result = stage.evaluate(&sim);
+
+        assert!(result.finished);
+        assert_eq!(result.total_cost, 800);
+        assert_eq!(result.fixed_cost, 650);
+        assert!(!result.passed);
+        assert_eq!(
+            result.reasons,
+            vec![FailReason::OverBudget {
+                total_cost: 800,
+                budget: 500
+            }]
+        );
+    }
+
+    #[test]
+    fn place_node_checks_grade_and_budget() {
+        let mut sim = SimulationState::with_seed(100, 1);
+        let mut stage = LoadedStage::new(config());
+        stage.place_fixed_nodes(&mut sim);
+
+        assert_eq!(
+            stage.place_node(&mut sim, "server", "huge", 0.0, 0.0),
+            Err(PlacementError::UnknownGrade {
+                node_type: "server".to_string(),
+                grade: "huge".to_string()
+            })
+        );
+        let id = stage
+            .place_node(&mut sim, "server", "large", 0.0, 0.0)
+            .unwrap();
+        assert_eq!(id, 6);
+        assert_eq!(stage.remaining_budget(&sim), 100);
+        assert_eq!(
+            stage.place_node(&mut sim, "server", "medium", 0.0, 0.0),
+            Err(PlacementError::OverBudget {
+                cost: 150,
+                remaining: 100
+            })
+        );
+        assert_eq!(stage.remove_node(&mut sim, id), Ok(400));
+        assert_eq!(stage.remaining_budget(&sim), 500);
+    }
+
+    #[test]
+    fn upgrade_pays_the_cost_difference_and_remove_refunds() {
+        let mut sim = SimulationState::with_seed(100, 1);
+        let mut stage = LoadedStage::new(config());
+        stage.place_fixed_nodes(&mut sim);
+
+        let server = stage
+            .place_node(&mut sim, "server", "small", 0.0, 0.0)
+            .unwrap();
+        stage
+            .place_node(&mut sim, "lb", "standard", 0.0, 0.0)
+            .unwrap();
+        assert_eq!(stage.remaining_budget(&sim), 350);
+
+        // small(50) -> large(400) は差額350をちょうど払える
+        assert_eq!(stage.upgrade_node(&mut sim, server, "large"), Ok(400));
+        assert_eq!(stage.remaining_budget(&sim), 0);
+        let idx = sim.get_node_index(server).unwrap();
+        assert_eq!(sim.get_node_spec_by_index(idx).unwrap().max_concurrent, 100);
+
+        // ダウングレードは差額が戻る
+        assert_eq!(stage.upgrade_node(&mut sim, server, "medium"), Ok(150));
+        assert_eq!(stage.remaining_budget(&sim), 250);
+
+        // 差額が残り予算を超えるアップグレードは断り、何も変えない
+        let second = stage
+            .place_node(&mut sim, "server", "medium", 0.0, 0.0)
+            .unwrap();
+        assert_eq!(stage.remaining_budget(&sim), 100);
+        assert_eq!(
+            stage.upgrade_node(&mut sim, second, "large"),
+            Err(PlacementError::OverBudget {
+                cost: 250,
+                remaining: 100
+            })
+        );
+        assert_eq!(stage.remaining_budget(&sim), 100);
+        let idx = sim.get_node_index(second).unwrap();
+        assert_eq!(sim.get_node_spec_by_index(idx).unwrap().cost, 150);
+
+        // 売ると今のグレードのコストが戻る
+        assert_eq!(stage.remove_node(&mut sim, server), Ok(150));
+        assert_eq!(stage.remaining_budget(&sim), 250);
+        assert_eq!(
+            stage.remove_node(&mut sim, 0),
+            Err(PlacementError::FixedNode { id: 0 })
+        );
+    }
+
+    #[test]
+    fn map_links_replace_type_order_routing() {
+        // server1 だけがLBとDBに繋がり、server2・server3には何も届かない
+        let mut config = config();
+        config.map.links = [("gateway", "lb"), ("lb", "server1"), ("server1", "db")]
+            .iter()
+            .map(|&(from, to)| LinkConfig {
+                from: from.to_string(),
+                to: to.to_string(),
+                profile: LinkProfile::default(),
+            })
+            .collect();
+        config.waves[1].count = 20;
+        let (sim, stage) = run(config, None, 1);
+        let result = stage.evaluate(&sim);
+
+        assert_eq!(result.processed, 70);
+        assert_eq!(result.dropped, 0);
+        // リクエストとレスポンスがどのリンクも1回ずつ通る
+        let sent: Vec<u32> = sim.link_status().iter().map(|l| l.sent).collect();
+        assert_eq!(sent, vec![140, 140, 140]);
+    }
+
+    #[test]
+    fn node_type_aliases_work_for_fixed_and_placed_nodes() {
+        let mut config = config();
+        let basic: GradeSpec = serde_json::from_str(
+            r#"{ "max_concurrent": 50, "process_time_ms": 5, "queue_capacity": 50, "cost": 60 }"#,
+        )
+        .unwrap();
+        config
+            .grades
+            .firewall
+            .insert("basic".to_string(), basic.clone());
+        config
+            .grades
+            .rate_limiter
+            .insert("basic".to_string(), basic);
+        config.map.fixed_nodes[0].node_type = "WAF".to_string();
+        config.map.fixed_nodes[0].grade = Some("basic".to_string());
+
+        let mut sim = SimulationState::with_seed(100, 1);
+        let mut stage = LoadedStage::new(config);
+        stage.place_fixed_nodes(&mut sim);
+        // 固定ノードの別名もグレード表を引ける
+        assert_eq!(sim.get_node_type_by_index(0), Some(4));
+        assert_eq!(sim.get_node_spec_by_index(0).unwrap().max_concurrent, 50);
+
+        for (name, node_type) in [("waf", 4), ("RateLimiter", 6), ("rate_limiter", 6)] {
+            let id = stage.place_node(&mut sim, name, "basic", 0.0, 0.0).unwrap();
+            let idx = sim.get_node_index(id).unwrap();
+            assert_eq!(sim.get_node_type_by_index(idx), Some(node_type), "{name}");
+        }
+        let names: Vec<&str> = stage
+            .player_nodes
+            .iter()
+            .map(|n| n.node_type.as_str())
+            .collect();
+        assert_eq!(names, vec!["firewall", "rate_limiter", "rate_limiter"]);
+    }
+
+    #[test]
+    fn frame_rate_does_not_change_the_result() {
+        // 60Hz と 144Hz のフレームでも固定ステップで進むので、統計・レイテンシまで一致する
+        for seed in 1..=3 {
+            let (mut slow, stage) = run_with_dt(config(), None, seed, 16.67);
+            let (mut fast, _) = run_with_dt(config(), None, seed, 6.94);
+            assert_eq!(
+                serde_json::to_string(&stage.evaluate(&slow)).unwrap(),
+                serde_json::to_string(&stage.evaluate(&fast)).unwrap(),
+                "seed {seed}"
+            );
+            assert_eq!(
+                serde_json::to_string(&slow.latency_summary()).unwrap(),
+                serde_json::to_string(&fast.latency_summary()).unwrap(),
+                "seed {seed}"
+            );
+            let finished = |sim: &mut SimulationState| {
+                sim.drain_events()
+                    .into_iter()
+                    .find(|e| matches!(e.kind, SimEventKind::StageFinished { .. }))
+                    .map(|e| e.time_ms)
+            };
+            assert_eq!(finished(&mut slow), finished(&mut fast), "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,1793 @@
+// =============================================================================
+// WASM API - JSとのつなぎ込み（wasm_bindgen）、グローバル変数管理
+// `wasm` フィーチャー時のみビルド。GPU描画を伴うAPIは `render` フィーチャーが必要
+// =============================================================================
+
+use crate::decode::{decode_binary_coords, decode_json_packets, json_packets_to_coords};
+use crate::load_balancer::LbStrategy;
+#[cfg(feature = "render")]
+use crate::packet_store::{MotionEvent, MotionUpdate};
+#[cfg(feature = "render")]
+use crate::renderer::{
+    disable_gpu_motion, init_gpu_internal, init_gpu_motion, render_frame_internal,
+    render_packets_gpu, render_simulation_frame_internal, step_gpu_motion, take_gpu_motion_events,
+};
+use crate::replay::{Command, Replay, Session};
+use crate::rng::DEFAULT_SEED;
+use crate::simulation::{CrashPolicy, RejectPolicy, SimulationState};
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::stage::{LoadedStage, PlacementError, StageConfig};
+
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+// =============================================================================
+// GLOBAL STATE - グローバル変数管理
+// =============================================================================
+
+// JavaScriptからRustへ大量のデータを渡す際や、計算結果を一時的に保持するための「使いまわし可能なメモリ領域」
+thread_local! {
+    // JSとRust間でデータをやり取りするための一時的な共有メモリバッファ
+    static PACKET_BUFFER: RefCell<Vec<f32>> = const { RefCell::new(Vec::new()) };
+}
+
+// シミュレーション状態をグローバルに保持（JSから複数回アクセスするため）
+thread_local! {
+    static SIMULATION_STATE: RefCell<Option<SimulationState>> = const { RefCell::new(None) };
+}
+
+// ロード済みステージをグローバルに保持
+thread_local! {
+    static LOADED_STAGE: RefCell<Option<LoadedStage>> = const { RefCell::new(None) };
+}
+
+// 外部からの操作の記録（create_simulation で記録し直す。None = 記録していない）
+thread_local! {
+    static REPLAY_LOG: RefCell<Option<Replay>> = const { RefCell::new(None) };
+}
+
+// ノード描画用の列（位置 [x, y]・見た目 [r, g, b, size]）。毎フレーム確保し直さないよう使い回す
+#[cfg(feature = "render")]
+type NodeEntityColumns = (Vec<[f32; 2]>, Vec<[f32; 4]>);
+
+#[cfg(feature = "render")]
+thread_local! {
+    static NODE_ENTITIES: RefCell<NodeEntityColumns> =
+        const { RefCell::new((Vec::new(), Vec::new())) };
+}
+
+// GPU移動モードの1tick分の作業領域（読み戻したイベント・送る移動状態・ノード位置）
+#[cfg(feature = "render")]
+type MotionScratch = (Vec<MotionEvent>, Vec<MotionUpdate>, Vec<[f32; 2]>);
+
+#[cfg(feature = "render")]
+thread_local! {
+    static MOTION_SCRATCH: RefCell<MotionScratch> =
+        const { RefCell::new((Vec::new(), Vec::new(), Vec::new())) };
+}
+
+// =============================================================================
+// JS INTERFACE - 外部関数宣言
+// =============================================================================
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+
+    #[wasm_bindgen(js_namespace = performance)]
+    fn now() -> f64;
+}
+
+// =============================================================================
+// WASM ENTRY POINT
+// =============================================================================
+
+// Wasmモジュール読み込み時に自動実行されるエントリーポイント
+#[wasm_bindgen(start)]
+pub fn main() {
+    log("[Rust/Wasm] Module initialized!");
+}
+
+// =============================================================================
+// GPU INITIALIZATION API
+// =============================================================================
+
+// JSから呼び出されるWebGPU初期化のエントリーポイント。非同期処理のPromiseを返す
+#[cfg(feature = "render")]
+#[wasm_bindgen]
+pub fn init_gpu(canvas_id: &str) -> JsValue {
+    let canvas_id = canvas_id.to_string();
+    wasm_bindgen_futures::future_to_promise(async move {
+        init_gpu_internal(&canvas_id)
+            .await
+            .map(|_| JsValue::TRUE)
+    })
+    .into()
+}
+
+// =============================================================================
+// RENDERING API
+// =============================================================================
+
+// アニメーションフレームごとに呼び出され、画面を再描画する関数
+#[cfg(feature = "render")]
+#[wasm_bindgen]
+pub fn render_frame() {
+    render_frame_internal();
+}
+
+// =============================================================================
+// PACKET BUFFER API - JSとの共有メモリ管理
+// =============================================================================
+
+// 共有バッファのメモリアドレス（ポインタ）をJSに返す関数
+#[wasm_bindgen]
+pub fn get_packet_buffer_ptr() -> *const f32 {
+    PACKET_BUFFER.with(|buffer| buffer.borrow().as_ptr())
+}
+
+// 共有バッファの現在の長さをJSに返す関数
+#[wasm_bindgen]
+pub fn get_packet_buffer_len() -> usize {
+    PACKET_BUFFER.with(|buffer| buffer.borrow().len())
+}
+
+// 共有バッファのメモリ領域を指定サイズ分確保する関数
+#[wasm_bindgen]
+pub fn allocate_packet_buffer(capacity: usize) {
+    PACKET_BUFFER.with(|buffer| {
+        let mut buf = buffer.borrow_mut();
+        buf.clear();
+        buf.reserve(capacity * 2);
+        log(&format!(
+            "[Rust/Wasm] Allocated packet buffer with capacity for {} packets ({} bytes)",
+            capacity,
+            capacity * 2 * std::mem::size_of::<f32>()
+        ));
+    });
+}
+
+// 共有バッファの内容をクリアする関数
+#[wasm_bindgen]
+pub fn clear_packet_buffer() {
+    PACKET_BUFFER.with(|buffer| {
+        buffer.borrow_mut().clear();
+    });
+}
+
+// バイナリデータからパケット情報を読み取り、共有バッファを更新する関数
+#[wasm_bindgen]
+pub fn update_packet_buffer_from_binary(data: &[u8]) -> usize {
+    PACKET_BUFFER.with(|buffer| {
+        let mut buf = buffer.borrow_mut();
+        buf.clear();
+
+        decode_binary_coords(data, &mut buf)
+    })
+}
+
+// JSON文字列からパケット情報を読み取り、共有バッファを更新する関数
+#[wasm_bindgen]
+pub fn update_packet_buffer_from_json(json_data: &str) -> usize {
+    let packets = match decode_json_packets(json_data) {
+        Ok(p) => p,
+        Err(_) => return 0,
+    };
+
+    PACKET_BUFFER.with(|buffer| {
+        let mut buf = buffer.borrow_mut();
+        buf.clear();
+        json_packets_to_coords(&packets, &mut buf);
+        packets.len()
+    })
+}
+
+// WasmのメモリインスタンスをJSに返す関数
+#[wasm_bindgen]
+pub fn get_memory() -> JsValue {
+    wasm_bindgen::memory()
+}
+
+// =============================================================================
+// MESSAGE HANDLING API - WebSocket等からのメッセージ処理
+// =============================================================================
+
+// JSのconsole.logをRustから使いやすくラップした関数
+#[wasm_bindgen]
+pub fn console_log(message: &str) {
+    log(message);
+}
+
+// WebSocketなどで受信したメッセージ（JSONまたは文字列）を処理し、描画を行う関数
+#[cfg(feature = "render")]
+#[wasm_bindgen]
+pub fn handle_message(message: &str) {
+    let msg_size = message.len();
+    log(&format!(
+        "[Rust/Wasm] Received: {} bytes ({:.2} KB)",
+        msg_size,
+        msg_size as f64 / 1024.0
+    ));
+
+    let start_parse = now();
+    if let Ok(packets) = decode_json_packets(message) {
+        let parse_time = now() - start_parse;
+
+        log(&format!(
+            "[Rust/Wasm] Parsed {} packets in {:.2}ms",
+            packets.len(),
+            parse_time
+        ));
+
+        let start_convert = now();
+        let mut coords: Vec<f32> = Vec::new();
+        json_packets_to_coords(&packets, &mut coords);
+        let convert_time = now() - start_convert;
+
+        let start_draw = now();
+        render_packets_gpu(&coords);
+        let draw_time = now() - start_draw;
+
+        log(&format!(
+            "[Rust/Wasm] Performance: parse={:.2}ms, convert={:.2}ms, draw={:.2}ms, total={:.2}ms",
+            parse_time,
+            convert_time,
+            draw_time,
+            parse_time + convert_time + draw_time
+        ));
+        log(&format!(
+            "[Rust/Wasm] JSON overhead: {:.2} bytes/packet",
+            msg_size as f64 / packets.len() as f64
+        ));
+
+        return;
+    }
+
+    match serde_json::from_str::<crate::decode::JsonPacket>(message) {
+        Ok(packet) => {
+            log(&format!(
+                "[Rust/Wasm] Parsed single Packet: id={}, x={}, y={}",
+                packet.id, packet.x, packet.y
+            ));
+            let coords = vec![packet.x as f32, packet.y as f32];
+            render_packets_gpu(&coords);
+        }
+        Err(_) => {
+            log(&format!("[Rust/Wasm] Plain text: {}", message));
+        }
+    }
+}
+
+// バイナリ形式のパケットデータを受け取り、解析して描画する関数
+#[cfg(feature = "render")]
+#[wasm_bindgen]
+pub fn handle_binary(data: &[u8]) {
+    let mut coords: Vec<f32> = Vec::new();
+    decode_binary_coords(data, &mut coords);
+    render_packets_gpu(&coords);
+}
+
+// =============================================================================
+// SIMULATION API - JSからSimulationStateを操作するためのグローバル関数
+// =============================================================================
+
+/// シミュレーションを初期化
+#[wasm_bindgen]
+pub fn create_simulation(max_packets: usize) {
+    start_recording(Command::CreateSimulation {
+        max_packets,
+        seed: DEFAULT_SEED,
+    });
+    let sim = SimulationState::new(max_packets);
+    // 新しいシミュレーションはCPU移動から始まるので、前のGPU移動モードの資源は捨てる
+    #[cfg(feature = "render")]
+    disable_gpu_motion();
+    SIMULATION_STATE.with(|state| {
+        *state.borrow_mut() = Some(sim);
+    });
+    log(&format!(
+        "[Rust/Wasm] Simulation created with {} max packets",
+        max_packets
+    ));
+}
+
+/// シードを指定してシミュレーションを初期化
+/// 同じステージ・同じシードなら同じ統計結果になる
+#[wasm_bindgen]
+pub fn create_simulation_with_seed(max_packets: usize, seed: u32) {
+    start_recording(Command::CreateSimulation {
+        max_packets,
+        seed: seed as u64,
+    });
+    let sim = SimulationState::with_seed(max_packets, seed as u64);
+    #[cfg(feature = "render")]
+    disable_gpu_motion();
+    SIMULATION_STATE.with(|state| {
+        *state.borrow_mut() = Some(sim);
+    });
+    log(&format!(
+        "[Rust/Wasm] Simulation created with {} max packets (seed={})",
+        max_packets, seed
+    ));
+}
+
+/// シミュレーションにパケット生成予約を追加（座標指定モード）
+/// base_speed / speed_variance の単位はピクセル/ミリ秒
+/// 戻り値: レイテンシ集計用のWave ID（未初期化ならundefined）
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn simulation_spawn_wave(
+    x: f32,
+    y: f32,
+    target_x: f32,
+    target_y: f32,
+    count: usize,
+    duration_ms: f64,
+    base_speed: f32,
+    speed_variance: f32,
+    packet_type: u32,
+    complexity: u8,
+) -> Option<u32> {
+    record(Command::SpawnWave {
+        x,
+        y,
+        target_x,
+        target_y,
+        count,
+        duration_ms,
+        base_speed,
+        speed_variance,
+        packet_type,
+        complexity,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            Some(sim.spawn_wave(
+                x,
+                y,
+                target_x,
+                target_y,
+                count,
+                duration_ms,
+                base_speed,
+                speed_variance,
+                packet_type,
+                complexity,
+            ))
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            None
+        }
+    })
+}
+
+/// シミュレーションにパケット生成予約を追加（ノード指定モード）
+/// base_speed / speed_variance の単位はピクセル/ミリ秒
+/// 戻り値: レイテンシ集計用のWave ID（未初期化ならundefined）
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn simulation_spawn_wave_to_node(
+    x: f32,
+    y: f32,
+    target_node_idx: i32,
+    count: usize,
+    duration_ms: f64,
+    base_speed: f32,
+    speed_variance: f32,
+    packet_type: u32,
+    complexity: u8,
+) -> Option<u32> {
+    record(Command::SpawnWaveToNode {
+        x,
+        y,
+        target_node_idx,
+        count,
+        duration_ms,
+        base_speed,
+        speed_variance,
+        packet_type,
+        complexity,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            Some(sim.spawn_wave_to_node(
+                x,
+                y,
+                target_node_idx,
+                count,
+                duration_ms,
+                base_speed,
+                speed_variance,
+                packet_type,
+                complexity,
+            ))
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            None
+        }
+    })
+}
+
+/// ノードを追加
+#[wasm_bindgen]
+pub fn simulation_add_node(id: u32, x: f32, y: f32, node_type: u32) {
+    record(Command::AddNode {
+        id,
+        x,
+        y,
+        node_type,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.add_node(id, x, y, node_type);
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+        }
+    });
+}
+
+/// すべてのノードをクリア
+#[wasm_bindgen]
+pub fn simulation_clear_nodes() {
+    record(Command::ClearNodes);
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.clear_nodes();
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+        }
+    });
+}
+
+/// LBノードの振り分け戦略を設定
+#[wasm_bindgen]
+pub fn simulation_set_lb_strategy(id: u32, strategy: LbStrategy) -> bool {
+    record(Command::SetLbStrategy {
+        id,
+        strategy: strategy as u32,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_lb_strategy(id, strategy as u32)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// ノードを停止させる（障害訓練用、一定時間後に再起動して復旧）
+#[wasm_bindgen]
+pub fn simulation_crash_node(id: u32) -> bool {
+    record(Command::CrashNode { id });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.crash_node(id)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// ノードを劣化させる（一定時間、処理時間が遅くなる）
+#[wasm_bindgen]
+pub fn simulation_degrade_node(id: u32) -> bool {
+    record(Command::DegradeNode { id });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.degrade_node(id)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// ノードの障害・復旧設定（各状態が続く時間と、停止時のパケットの扱い）を変更
+#[wasm_bindgen]
+pub fn simulation_set_node_health_config(
+    id: u32,
+    degraded_time_ms: f64,
+    down_time_ms: f64,
+    restart_time_ms: f64,
+    crash_policy: CrashPolicy,
+) -> bool {
+    record(Command::SetNodeHealthConfig {
+        id,
+        degraded_time_ms,
+        down_time_ms,
+        restart_time_ms,
+        crash_policy: crash_policy as u32,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_node_health_config(
+                id,
+                degraded_time_ms,
+                down_time_ms,
+                restart_time_ms,
+                crash_policy as u32,
+            )
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// Firewallノードの検知設定（検知率・誤検知率・1秒あたりの検査数）を変更
+#[wasm_bindgen]
+pub fn simulation_set_firewall_config(
+    id: u32,
+    detection_rate: f32,
+    false_positive_rate: f32,
+    throughput_per_sec: f64,
+) -> bool {
+    record(Command::SetFirewallConfig {
+        id,
+        detection_rate,
+        false_positive_rate,
+        throughput_per_sec,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_firewall_config(id, detection_rate, false_positive_rate, throughput_per_sec)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// RateLimiterノードの設定（補充レート・バースト容量・拒否時の扱い・クライアントごとのバケット）を変更
+#[wasm_bindgen]
+pub fn simulation_set_rate_limit_config(
+    id: u32,
+    rate_per_sec: f64,
+    burst: f64,
+    reject_policy: RejectPolicy,
+    per_client: bool,
+) -> bool {
+    record(Command::SetRateLimitConfig {
+        id,
+        rate_per_sec,
+        burst,
+        reject_policy: reject_policy as u32,
+        per_client,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_rate_limit_config(id, rate_per_sec, burst, reject_policy as u32, per_client)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// ノードのキュー規律をJSONで変更
+/// 例: {"discipline":"fifo"} / {"discipline":"red","min_fill":0.3,"max_fill":0.9} / {"discipline":"codel","target_ms":100}
+#[wasm_bindgen]
+pub fn simulation_set_queue_policy(id: u32, policy_json: &str) -> bool {
+    record(Command::SetQueuePolicy {
+        id,
+        policy_json: policy_json.to_string(),
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_queue_policy_json(id, policy_json)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// Cacheノードの設定（キャッシュ可能な割合・TTL）を変更
+#[wasm_bindgen]
+pub fn simulation_set_cache_config(id: u32, hit_ratio: f32, ttl_ms: f64) -> bool {
+    record(Command::SetCacheConfig {
+        id,
+        hit_ratio,
+        ttl_ms,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_cache_config(id, hit_ratio, ttl_ms)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// Cacheノードの中身を捨てる（フラッシュ）
+#[wasm_bindgen]
+pub fn simulation_flush_cache(id: u32) -> bool {
+    record(Command::FlushCache { id });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.flush_cache(id)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// 各ノードの稼働状態を取得（NodeHealth as u32、ノードのインデックス順）
+#[wasm_bindgen]
+pub fn simulation_get_node_health_states() -> Vec<u32> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_node_health_states())
+            .unwrap_or_default()
+    })
+}
+
+/// クライアントのタイムアウト・リトライ方針を設定（timeout_ms = 0 で無効）
+#[wasm_bindgen]
+pub fn simulation_set_retry_policy(
+    timeout_ms: f64,
+    max_retries: u32,
+    backoff_base_ms: f64,
+    backoff_multiplier: f64,
+    backoff_max_ms: f64,
+    jitter: f64,
+) {
+    record(Command::SetRetryPolicy {
+        timeout_ms,
+        max_retries,
+        backoff_base_ms,
+        backoff_multiplier,
+        backoff_max_ms,
+        jitter,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_retry_policy(
+                timeout_ms,
+                max_retries,
+                backoff_base_ms,
+                backoff_multiplier,
+                backoff_max_ms,
+                jitter,
+            );
+        }
+    });
+}
+
+/// クライアントキーの種類数を設定（一貫性ハッシュの振り分けに影響）
+#[wasm_bindgen]
+pub fn simulation_set_client_count(count: u32) {
+    record(Command::SetClientCount { count });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_client_count(count);
+        }
+    });
+}
+
+/// Waveのパケットをすべて同じクライアントキーから送らせる（1クライアントからのスパイク）
+#[wasm_bindgen]
+pub fn simulation_set_wave_client(wave_id: u32, client_id: u32) -> bool {
+    record(Command::SetWaveClient { wave_id, client_id });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_wave_client(wave_id, client_id)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// ノード間リンクを追加（from_id -> to_id）
+#[wasm_bindgen]
+pub fn simulation_add_link(from_id: u32, to_id: u32) -> bool {
+    record(Command::AddLink { from_id, to_id });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.add_link(from_id, to_id)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// リンクの伝送特性を設定（latency_ms: 伝搬遅延、bandwidth: パケットサイズ/秒、loss_rate: 損失率）
+/// すべて0なら従来どおり移動速度で移動する。リンクがなければfalse
+#[wasm_bindgen]
+pub fn simulation_set_link_profile(
+    from_id: u32,
+    to_id: u32,
+    latency_ms: f64,
+    bandwidth: f64,
+    loss_rate: f32,
+) -> bool {
+    record(Command::SetLinkProfile {
+        from_id,
+        to_id,
+        latency_ms,
+        bandwidth,
+        loss_rate,
+    });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_link_profile(from_id, to_id, latency_ms, bandwidth, loss_rate)
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// ノード間リンクを削除
+#[wasm_bindgen]
+pub fn simulation_remove_link(from_id: u32, to_id: u32) -> bool {
+    record(Command::RemoveLink { from_id, to_id });
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow_mut()
+            .as_mut()
+            .map(|sim| sim.remove_link(from_id, to_id))
+            .unwrap_or(false)
+    })
+}
+
+/// すべてのリンクをクリア
+#[wasm_bindgen]
+pub fn simulation_clear_links() {
+    record(Command::ClearLinks);
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.clear_links();
+        }
+    });
+}
+
+/// リンク数を取得
+#[wasm_bindgen]
+pub fn simulation_get_link_count() -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_link_count())
+            .unwrap_or(0)
+    })
+}
+
+/// リンクの状態をJSON文字列で取得
+/// 戻り値: [{"from_id", "to_id", "in_flight", "sent", "delivered", "lost", "utilization", "avg_utilization", "throughput"}, ...]
+#[wasm_bindgen]
+pub fn simulation_get_link_status() -> Option<String> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_link_status_json())
+    })
+}
+
+/// ノード数を取得
+#[wasm_bindgen]
+pub fn simulation_get_node_count() -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_node_count())
+            .unwrap_or(0)
+    })
+}
+
+/// ノードの位置を更新
+#[wasm_bindgen]
+pub fn simulation_update_node_position(id: u32, x: f32, y: f32) {
+    record(Command::UpdateNodePosition { id, x, y });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.update_node_position(id, x, y);
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+        }
+    });
+}
+
+/// テスト用: 指定位置からパケットを生成
+#[wasm_bindgen]
+pub fn simulation_debug_spawn(x: f32, y: f32, count: usize) {
+    record(Command::DebugSpawn { x, y, count });
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.debug_spawn(x, y, count);
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+        }
+    });
+}
+
+/// シミュレーションを1フレーム進める
+/// ステージがロードされていれば、固定ステップごとにWaveを発火させる（ヘッドレス実行と同じ結果になる）
+#[wasm_bindgen]
+pub fn simulation_tick(delta_ms: f64) {
+    record(Command::Tick { delta_ms });
+    LOADED_STAGE.with(|stage| {
+        SIMULATION_STATE.with(|state| {
+            if let Some(sim) = state.borrow_mut().as_mut() {
+                let mut stage = stage.borrow_mut();
+                #[cfg(feature = "render")]
+                if sim.gpu_motion() {
+                    tick_with_gpu_motion(sim, stage.as_mut(), delta_ms);
+                    return;
+                }
+                advance(sim, stage.as_mut(), delta_ms);
+            }
+        });
+    });
+}
+
+/// ステージがあればそれを通して進める
+fn advance(sim: &mut SimulationState, stage: Option<&mut LoadedStage>, delta_ms: f64) {
+    match stage {
+        Some(loaded) => {
+            loaded.tick(sim, delta_ms);
+        }
+        None => sim.tick(delta_ms),
+    }
+}
+
+/// GPU移動モードのtick: 読み戻したイベントを反映してから進め、変わったスロットをGPUへ送って移動させる
+/// GPUにはこのtickで実際に進んだ時間（固定ステップの倍数）だけ移動させる
+#[cfg(feature = "render")]
+fn tick_with_gpu_motion(sim: &mut SimulationState, stage: Option<&mut LoadedStage>, delta_ms: f64) {
+    MOTION_SCRATCH.with(|scratch| {
+        let (events, updates, nodes) = &mut *scratch.borrow_mut();
+        events.clear();
+        take_gpu_motion_events(events);
+        sim.push_motion_events(events);
+        let before_ms = sim.get_current_time();
+        advance(sim, stage, delta_ms);
+        updates.clear();
+        sim.take_motion_updates(updates);
+        sim.node_positions_into(nodes);
+        let advanced_ms = sim.get_current_time() - before_ms;
+        step_gpu_motion(updates, nodes, sim.get_packet_slot_count(), advanced_ms);
+    });
+}
+
+/// パケットの移動をGPU（コンピュートシェーダー）で行うかを切り替える
+/// 有効化にはGPU初期化済みかつコンピュートシェーダー対応のアダプタが必要。失敗時は false
+#[cfg(feature = "render")]
+#[wasm_bindgen]
+pub fn simulation_set_gpu_motion(enabled: bool) -> bool {
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            if enabled {
+                if let Err(e) = init_gpu_motion(sim.get_max_packets()) {
+                    log(&format!("[Rust/Wasm] Error: GPU motion unavailable: {}", e));
+                    return false;
+                }
+            } else {
+                disable_gpu_motion();
+            }
+            sim.set_gpu_motion(enabled);
+            // GPUでの移動はCPUでの再生と一致しないことがあるため、使ったことを記録に残す
+            if enabled {
+                REPLAY_LOG.with(|replay| {
+                    if let Some(replay) = replay.borrow_mut().as_mut() {
+                        replay.gpu_motion = true;
+                    }
+                });
+            }
+            true
+        } else {
+            log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+            false
+        }
+    })
+}
+
+/// アクティブなパケット数を取得
+#[wasm_bindgen]
+pub fn simulation_get_active_count() -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_active_count())
+            .unwrap_or(0)
+    })
+}
+
+/// 負荷率から色を計算（緑→黄→赤のグラデーション）
+#[cfg(feature = "render")]
+fn load_rate_to_color(load_rate: f32) -> (f32, f32, f32) {
+    if load_rate <= 0.0 {
+        // 負荷なし: 緑
+        (0.14, 0.53, 0.21)
+    } else if load_rate <= 0.5 {
+        // 0-50%: 緑 → 黄色
+        let t = load_rate / 0.5;
+        (
+            0.14 + t * (0.94 - 0.14), // 緑 → 黄
+            0.53 + t * (0.73 - 0.53),
+            0.21 + t * (0.09 - 0.21),
+        )
+    } else if load_rate <= 1.0 {
+        // 50-100%: 黄色 → オレンジ
+        let t = (load_rate - 0.5) / 0.5;
+        (
+            0.94 + t * (0.97 - 0.94), // 黄 → オレンジ
+            0.73 - t * (0.73 - 0.32),
+            0.09 - t * 0.09,
+        )
+    } else {
+        // 100%+: 赤
+        (0.97, 0.32, 0.29)
+    }
+}
+
+/// シミュレーションのパケットとノードをWebGPUで描画
+#[cfg(feature = "render")]
+#[wasm_bindgen]
+pub fn render_simulation_frame() {
+    // ノードサイズ（外側=縁、内側=本体）
+    let node_outer_size = 24.0_f32; // 外側（負荷色）
+    let node_inner_size = 16.0_f32; // 内側（タイプ色）
+
+    // ノードタイプごとの色定義
+    // Gateway: 緑, LB: 青, Server: 紫, DB: オレンジ
+    let node_type_colors: [(f32, f32, f32); 7] = [
+        (0.14, 0.53, 0.21), // Gateway: #238636
+        (0.12, 0.43, 0.92), // LB: #1f6feb
+        (0.54, 0.34, 0.90), // Server: #8957e5
+        (0.94, 0.53, 0.24), // DB: #f0883e
+        (0.85, 0.21, 0.20), // Firewall: #da3633
+        (0.22, 0.77, 0.81), // Cache: #39c5cf
+        (0.83, 0.60, 0.13), // RateLimiter: #d29922
+    ];
+
+    // パケットの色・サイズはシミュレーション側の見た目列（packet_store.rs）で決まる
+    NODE_ENTITIES.with(|scratch| {
+        let (positions, appearance) = &mut *scratch.borrow_mut();
+        positions.clear();
+        appearance.clear();
+
+        SIMULATION_STATE.with(|state| {
+            let state = state.borrow();
+            let Some(sim) = state.as_ref() else {
+                render_simulation_frame_internal(&[], &[], &[], &[]);
+                return;
+            };
+            // 各ノードの負荷率と稼働状態を取得
+            let load_rates = sim.get_node_load_rates();
+            let health_states = sim.get_node_health_states();
+
+            // 1. ノードの外側（縁）を追加 - 負荷色
+            for i in 0..sim.get_node_count() {
+                if let Some((x, y)) = sim.get_node_position_by_index(i) {
+                    let node_type = sim.get_node_type_by_index(i).unwrap_or(0);
+
+                    // 負荷率に応じた色（Gatewayは常に緑、停止・再起動中は灰色）
+                    let health = health_states.get(i).copied().unwrap_or(0);
+                    let (r, g, b) = if health == crate::simulation::NodeHealth::Down as u32
+                        || health == crate::simulation::NodeHealth::Restarting as u32
+                    {
+                        (0.35, 0.35, 0.35) // 停止・再起動中: 灰色
+                    } else if node_type == 0 {
+                        (0.14, 0.53, 0.21) // Gateway: 緑
+                    } else {
+                        let load_rate = load_rates.get(i).copied().unwrap_or(0.0);
+                        load_rate_to_color(load_rate)
+                    };
+
+                    positions.push([x, y]);
+                    appearance.push([r, g, b, node_outer_size]);
+                }
+            }
+
+            // 2. ノードの内側を追加 - タイプ色
+            for i in 0..sim.get_node_count() {
+                if let Some((x, y)) = sim.get_node_position_by_index(i) {
+                    let node_type = sim.get_node_type_by_index(i).unwrap_or(0) as usize;
+                    let color_idx = node_type.min(node_type_colors.len() - 1);
+                    let (r, g, b) = node_type_colors[color_idx];
+
+                    positions.push([x, y]);
+                    appearance.push([r, g, b, node_inner_size]);
+                }
+            }
+
+            // 3. パケットはシミュレーションの列をそのまま渡す（非アクティブなスロットはサイズ0で描かれない）
+            let (packet_positions, packet_appearance) = sim.packet_render_columns();
+
+            // GPUで描画
+            render_simulation_frame_internal(positions, appearance, packet_positions, packet_appearance);
+        });
+    });
+}
+
+/// パケット位置列のポインタ（[x, y] × simulation_get_packet_slot_count()、JSからゼロコピーで参照する用）
+/// Wasmメモリが伸びるとビューが無効になるので、毎フレーム取り直すこと
+#[wasm_bindgen]
+pub fn simulation_get_packet_positions_ptr() -> *const f32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_packet_positions_ptr())
+            .unwrap_or(std::ptr::null())
+    })
+}
+
+/// パケットの見た目列のポインタ（[r, g, b, size] × simulation_get_packet_slot_count()、非アクティブはサイズ0）
+#[wasm_bindgen]
+pub fn simulation_get_packet_appearance_ptr() -> *const f32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_packet_appearance_ptr())
+            .unwrap_or(std::ptr::null())
+    })
+}
+
+/// 描画で参照すべきパケットスロット数（これより後ろのスロットは常に非アクティブ）
+#[wasm_bindgen]
+pub fn simulation_get_packet_slot_count() -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_packet_slot_count())
+            .unwrap_or(0)
+    })
+}
+
+// =============================================================================
+// SIMULATION STATS API - 統計情報取得
+// =============================================================================
+
+/// 統計: 生成されたパケット総数を取得
+#[wasm_bindgen]
+pub fn simulation_get_stats_spawned() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_spawned())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: 処理完了したパケット数を取得
+#[wasm_bindgen]
+pub fn simulation_get_stats_processed() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_processed())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: ドロップしたパケット数を取得
+#[wasm_bindgen]
+pub fn simulation_get_stats_dropped() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_dropped())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: 生成された攻撃パケット数（SynFlood / Killer）を取得
+#[wasm_bindgen]
+pub fn simulation_get_stats_attacks_spawned() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_attacks_spawned())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: 応答せずに消えた攻撃パケット数を取得
+#[wasm_bindgen]
+pub fn simulation_get_stats_attacks_absorbed() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_attacks_absorbed())
+            .unwrap_or(0)
+    })
+}
+
+/// オートスケーリンググループの状態をJSON文字列で取得
+/// 戻り値: [{"id", "instances", "draining", "window_load", "cost"}, ...]
+#[wasm_bindgen]
+pub fn simulation_get_autoscaling_status() -> Option<String> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_autoscaling_status_json())
+    })
+}
+
+/// オートスケーリングの累計コスト
+#[wasm_bindgen]
+pub fn simulation_get_autoscaling_cost() -> f64 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_autoscaling_cost())
+            .unwrap_or(0.0)
+    })
+}
+
+/// 統計: Cacheでヒットしたリクエスト数
+#[wasm_bindgen]
+pub fn simulation_get_stats_cache_hits() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_cache_hits())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: Cacheでミスしたリクエスト数
+#[wasm_bindgen]
+pub fn simulation_get_stats_cache_misses() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_cache_misses())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: キューでのドロップ数（原因ごと）をJSONで取得
+/// 例: {"tail":12,"priority":0,"red":30,"codel":0}
+#[wasm_bindgen]
+pub fn simulation_get_queue_drops() -> Option<String> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_queue_drops_json())
+    })
+}
+
+/// 統計: クライアントがタイムアウトした回数（送り直しごと）
+#[wasm_bindgen]
+pub fn simulation_get_stats_timed_out() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_timed_out())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: クライアントが送り直したリクエスト数
+#[wasm_bindgen]
+pub fn simulation_get_stats_retried() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_retried())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: RateLimiterで拒否されたリクエスト数（429応答を含む）
+#[wasm_bindgen]
+pub fn simulation_get_stats_rate_limited() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_rate_limited())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: Firewallで遮断されたパケット数（誤検知を含む）
+#[wasm_bindgen]
+pub fn simulation_get_stats_filtered() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_filtered())
+            .unwrap_or(0)
+    })
+}
+
+/// 統計: Firewallで遮断された攻撃パケット数
+#[wasm_bindgen]
+pub fn simulation_get_stats_attacks_filtered() -> u32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_stats_attacks_filtered())
+            .unwrap_or(0)
+    })
+}
+
+/// レイテンシ: 全体のp50 / p90 / p99 / max（ミリ秒）を [p50, p90, p99, max] で取得
+#[wasm_bindgen]
+pub fn simulation_get_latency_percentiles() -> Vec<f64> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| {
+                vec![
+                    sim.get_latency_p50(),
+                    sim.get_latency_p90(),
+                    sim.get_latency_p99(),
+                    sim.get_latency_max(),
+                ]
+            })
+            .unwrap_or_else(|| vec![0.0; 4])
+    })
+}
+
+/// レイテンシ: 指定Waveのp50 / p90 / p99 / max（ミリ秒）を [p50, p90, p99, max] で取得
+#[wasm_bindgen]
+pub fn simulation_get_wave_latency_percentiles(wave_id: u32) -> Vec<f64> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| {
+                vec![
+                    sim.get_wave_latency_percentile(wave_id, 50.0),
+                    sim.get_wave_latency_percentile(wave_id, 90.0),
+                    sim.get_wave_latency_percentile(wave_id, 99.0),
+                    sim.get_wave_latency_max(wave_id),
+                ]
+            })
+            .unwrap_or_else(|| vec![0.0; 4])
+    })
+}
+
+/// レイテンシレポート（全体・Waveごと）をJSON文字列で取得
+#[wasm_bindgen]
+pub fn simulation_get_latency_report() -> Option<String> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_latency_report_json())
+    })
+}
+
+/// 現在の経過時間（ミリ秒）を取得
+#[wasm_bindgen]
+pub fn simulation_get_current_time() -> f64 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.get_current_time())
+            .unwrap_or(0.0)
+    })
+}
+
+/// シミュレーション全体をリセット
+#[wasm_bindgen]
+pub fn simulation_reset() {
+    record(Command::Reset);
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.reset();
+        }
+    });
+}
+
+/// 指定インデックスのノード位置を取得（x, y）、見つからない場合は(-1, -1)
+#[wasm_bindgen]
+pub fn simulation_get_node_position(index: usize) -> Vec<f32> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .and_then(|sim| sim.get_node_position_by_index(index))
+            .map(|(x, y)| vec![x, y])
+            .unwrap_or_else(|| vec![-1.0, -1.0])
+    })
+}
+
+// =============================================================================
+// EVENT API - シミュレーションイベント（ドロップの原因・ノードの飽和・Wave・ステージ終了）
+// =============================================================================
+
+/// 記録したイベントを古い順にすべて取り出してJSON配列で返す（未初期化ならundefined）
+/// 各要素: {"time_ms", "kind", ...}。毎フレーム呼ぶこと（溜まりすぎた分は古いものから捨てる）
+#[wasm_bindgen]
+pub fn simulation_drain_events() -> Option<String> {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow_mut()
+            .as_mut()
+            .map(|sim| serde_json::to_string(&sim.drain_events()).unwrap_or_default())
+    })
+}
+
+// =============================================================================
+// NODE METRICS API - ノードごとの時系列メトリクス（リングバッファをゼロコピーで参照）
+// =============================================================================
+
+/// 記録間隔（ミリ秒）とサンプル数を変更（全ノードの記録済みサンプルは消える）
+/// 結果には影響しないため、リプレイには記録しない
+#[wasm_bindgen]
+pub fn simulation_set_metrics_config(interval_ms: f64, capacity: usize) {
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            sim.set_metrics_config(interval_ms, capacity);
+        }
+    });
+}
+
+/// 記録間隔（ミリ秒）
+#[wasm_bindgen]
+pub fn simulation_get_metrics_interval() -> f64 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.metrics_config().interval_ms)
+            .unwrap_or(0.0)
+    })
+}
+
+/// リングバッファのサンプル数（1サンプル = NODE_METRIC_FIELDS 個の f32）
+#[wasm_bindgen]
+pub fn simulation_get_metrics_capacity() -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|sim| sim.metrics_config().capacity)
+            .unwrap_or(0)
+    })
+}
+
+/// 指定インデックスのノードのリングバッファのポインタ
+/// [time, throughput, queue_depth, utilization, drops, avg_service_ms] × capacity（未記録ならnull）
+/// Wasmメモリが伸びるとビューが無効になるので、読むたびに取り直すこと
+#[wasm_bindgen]
+pub fn simulation_get_node_metrics_ptr(index: usize) -> *const f32 {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .and_then(|sim| sim.node_metrics(index))
+            .map(|metrics| metrics.as_ptr())
+            .unwrap_or(std::ptr::null())
+    })
+}
+
+/// 指定インデックスのノードで次に書き込むサンプル位置（満杯なら最も古いサンプル）
+#[wasm_bindgen]
+pub fn simulation_get_node_metrics_head(index: usize) -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .and_then(|sim| sim.node_metrics(index))
+            .map(|metrics| metrics.head())
+            .unwrap_or(0)
+    })
+}
+
+/// 指定インデックスのノードの記録済みサンプル数（capacityで頭打ち）
+#[wasm_bindgen]
+pub fn simulation_get_node_metrics_count(index: usize) -> usize {
+    SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .and_then(|sim| sim.node_metrics(index))
+            .map(|metrics| metrics.count())
+            .unwrap_or(0)
+    })
+}
+
+// =============================================================================
+// STAGE CONFIG API - ステージ設定のロード・管理
+// =============================================================================
+
+/// ステージ設定JSONをパースしてロード
+/// 固定ノードをシミュレーションに配置し、Wave情報を保持
+#[wasm_bindgen]
+pub fn load_stage_config(json_str: &str) -> bool {
+    // JSONをパース
+    let config: StageConfig = match serde_json::from_str(json_str) {
+        Ok(c) => c,
+        Err(e) => {
+            log(&format!("[Rust/Wasm] Failed to parse stage config: {}", e));
+            return false;
+        }
+    };
+
+    log(&format!(
+        "[Rust/Wasm] Loading stage: {} (budget={}, sla_target={})",
+        config.meta.title, config.meta.budget, config.meta.sla_target
+    ));
+    record(Command::LoadStage {
+        config: Box::new(config.clone()),
+    });
+
+    // 固定ノードとリンクを配置
+    let mut loaded_stage = LoadedStage::new(config);
+    SIMULATION_STATE.with(|state| {
+        if let Some(sim) = state.borrow_mut().as_mut() {
+            loaded_stage.place_fixed_nodes(sim);
+        }
+    });
+
+    LOADED_STAGE.with(|stage| {
+        *stage.borrow_mut() = Some(loaded_stage);
+    });
+
+    true
+}
+
+/// ロード済みステージのメタ情報を取得（JSON文字列で返す）
+#[wasm_bindgen]
+pub fn get_stage_meta() -> Option<String> {
+    LOADED_STAGE.with(|stage| {
+        stage
+            .borrow()
+            .as_ref()
+            .map(|s| serde_json::to_string(&s.config.meta).unwrap_or_default())
+    })
+}
+
+/// ロード済みステージの予算を取得
+#[wasm_bindgen]
+pub fn get_stage_budget() -> u32 {
+    LOADED_STAGE.with(|stage| {
+        stage
+            .borrow()
+            .as_ref()
+            .map(|s| s.config.meta.budget)
+            .unwrap_or(0)
+    })
+}
+
+/// ロード済みステージのSLAターゲットを取得
+#[wasm_bindgen]
+pub fn get_stage_sla_target() -> f64 {
+    LOADED_STAGE.with(|stage| {
+        stage
+            .borrow()
+            .as_ref()
+            .map(|s| s.config.meta.sla_target)
+            .unwrap_or(0.0)
+    })
+}
+
+/// 指定した時刻までのWaveを発火させる
+/// シミュレーション開始後、current_timeに応じて呼び出す
+#[wasm_bindgen]
+pub fn trigger_waves_until(current_time_ms: u32) {
+    record(Command::TriggerWavesUntil {
+        time_ms: current_time_ms,
+    });
+    LOADED_STAGE.with(|stage| {
+        SIMULATION_STATE.with(|state| {
+            if let (Some(loaded), Some(sim)) =
+                (stage.borrow_mut().as_mut(), state.borrow_mut().as_mut())
+            {
+                loaded.trigger_waves_until(sim, current_time_ms);
+            }
+        });
+    });
+}
+
+/// 残りのWave数を取得
+#[wasm_bindgen]
+pub fn get_pending_wave_count() -> usize {
+    LOADED_STAGE.with(|stage| {
+        stage
+            .borrow()
+            .as_ref()
+            .map(|s| s.pending_waves.len())
+            .unwrap_or(0)
+    })
+}
+
+/// ステージ結果（SLA・予算・合否）をJSON文字列で取得
+/// ステージまたはシミュレーションが未初期化ならundefined
+#[wasm_bindgen]
+pub fn get_stage_result() -> Option<String> {
+    LOADED_STAGE.with(|stage| {
+        SIMULATION_STATE.with(|state| {
+            let stage = stage.borrow();
+            let state = state.borrow();
+            let result = stage.as_ref()?.evaluate(state.as_ref()?);
+            serde_json::to_string(&result).ok()
+        })
+    })
+}
+
+/// ステージをリセット（Waveを再ロード）
+#[wasm_bindgen]
+pub fn reset_stage_waves() {
+    record(Command::ResetStageWaves);
+    LOADED_STAGE.with(|stage| {
+        if let Some(loaded) = stage.borrow_mut().as_mut() {
+            loaded.reset_waves();
+        }
+    });
+}
+
+// =============================================================================
+// PLACEMENT API - プレイヤーによるノード配置（予算管理）
+// =============================================================================
+
+/// 配置系APIの結果をJSON文字列にする
+/// 成功: {"ok": true, ...value, "remaining_budget": n}、失敗: {"ok": false, "error": {"kind": ...}}
+fn placement_response(
+    result: Result<serde_json::Value, PlacementError>,
+    remaining_budget: u32,
+) -> String {
+    let response = match result {
+        Ok(mut value) => {
+            value["ok"] = true.into();
+            value["remaining_budget"] = remaining_budget.into();
+            value
+        }
+        Err(err) => {
+            log(&format!("[Rust/Wasm] Placement failed: {}", err));
+            serde_json::json!({ "ok": false, "error": err })
+        }
+    };
+    response.to_string()
+}
+
+/// ステージとシミュレーションの両方を可変で借りて配置系の処理を行う
+fn with_stage_and_sim(
+    f: impl FnOnce(&mut LoadedStage, &mut SimulationState) -> Result<serde_json::Value, PlacementError>,
+) -> String {
+    LOADED_STAGE.with(|stage| {
+        SIMULATION_STATE.with(|state| {
+            match (stage.borrow_mut().as_mut(), state.borrow_mut().as_mut()) {
+                (Some(loaded), Some(sim)) => {
+                    let result = f(loaded, sim);
+                    placement_response(result, loaded.remaining_budget(sim))
+                }
+                _ => placement_response(Err(PlacementError::StageNotLoaded), 0),
+            }
+        })
+    })
+}
+
+/// グレードを指定してノードを購入・配置
+/// 戻り値: JSON文字列 {"ok": true, "node_id", "cost", "remaining_budget"} またはエラー
+#[wasm_bindgen]
+pub fn place_node(node_type: &str, grade: &str, x: f32, y: f32) -> String {
+    record(Command::PlaceNode {
+        node_type: node_type.to_string(),
+        grade: grade.to_string(),
+        x,
+        y,
+    });
+    with_stage_and_sim(|loaded, sim| {
+        let id = loaded.place_node(sim, node_type, grade, x, y)?;
+        let cost = loaded.player_nodes.last().map_or(0, |n| n.cost);
+        Ok(serde_json::json!({ "node_id": id, "cost": cost }))
+    })
+}
+
+/// プレイヤーが配置したノードを削除（コストは払い戻し）
+/// 戻り値: JSON文字列 {"ok": true, "refund", "remaining_budget"} またはエラー
+#[wasm_bindgen]
+pub fn remove_node(id: u32) -> String {
+    record(Command::RemoveNode { id });
+    with_stage_and_sim(|loaded, sim| {
+        let refund = loaded.remove_node(sim, id)?;
+        Ok(serde_json::json!({ "refund": refund }))
+    })
+}
+
+/// プレイヤーが配置したノードのグレードを変更（差額を支払う）
+/// 戻り値: JSON文字列 {"ok": true, "cost", "remaining_budget"} またはエラー
+#[wasm_bindgen]
+pub fn upgrade_node(id: u32, grade: &str) -> String {
+    record(Command::UpgradeNode {
+        id,
+        grade: grade.to_string(),
+    });
+    with_stage_and_sim(|loaded, sim| {
+        let cost = loaded.upgrade_node(sim, id, grade)?;
+        Ok(serde_json::json!({ "cost": cost }))
+    })
+}
+
+/// 残り予算を取得（オートスケーリングの課金を含む）
+#[wasm_bindgen]
+pub fn get_stage_remaining_budget() -> u32 {
+    LOADED_STAGE.with(|stage| {
+        SIMULATION_STATE.with(|state| {
+            match (stage.borrow().as_ref(), state.borrow().as_ref()) {
+                (Some(loaded), Some(sim)) => loaded.remaining_budget(sim),
+                _ => 0,
+            }
+        })
+    })
+}
+
+/// プレイヤーが配置したノード一覧をJSON文字列で取得
+#[wasm_bindgen]
+pub fn get_placed_nodes() -> Option<String> {
+    LOADED_STAGE.with(|stage| {
+        stage
+            .borrow()
+            .as_ref()
+            .and_then(|s| serde_json::to_string(&s.player_nodes).ok())
+    })
+}
+
+// =============================================================================
+// SNAPSHOT API - シミュレーション状態の保存・復元
+// =============================================================================
+
+/// シミュレーションとロード済みステージの状態を書き出す
+fn export_with<T>(
+    encode: impl FnOnce(&SimulationState, Option<&LoadedStage>) -> Result<T, SnapshotError>,
+) -> Option<T> {
+    LOADED_STAGE.with(|stage| {
+        SIMULATION_STATE.with(|state| {
+            let state = state.borrow();
+            let Some(sim) = state.as_ref() else {
+                log("[Rust/Wasm] Error: Simulation not initialized. Call create_simulation first.");
+                return None;
+            };
+            match encode(sim, stage.borrow().as_ref()) {
+                Ok(encoded) => Some(encoded),
+                Err(e) => {
+                    log(&format!("[Rust/Wasm] Snapshot export failed: {}", e));
+                    None
+                }
+            }
+        })
+    })
+}
+
+/// 復元した状態でシミュレーションとステージを置き換える
+/// スナップショットにステージがなければ、ロード済みのステージも外す
+fn import_with(restored: Result<Snapshot, SnapshotError>) -> bool {
+    let snapshot = match restored {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            log(&format!("[Rust/Wasm] Snapshot import failed: {}", e));
+            return false;
+        }
+    };
+    // 復元した状態はCPU移動から始まるので、GPU移動モードの資源は捨てる
+    #[cfg(feature = "render")]
+    disable_gpu_motion();
+    // 記録は create_simulation からの操作なので、復元した状態からは再生できない
+    REPLAY_LOG.with(|replay| *replay.borrow_mut() = None);
+    log(&format!(
+        "[Rust/Wasm] Snapshot restored at {}ms ({} active packets, stage={})",
+        snapshot.state.get_current_time(),
+        snapshot.state.get_active_count(),
+        snapshot.stage.is_some()
+    ));
+    SIMULATION_STATE.with(|state| {
+        *state.borrow_mut() = Some(snapshot.state);
+    });
+    LOADED_STAGE.with(|stage| {
+        *stage.borrow_mut() = snapshot.stage;
+    });
+    true
+}
+
+/// 状態をバイナリ形式で書き出す（Uint8Array、未初期化ならundefined）
+/// 同じシードのまま復元すれば、書き出した時点から同じ結果で再開できる
+#[wasm_bindgen]
+pub fn simulation_export_snapshot() -> Option<Vec<u8>> {
+    export_with(Snapshot::encode)
+}
+
+/// 状態をJSON形式で書き出す（デバッグ用、未初期化ならundefined）
+#[wasm_bindgen]
+pub fn simulation_export_snapshot_json() -> Option<String> {
+    export_with(Snapshot::to_json)
+}
+
+/// バイナリ形式のスナップショットから復元（シミュレーションとステージを置き換える）
+#[wasm_bindgen]
+pub fn simulation_import_snapshot(data: &[u8]) -> bool {
+    import_with(Snapshot::decode(data))
+}
+
+/// JSON形式のスナップショットから復元
+#[wasm_bindgen]
+pub fn simulation_import_snapshot_json(json: &str) -> bool {
+    import_with(Snapshot::from_json(json))
+}
+
+// =============================================================================
+// REPLAY API - 外部からの操作の記録・再生
+// =============================================================================
+
+/// 操作を、受けた時点のシミュレーション時刻とともに記録する（記録中のみ）
+fn record(command: Command) {
+    let time_ms = SIMULATION_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map_or(0.0, |sim| sim.get_current_time())
+    });
+    REPLAY_LOG.with(|replay| {
+        if let Some(replay) = replay.borrow_mut().as_mut() {
+            replay.record(time_ms, command);
+        }
+    });
+}
+
+/// 記録をやり直す（シミュレーションの作成から記録する）
+fn start_recording(command: Command) {
+    let mut replay = Replay::default();
+    replay.record(0.0, command);
+    REPLAY_LOG.with(|log| *log.borrow_mut() = Some(replay));
+}
+
+/// create_simulation からの操作の記録をJSON文字列で取得（記録していなければundefined）
+/// 戻り値: {"version", "gpu_motion", "commands": [{"at_ms", "cmd", ...}, ...]}
+#[wasm_bindgen]
+pub fn simulation_export_replay() -> Option<String> {
+    REPLAY_LOG.with(|replay| replay.borrow().as_ref().map(Replay::to_json))
+}
+
+/// 記録を再生し、最後の状態でシミュレーションとステージを置き換える（記録はこの続きから取る）
+/// 読めない・再生が記録と食い違った場合は false で、今の状態はそのまま
+#[wasm_bindgen]
+pub fn simulation_load_replay(json: &str) -> bool {
+    let result = Replay::from_json(json).and_then(|replay| Ok((replay.run()?, replay)));
+    let (Session { sim, stage }, replay) = match result {
+        Ok(run) => run,
+        Err(e) => {
+            log(&format!("[Rust/Wasm] Replay failed: {}", e));
+            return false;
+        }
+    };
+    #[cfg(feature = "render")]
+    disable_gpu_motion();
+    log(&format!(
+        "[Rust/Wasm] Replayed {} commands (time={}ms)",
+        replay.commands.len(),
+        sim.as_ref().map_or(0.0, |sim| sim.get_current_time())
+    ));
+    SIMULATION_STATE.with(|state| *state.borrow_mut() = sim);
+    LOADED_STAGE.with(|loaded| *loaded.borrow_mut() = stage);
+    REPLAY_LOG.with(|log| *log.borrow_mut() = Some(replay));
+    true
+}